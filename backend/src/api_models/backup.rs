@@ -13,6 +13,9 @@ pub struct BackupRequest {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RestoreBackupRequest {
+    /// The user who started the task, recorded as its owner. Tasks without it have no owner.
+    #[serde(default)]
+    pub username: Option<String>,
     pub backup_id: String,
 }
 
@@ -8,6 +8,31 @@ pub struct RetrieveTaskResultRequest {
     pub task_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeTaskUpdatesQuery {
+    /// Only the updates of the tasks started by this user are pushed
+    pub username: String,
+    /// Only push the updates of this task. Leave it empty to receive the updates of all the tasks of the user.
+    pub task_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTasksRequest {
+    pub username: String,
+    /// Only list the tasks in this status
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+    /// Only list the runs of this scheduled job
+    #[serde(default)]
+    pub scheduled_job_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelTaskRequest {
+    pub username: String,
+    pub task_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenericResponse {
     task_id: String,
@@ -35,6 +60,25 @@ impl GenericResponse {
         }
     }
 
+    /// The task is waiting for a worker
+    pub fn queued(task_id: String, queue_position: usize) -> Self {
+        Self {
+            task_id,
+            status: TaskStatus::InProgress,
+            message: Some(format!("queued at position {}", queue_position)),
+            data: None,
+        }
+    }
+
+    pub fn cancelled(task_id: String) -> Self {
+        Self {
+            task_id,
+            status: TaskStatus::Cancelled,
+            message: Some("cancelled".to_string()),
+            data: None,
+        }
+    }
+
     pub fn fail(task_id: String, message: String) -> Self {
         Self {
             task_id,
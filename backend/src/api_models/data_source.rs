@@ -0,0 +1,57 @@
+//! It defines the API requests and response models of data sources
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{connectors::requests::ImportType, data_sources::data_source::DataSource};
+
+/// region: requests
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateDataSourceRequest {
+    pub username: String,
+    pub name: String,
+    pub import_type: ImportType,
+    /// Same as the artifact of an import. Secret fields will be encrypted.
+    pub artifact: Value,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteDataSourceRequest {
+    pub username: String,
+    pub data_source_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetDataSourcesRequest {
+    pub username: String,
+}
+
+/// region: responses
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateDataSourceResponse {
+    pub data_source_id: String,
+}
+
+/// A data source with its secrets masked
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DataSourceResponse {
+    pub id: String,
+    pub name: String,
+    pub import_type: ImportType,
+    pub artifact: Value,
+    pub created_at: String,
+}
+
+impl From<&DataSource> for DataSourceResponse {
+    fn from(value: &DataSource) -> Self {
+        Self {
+            id: value.id.clone(),
+            name: value.name.clone(),
+            import_type: value.import_type.clone(),
+            artifact: value.get_masked_artifact(),
+            created_at: value.created_at.clone(),
+        }
+    }
+}
@@ -2,8 +2,9 @@
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::documents::document_metadata::DocumentMetadata;
+use crate::{documents::document_metadata::DocumentMetadata, embedder::VectorizationStatistics};
 
 /// region: request
 
@@ -17,6 +18,9 @@ pub struct AddDocumentRequest {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DeleteDocumentRequest {
+    /// The user who started the task, recorded as its owner. Tasks without it have no owner.
+    #[serde(default)]
+    pub username: Option<String>,
     pub document_metadata_id: String,
 }
 
@@ -45,16 +49,42 @@ pub struct ReindexRequest {
     pub username: String,
 }
 
+/// Who is refreshing the documents, and the secrets to fetch the sources with
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefreshCredentials {
+    pub username: String,
+    /// Secrets that are not kept in the provenance, like a database password.
+    /// Not needed for documents that were imported with a data source.
+    #[serde(default)]
+    pub secrets: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefreshDocumentRequest {
+    pub document_metadata_id: String,
+    #[serde(flatten)]
+    pub credentials: RefreshCredentials,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefreshCollectionRequest {
+    pub collection_metadata_id: String,
+    #[serde(flatten)]
+    pub credentials: RefreshCredentials,
+}
+
 /// region: response
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReindexResponse {
     pub documents_reindexed: usize,
+    pub vectorization_statistics: VectorizationStatistics,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AddDocumentResponse {
     pub document_metadata_id: String,
+    pub vectorization_statistics: VectorizationStatistics,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -65,6 +95,19 @@ pub struct DeleteDocumentResponse {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UpdateDocumentResponse {
     pub document_metadata_id: String,
+    pub vectorization_statistics: VectorizationStatistics,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RefreshDocumentsResponse {
+    /// Documents that were re-embedded with new content
+    pub refreshed_document_metadata_ids: Vec<String>,
+    /// Documents whose sources have not changed
+    pub unchanged_document_metadata_ids: Vec<String>,
+    /// Documents that were uploaded, or added by users
+    pub skipped_document_metadata_ids: Vec<String>,
+    pub failed_document_metadata_ids: Vec<String>,
+    pub vectorization_statistics: VectorizationStatistics,
 }
 
 /// region: query
@@ -1,7 +1,10 @@
 pub mod backup;
 pub mod callbacks;
 pub mod collection;
+pub mod data_source;
 pub mod document;
 pub mod general;
+pub mod scheduled_job;
 pub mod search;
+pub mod search_history;
 pub mod user;
@@ -0,0 +1,35 @@
+//! It defines the API requests and response models of scheduled jobs
+
+use serde::{Deserialize, Serialize};
+
+use crate::scheduled_jobs::scheduled_job::ScheduledJobKind;
+
+/// region: requests
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateScheduledJobRequest {
+    pub username: String,
+    pub name: String,
+    /// Cron expression in UTC, like `0 3 * * *`
+    pub cron_expression: String,
+    #[serde(flatten)]
+    pub kind: ScheduledJobKind,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteScheduledJobRequest {
+    pub username: String,
+    pub scheduled_job_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetScheduledJobsRequest {
+    pub username: String,
+}
+
+/// region: responses
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateScheduledJobResponse {
+    pub scheduled_job_id: String,
+}
@@ -1,8 +1,10 @@
 //! It defines request and response API models for search
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::search::SearchScopeIndicator;
+use crate::search::{SearchFilter, SearchScopeIndicator, ask::Citation};
 
 /// region: request
 
@@ -11,4 +13,136 @@ pub struct SearchDocumentRequest {
     pub query: String,
     pub top_n: usize,
     pub scope: SearchScopeIndicator,
+    /// Only search the documents whose attributes match all of these
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    /// Narrows the documents down within the scope
+    #[serde(default)]
+    pub filter: SearchFilter,
+    /// Group the matching chunks by their documents. `top_n` then counts documents.
+    #[serde(default)]
+    pub group_by_document: Option<DocumentGroupingOptions>,
+    /// Results to skip, for pagination. They are documents when grouping by document.
+    #[serde(default)]
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DocumentGroupingOptions {
+    /// Best matching chunks returned for each document. It must be at least 1.
+    #[serde(deserialize_with = "deserialize_chunks_per_document")]
+    pub chunks_per_document: usize,
+}
+
+fn deserialize_chunks_per_document<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let chunks_per_document: usize = usize::deserialize(deserializer)?;
+    if chunks_per_document < 1 {
+        return Err(serde::de::Error::custom(
+            "`chunks_per_document` must be at least 1",
+        ));
+    }
+
+    Ok(chunks_per_document)
+}
+
+impl Default for DocumentGroupingOptions {
+    fn default() -> Self {
+        Self {
+            chunks_per_document: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RelatedDocumentsRequest {
+    /// Related documents are searched in the userspace of this user
+    pub username: String,
+    pub document_metadata_id: String,
+    pub top_n: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AskRequest {
+    /// The question to answer from the notes
+    pub query: String,
+    pub scope: SearchScopeIndicator,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    #[serde(default)]
+    pub filter: SearchFilter,
+    /// Chunks given to the chat model. It defaults to `context_chunks` of the LLM configurations.
+    #[serde(default)]
+    pub top_n: Option<usize>,
+    /// Stream the answer as Server-Sent Events
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// region: response
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResultsPage<T> {
+    pub results: Vec<T>,
+    /// Approximate number of all the hits. They are documents when grouping by document.
+    pub total_hits: usize,
+    /// Pass it as `offset` to load the next page. It is empty on the last page.
+    pub next_offset: Option<usize>,
+}
+
+impl<T> SearchResultsPage<T> {
+    pub fn new(results: Vec<T>, offset: usize, top_n: usize, total_hits: usize) -> Self {
+        // The total is approximate, so a page that is not full is taken as the last one
+        let next_offset: Option<usize> =
+            (top_n > 0 && results.len() >= top_n && offset.saturating_add(top_n) < total_hits)
+                .then_some(offset.saturating_add(top_n));
+
+        Self {
+            results,
+            total_hits,
+            next_offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskResponse {
+    /// It cites the sources by their numbers, like `[1]`
+    pub answer: String,
+    /// Sources given to the chat model
+    pub citations: Vec<Citation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_offset() {
+        let page = SearchResultsPage::new(vec![1, 2], 0, 2, 5);
+        assert_eq!(page.next_offset, Some(2));
+
+        // The last page, either by the total or by not being full
+        let page = SearchResultsPage::new(vec![5], 4, 2, 5);
+        assert_eq!(page.next_offset, None);
+        let page = SearchResultsPage::new(vec![3], 2, 2, 10);
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[test]
+    fn test_document_grouping_options() {
+        let options: DocumentGroupingOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(options.chunks_per_document, 3);
+        let options: DocumentGroupingOptions =
+            serde_json::from_str(r#"{"chunks_per_document": 1}"#).unwrap();
+        assert_eq!(options.chunks_per_document, 1);
+
+        assert!(
+            serde_json::from_str::<DocumentGroupingOptions>(r#"{"chunks_per_document": 0}"#)
+                .is_err()
+        );
+    }
 }
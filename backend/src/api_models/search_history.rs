@@ -0,0 +1,63 @@
+//! It defines the API requests and response models of search history and saved searches
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api_models::search::SearchDocumentRequest, configurations::search::SupportedSearchMethod,
+};
+
+/// region: requests
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetSearchHistoryRequest {
+    pub username: String,
+    /// How many of the latest searches to list
+    #[serde(default = "default_search_history_limit")]
+    pub limit: usize,
+}
+
+fn default_search_history_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClearSearchHistoryRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateSavedSearchRequest {
+    pub username: String,
+    pub name: String,
+    pub method: SupportedSearchMethod,
+    /// Same as the body of the search endpoint. `offset` is not kept.
+    #[serde(flatten)]
+    pub search: SearchDocumentRequest,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeleteSavedSearchRequest {
+    pub username: String,
+    pub saved_search_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GetSavedSearchesRequest {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RunSavedSearchRequest {
+    pub username: String,
+    pub saved_search_id: String,
+    /// Results to skip, for pagination
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// region: responses
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateSavedSearchResponse {
+    pub saved_search_id: String,
+}
@@ -3,27 +3,40 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::{
-    backup::storage::BackupsStorage, configurations::system::Config, vector_database::{shared::create_vector_database, traits::VectorDatabase}, identities::storage::IdentitiesStorage, metadata_storage::MetadataStorage, tasks_scheduler::TasksScheduler, traits::LoadAndSave
+    backup::storage::BackupsStorage, configurations::system::Config, data_sources::storage::DataSourcesStorage, embedding_cache::EmbeddingCache, vector_database::{shared::create_vector_database, traits::VectorDatabase}, identities::storage::IdentitiesStorage, metadata_storage::MetadataStorage, scheduled_jobs::storage::ScheduledJobsStorage, search_history::storage::SearchHistoryStorage, tasks_queue::TasksQueue, tasks_scheduler::TasksScheduler, traits::LoadAndSave
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Config,
     pub tasks_scheduler: Arc<Mutex<TasksScheduler>>,
+    pub tasks_queue: Arc<TasksQueue>,
     pub database: Arc<dyn VectorDatabase>,
     pub backups_storage: Arc<Mutex<BackupsStorage>>,
     pub metadata_storage: Arc<Mutex<MetadataStorage>>,
     pub identities_storage: Arc<Mutex<IdentitiesStorage>>,
+    pub data_sources_storage: Arc<Mutex<DataSourcesStorage>>,
+    pub scheduled_jobs_storage: Arc<Mutex<ScheduledJobsStorage>>,
+    pub search_history_storage: Arc<Mutex<SearchHistoryStorage>>,
+    pub embedding_cache: Arc<EmbeddingCache>,
 }
 
 impl AppState {
     pub async fn new(config: Config) -> anyhow::Result<Self> {
         let config_clone = config.clone();
-        let vector_database = create_vector_database(&config).await?;
+        let embedding_cache = Arc::new(EmbeddingCache::new(&config.embedding_cache));
+        let vector_database = create_vector_database(&config, embedding_cache.clone()).await?;
+
+        // Rewritten on every task update, so it is the storage most likely to be cut off by a crash
+        let tasks_scheduler = Arc::new(Mutex::new(TasksScheduler::load_or_set_aside(
+            &config_clone.tasks_scheduler.path,
+        )?));
+        let tasks_queue = TasksQueue::new(config.tasks_scheduler.workers, tasks_scheduler.clone());
 
         Ok(Self {
             config,
-            tasks_scheduler: Arc::new(Mutex::new(TasksScheduler::new())),
+            tasks_scheduler,
+            tasks_queue,
             database: vector_database,
             backups_storage: Arc::new(Mutex::new(BackupsStorage::load(
                 &config_clone.backups_storage.path,
@@ -34,6 +47,16 @@ impl AppState {
             identities_storage: Arc::new(Mutex::new(IdentitiesStorage::load(
                 &config_clone.identities_storage.path,
             )?)),
+            data_sources_storage: Arc::new(Mutex::new(DataSourcesStorage::load(
+                &config_clone.data_sources_storage.path,
+            )?)),
+            scheduled_jobs_storage: Arc::new(Mutex::new(ScheduledJobsStorage::load(
+                &config_clone.scheduled_jobs.path,
+            )?)),
+            search_history_storage: Arc::new(Mutex::new(SearchHistoryStorage::load(
+                &config_clone.search_history.path,
+            )?)),
+            embedding_cache,
         })
     }
 }
@@ -5,6 +5,7 @@ use anyhow::{Result, anyhow};
 use crate::{
     app_state::AppState,
     configurations::system::{Config, EmbedderConfig},
+    constants::DOCUMENT_PAYLOADS_VERSION,
     documents::document_chunk::DocumentChunk,
     embedder::send_vectorization,
     traits::LoadAndSave,
@@ -50,17 +51,48 @@ pub async fn handshake_embedding_service(config: &EmbedderConfig) -> Result<()>
 pub async fn align_embedder_model(config: &Config, app_state: &AppState) -> Result<()> {
     let mut metadata_storage = app_state.metadata_storage.lock().await;
 
-    // This means the embedder model has changed
+    // This means the embedder model has changed.
+    // A new document prefix changes the vectors as well, while the query prefix only applies to the searches.
     if metadata_storage.embedder_model_in_use != config.embedder.model
         || metadata_storage.embedder_model_vector_size_in_use != config.embedder.dimensions
+        || metadata_storage.embedder_document_prefix_in_use != config.embedder.get_document_prefix()
     {
         log::info!("Embedder model has changed. Perform re-indexing. please wait...");
         app_state.database.reindex_documents(config).await?;
         log::info!("Re-indexing finished.");
+        // The re-indexed chunks need the document fields again
+        metadata_storage.document_payloads_version = 0;
     }
 
     metadata_storage.embedder_model_in_use = config.embedder.model.clone();
     metadata_storage.embedder_model_vector_size_in_use = config.embedder.dimensions;
+    metadata_storage.embedder_document_prefix_in_use =
+        config.embedder.get_document_prefix().to_string();
+    metadata_storage.save().await?;
+
+    Ok(())
+}
+
+/// Copy the document fields that the search filters use onto the chunks,
+/// if the chunks were written before these fields existed
+pub async fn align_document_payloads(config: &Config, app_state: &AppState) -> Result<()> {
+    let mut metadata_storage = app_state.metadata_storage.lock().await;
+
+    if metadata_storage.document_payloads_version != DOCUMENT_PAYLOADS_VERSION {
+        log::info!("Document payloads are outdated. Updating them, please wait...");
+        let document_metadata_ids: Vec<String> =
+            metadata_storage.documents.keys().cloned().collect();
+        app_state
+            .database
+            .update_document_payloads(
+                &config.database,
+                metadata_storage.get_document_payloads(&document_metadata_ids),
+            )
+            .await?;
+        log::info!("Document payloads updated.");
+    }
+
+    metadata_storage.document_payloads_version = DOCUMENT_PAYLOADS_VERSION;
     metadata_storage.save().await?;
 
     Ok(())
@@ -1,11 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, JsonSchema)]
+/// How the documents are searched. Each method has its own endpoint.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SupportedSearchMethod {
+    /// `search`
     Keyword,
+    /// `intelligent_search`
     Semantic,
+    /// `hybrid_search`
+    Hybrid,
 }
 
 impl Default for SupportedSearchMethod {
@@ -25,6 +30,9 @@ pub struct UserSearchConfiguration {
 
     /// How many search results to get after typing in a search query
     pub top_n: usize,
+
+    /// How much the semantic and the keyword results weigh in hybrid searches
+    pub hybrid_search: HybridSearchConfiguration,
 }
 
 impl Default for UserSearchConfiguration {
@@ -33,6 +41,26 @@ impl Default for UserSearchConfiguration {
             document_chunk_size: 150,
             default_search_method: SupportedSearchMethod::Semantic,
             top_n: 10,
+            hybrid_search: HybridSearchConfiguration::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, JsonSchema)]
+#[serde(default)]
+pub struct HybridSearchConfiguration {
+    /// Weight of the ranks in the semantic (dense vector) results
+    pub dense_weight: f32,
+
+    /// Weight of the ranks in the keyword (BM25 sparse vector) results
+    pub sparse_weight: f32,
+}
+
+impl Default for HybridSearchConfiguration {
+    fn default() -> Self {
+        Self {
+            dense_weight: 1.0,
+            sparse_weight: 1.0,
         }
     }
 }
@@ -6,7 +6,10 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
-use crate::vector_database::traits::VectorDatabaseKind;
+use crate::{
+    constants::MAX_TASK_TTL_SECONDS, scheduled_jobs::scheduled_job::ScheduledJobKind,
+    vector_database::traits::VectorDatabaseKind,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -25,6 +28,31 @@ pub struct Config {
     pub database: DatabaseConfig,
     
     pub embedder: EmbedderConfig,
+
+    /// Chat model that answers questions over the notes.
+    /// Leave it out to disable the `ask` endpoint.
+    #[serde(default)]
+    pub llm: Option<LlmConfig>,
+
+    #[serde(default)]
+    pub data_sources_storage: DataSourcesStorageConfig,
+
+    #[serde(default)]
+    pub tasks_scheduler: TasksSchedulerConfig,
+
+    #[serde(default)]
+    pub scheduled_jobs: ScheduledJobsConfig,
+
+    #[serde(default)]
+    pub search_history: SearchHistoryConfig,
+
+    #[serde(default)]
+    pub embedding_cache: EmbeddingCacheConfig,
+
+    /// Cross-encoder that reorders the semantic search results.
+    /// Leave it out to skip the reranking stage.
+    #[serde(default)]
+    pub reranker: Option<RerankerConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +70,143 @@ pub struct MetadataStorageConfig {
     pub path: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TasksSchedulerConfig {
+    pub path: String,
+
+    /// Finished tasks and their unclaimed results are removed after this many seconds
+    #[serde(default = "default_task_ttl_seconds")]
+    pub ttl_seconds: u64,
+
+    /// How often to look for the expired tasks, in seconds
+    #[serde(default = "default_sweep_interval_seconds")]
+    pub sweep_interval_seconds: u64,
+
+    /// Number of background tasks that run at the same time.
+    /// The rest wait in a queue, where users take turns.
+    #[serde(default = "default_task_workers")]
+    pub workers: usize,
+}
+
+fn default_task_ttl_seconds() -> u64 {
+    // 7 days
+    604800
+}
+
+fn default_sweep_interval_seconds() -> u64 {
+    3600
+}
+
+fn default_task_workers() -> usize {
+    4
+}
+
+impl Default for TasksSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/tasks_scheduler.json".to_string(),
+            ttl_seconds: default_task_ttl_seconds(),
+            sweep_interval_seconds: default_sweep_interval_seconds(),
+            workers: default_task_workers(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJobsConfig {
+    pub path: String,
+
+    /// Jobs that are defined here are recreated on every startup,
+    /// and cannot be removed through the API
+    #[serde(default)]
+    pub jobs: Vec<ScheduledJobConfig>,
+}
+
+impl Default for ScheduledJobsConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/scheduled_jobs.json".to_string(),
+            jobs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchHistoryConfig {
+    pub path: String,
+
+    /// Recent searches kept for each user. The oldest ones are dropped first.
+    pub max_entries_per_user: usize,
+}
+
+impl Default for SearchHistoryConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/search_history.json".to_string(),
+            max_entries_per_user: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingCacheConfig {
+    /// Where the vectors of the document chunks are kept
+    pub path: String,
+
+    /// Query vectors kept in memory. Set it to 0 to disable the cache.
+    pub max_query_entries: usize,
+
+    /// Chunk vectors kept on disk. Set it to 0 to disable the cache.
+    pub max_chunk_entries: usize,
+}
+
+impl Default for EmbeddingCacheConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/embedding_cache.json".to_string(),
+            max_query_entries: 1000,
+            max_chunk_entries: 20000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJobConfig {
+    /// Unique among the jobs of the user
+    pub name: String,
+
+    /// The user that the job runs on behalf of
+    pub username: String,
+
+    /// Cron expression in UTC, like `0 3 * * *` for 3 AM every day
+    pub cron_expression: String,
+
+    #[serde(flatten)]
+    pub kind: ScheduledJobKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourcesStorageConfig {
+    pub path: String,
+
+    /// Secret used for encrypting the credentials of data sources at rest.
+    /// Data sources cannot be created if it is left empty.
+    /// Changing it will make the stored credentials unreadable.
+    #[serde(default)]
+    pub encryption_key: String,
+}
+
+impl Default for DataSourcesStorageConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/data_sources_storage.json".to_string(),
+            encryption_key: String::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbedderConfig {
     /// Provider of the embedding model
@@ -68,6 +233,187 @@ pub struct EmbedderConfig {
     
     /// API key of the model
     pub api_key: String,
+
+    /// Maximum number of vectorization requests sent at the same time,
+    /// shared by all the running tasks
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
+    /// How to retry the failed vectorization requests of document chunks
+    #[serde(default)]
+    pub retry: EmbedderRetryConfig,
+
+    /// Instruction prefixes that the family of the model was trained with.
+    /// `query_prefix` and `document_prefix` take precedence over it.
+    #[serde(default)]
+    pub prefix_preset: Option<EmbedderPrefixPreset>,
+
+    /// Prepended to the search queries before vectorizing them
+    #[serde(default)]
+    pub query_prefix: Option<String>,
+
+    /// Prepended to the document chunks before vectorizing them.
+    /// Changing it re-indexes the documents on the next start.
+    #[serde(default)]
+    pub document_prefix: Option<String>,
+}
+
+impl EmbedderConfig {
+    pub fn get_query_prefix(&self) -> &str {
+        match (&self.query_prefix, self.prefix_preset) {
+            (Some(prefix), _) => prefix,
+            (None, Some(preset)) => preset.get_prefixes().0,
+            (None, None) => "",
+        }
+    }
+
+    pub fn get_document_prefix(&self) -> &str {
+        match (&self.document_prefix, self.prefix_preset) {
+            (Some(prefix), _) => prefix,
+            (None, Some(preset)) => preset.get_prefixes().1,
+            (None, None) => "",
+        }
+    }
+}
+
+fn default_max_concurrent_requests() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedderPrefixPreset {
+    /// `query: ` and `passage: `
+    E5,
+    /// The English retrieval instruction for queries, and nothing for passages
+    Bge,
+    /// `search_query: ` and `search_document: `
+    Nomic,
+}
+
+impl EmbedderPrefixPreset {
+    /// The query prefix and the document prefix
+    pub fn get_prefixes(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::E5 => ("query: ", "passage: "),
+            Self::Bge => (
+                "Represent this sentence for searching relevant passages: ",
+                "",
+            ),
+            Self::Nomic => ("search_query: ", "search_document: "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbedderRetryConfig {
+    /// Retries of a batch after timeouts, rate limits or server errors
+    pub max_retries: usize,
+
+    /// Delay before the first retry. It doubles on each retry, with a random jitter.
+    pub initial_backoff_milliseconds: u64,
+
+    pub max_backoff_milliseconds: u64,
+
+    /// How many times a chunk can be halved when it is too long for the embedding model.
+    /// Vectors of the halves are averaged, so the chunk is kept as a whole.
+    pub max_split_depth: usize,
+}
+
+impl Default for EmbedderRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_milliseconds: 500,
+            max_backoff_milliseconds: 30000,
+            max_split_depth: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    /// Full url of the OpenAI compatible `/chat/completions` endpoint
+    pub base_url: String,
+
+    /// Model name of the chat model
+    pub model: String,
+
+    #[serde(default)]
+    pub api_key: String,
+
+    /// How many of the best matching chunks are given to the model to answer from
+    #[serde(default = "default_llm_context_chunks")]
+    pub context_chunks: usize,
+
+    #[serde(default = "default_llm_temperature")]
+    pub temperature: f32,
+
+    /// Leave it out to let the endpoint decide
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+
+    /// Timeout of the whole answer. Streamed answers may take longer, as long as each read is within it.
+    #[serde(default = "default_llm_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_llm_context_chunks() -> usize {
+    8
+}
+
+fn default_llm_temperature() -> f32 {
+    0.2
+}
+
+fn default_llm_timeout_seconds() -> u64 {
+    120
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RerankerConfig {
+    /// Full url of the `/rerank` endpoint
+    pub base_url: String,
+
+    /// Model name of the reranker. TEI serves a single model and ignores it.
+    #[serde(default)]
+    pub model: String,
+
+    #[serde(default)]
+    pub api_key: String,
+
+    /// Request and response shape of the endpoint
+    #[serde(default)]
+    pub api_format: RerankerApiFormat,
+
+    /// How many semantic search results are sent to the reranker.
+    /// The best `top_n` of them are returned after reranking.
+    #[serde(default = "default_reranker_candidates")]
+    pub candidates: usize,
+
+    #[serde(default = "default_reranker_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankerApiFormat {
+    /// `{query, documents}` in, `{results: [{index, relevance_score}]}` out.
+    /// Used by Cohere, vLLM and Jina.
+    #[default]
+    Cohere,
+    /// `{query, texts}` in, `[{index, score}]` out.
+    /// Used by HuggingFace text-embeddings-inference.
+    Tei,
+}
+
+fn default_reranker_candidates() -> usize {
+    50
+}
+
+fn default_reranker_timeout_seconds() -> u64 {
+    30
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +466,41 @@ impl Config {
             return Err(anyhow::anyhow!("Server port cannot be 0"));
         }
 
+        if self.tasks_scheduler.sweep_interval_seconds == 0 {
+            return Err(anyhow::anyhow!(
+                "Tasks scheduler sweep interval cannot be 0"
+            ));
+        }
+
+        if self.tasks_scheduler.ttl_seconds > MAX_TASK_TTL_SECONDS {
+            return Err(anyhow::anyhow!(
+                "Tasks scheduler ttl cannot be longer than {} seconds",
+                MAX_TASK_TTL_SECONDS
+            ));
+        }
+
+        if self.tasks_scheduler.workers == 0 {
+            return Err(anyhow::anyhow!("Tasks scheduler workers cannot be 0"));
+        }
+
+        if self.embedder.max_concurrent_requests == 0 {
+            return Err(anyhow::anyhow!(
+                "Embedder max concurrent requests cannot be 0"
+            ));
+        }
+
+        if let Some(reranker) = &self.reranker
+            && reranker.candidates == 0
+        {
+            return Err(anyhow::anyhow!("Reranker candidates cannot be 0"));
+        }
+
+        if let Some(llm) = &self.llm
+            && llm.context_chunks == 0
+        {
+            return Err(anyhow::anyhow!("LLM context chunks cannot be 0"));
+        }
+
         if !["trace", "debug", "info", "warn", "error"].contains(&self.logging.level.as_str()) {
             return Err(anyhow::anyhow!(
                 "Invalid logging level: {}",
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use mail_parser::{Address, HeaderValue, Message, MessageParser, mailbox::mbox::MessageIterator};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{models::ImportTaskIntermediate, traits::Connector, webpage::html_to_markdown};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailArtifact {
+    /// Raw content of a mbox file, or a single .eml message
+    pub content: String,
+
+    /// Merge messages of the same thread into one document.
+    /// Threads are resolved with the `In-Reply-To` and `References` headers.
+    #[serde(default)]
+    pub group_by_thread: bool,
+}
+
+/// An email message detached from its raw bytes
+#[derive(Debug, Clone)]
+struct EmailMessage {
+    message_id: Option<String>,
+    /// Message ids from `References`, followed by the ones from `In-Reply-To`
+    parent_ids: Vec<String>,
+    subject: String,
+    from: String,
+    to: String,
+    date: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailConnector;
+
+#[async_trait]
+impl Connector for EmailConnector {
+    async fn get_intermediate(artifact: Value) -> Result<ImportTaskIntermediate> {
+        match Self::get_intermediates(artifact).await?.into_iter().next() {
+            Some(intermediate) => Ok(intermediate),
+            None => Err(anyhow!("No email messages were found in the artifact")),
+        }
+    }
+
+    async fn get_intermediates(artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        let email_artifact: EmailArtifact = serde_json::from_value(artifact)?;
+
+        let messages: Vec<EmailMessage> = parse_messages(&email_artifact.content)?;
+        if messages.is_empty() {
+            return Err(anyhow!("No email messages were found in the artifact"));
+        }
+
+        if email_artifact.group_by_thread {
+            return Ok(group_messages_by_thread(messages)
+                .into_iter()
+                .map(|thread| thread_to_intermediate(&thread))
+                .collect());
+        }
+
+        Ok(messages
+            .iter()
+            .map(|message| thread_to_intermediate(&[message]))
+            .collect())
+    }
+}
+
+/// A mbox file always begins with a `From ` separator line,
+/// otherwise the content is treated as a single .eml message.
+fn parse_messages(content: &str) -> Result<Vec<EmailMessage>> {
+    if !content.starts_with("From ") {
+        return Ok(vec![parse_message(content.as_bytes())?]);
+    }
+
+    let mut messages: Vec<EmailMessage> = Vec::new();
+    for message in MessageIterator::new(content.as_bytes()) {
+        let message = message?;
+        match parse_message(message.contents()) {
+            Ok(result) => messages.push(result),
+            Err(error) => log::warn!("Skipped an unparsable message in mbox: {}", error),
+        }
+    }
+
+    Ok(messages)
+}
+
+fn parse_message(raw: &[u8]) -> Result<EmailMessage> {
+    let message: Message = match MessageParser::default().parse(raw) {
+        Some(result) => result,
+        None => return Err(anyhow!("Failed to parse the email message")),
+    };
+
+    let mut parent_ids: Vec<String> = header_to_ids(message.references());
+    parent_ids.extend(header_to_ids(message.in_reply_to()));
+
+    // Prefer the plain text body, fallback to the html body
+    let plain_text = message
+        .text_bodies()
+        .find(|part| part.is_text() && !part.is_text_html())
+        .and_then(|part| part.text_contents());
+    let body: String = match plain_text {
+        Some(text) => text.to_string(),
+        None => match message
+            .html_bodies()
+            .next()
+            .and_then(|part| part.text_contents())
+        {
+            Some(html) => html_to_markdown(html, false)?,
+            None => String::new(),
+        },
+    };
+
+    Ok(EmailMessage {
+        message_id: message.message_id().map(|id| id.to_string()),
+        parent_ids,
+        subject: message.subject().unwrap_or("(no subject)").to_string(),
+        from: address_to_string(message.from()),
+        to: address_to_string(message.to()),
+        date: message.date().map(|date| date.to_rfc3339()),
+        body,
+    })
+}
+
+fn header_to_ids(value: &HeaderValue) -> Vec<String> {
+    match value {
+        HeaderValue::Text(id) => vec![id.to_string()],
+        HeaderValue::TextList(ids) => ids.iter().map(|id| id.to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn address_to_string(address: Option<&Address>) -> String {
+    let address = match address {
+        Some(result) => result.clone().into_list(),
+        None => return String::new(),
+    };
+
+    address
+        .iter()
+        .map(|addr| match (&addr.name, &addr.address) {
+            (Some(name), Some(address)) => format!("{} <{}>", name, address),
+            (None, Some(address)) => address.to_string(),
+            (Some(name), None) => name.to_string(),
+            (None, None) => String::new(),
+        })
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Group the messages by the root of their threads.
+/// Threads keep the order in which their first message appears.
+fn group_messages_by_thread(messages: Vec<EmailMessage>) -> Vec<Vec<EmailMessage>> {
+    // key-value pair: message id, thread root id
+    let mut roots: HashMap<String, String> = HashMap::new();
+    let mut threads: Vec<(String, Vec<EmailMessage>)> = Vec::new();
+
+    for (index, message) in messages.into_iter().enumerate() {
+        // The first known ancestor decides the thread.
+        // `References` lists the ancestors from the root downwards.
+        let root: String = message
+            .parent_ids
+            .iter()
+            .find_map(|id| roots.get(id).cloned())
+            .or_else(|| message.parent_ids.first().cloned())
+            .or_else(|| message.message_id.clone())
+            .unwrap_or_else(|| format!("message-{}", index));
+
+        if let Some(message_id) = &message.message_id {
+            roots.insert(message_id.clone(), root.clone());
+        }
+
+        match threads.iter_mut().find(|(thread_root, _)| *thread_root == root) {
+            Some((_, thread)) => thread.push(message),
+            None => threads.push((root, vec![message])),
+        }
+    }
+
+    threads.into_iter().map(|(_, thread)| thread).collect()
+}
+
+fn thread_to_intermediate<T: std::borrow::Borrow<EmailMessage>>(
+    thread: &[T],
+) -> ImportTaskIntermediate {
+    let first: &EmailMessage = thread[0].borrow();
+
+    let mut attributes: HashMap<String, String> = HashMap::new();
+    attributes.insert("from".to_string(), first.from.clone());
+    attributes.insert("to".to_string(), first.to.clone());
+    if let Some(date) = &first.date {
+        attributes.insert("date".to_string(), date.clone());
+    }
+    let message_ids: Vec<String> = thread
+        .iter()
+        .filter_map(|message| message.borrow().message_id.clone())
+        .collect();
+    if !message_ids.is_empty() {
+        attributes.insert("message_id".to_string(), message_ids.join(", "));
+    }
+
+    // A single message is kept as is,
+    // a thread has a short header in front of each message.
+    let content: String = if thread.len() == 1 {
+        first.body.clone()
+    } else {
+        thread
+            .iter()
+            .map(|message| {
+                let message: &EmailMessage = message.borrow();
+                format!(
+                    "From: {}\nDate: {}\n\n{}",
+                    message.from,
+                    message.date.as_deref().unwrap_or(""),
+                    message.body.trim_end()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n---\n\n")
+    };
+
+    ImportTaskIntermediate {
+        title: first.subject.clone(),
+        content,
+        attributes,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MBOX: &str = "From alice@example.com Mon Jan  1 00:00:00 2024\n\
+Message-ID: <1@example.com>\n\
+From: Alice <alice@example.com>\n\
+To: bob@example.com\n\
+Subject: Decision on storage\n\
+Date: Mon, 1 Jan 2024 00:00:00 +0000\n\
+\n\
+We go with Qdrant.\n\
+\n\
+From bob@example.com Mon Jan  1 01:00:00 2024\n\
+Message-ID: <2@example.com>\n\
+In-Reply-To: <1@example.com>\n\
+References: <1@example.com>\n\
+From: bob@example.com\n\
+To: Alice <alice@example.com>\n\
+Subject: Re: Decision on storage\n\
+Date: Mon, 1 Jan 2024 01:00:00 +0000\n\
+\n\
+Agreed.\n\
+\n\
+From carol@example.com Mon Jan  1 02:00:00 2024\n\
+Message-ID: <3@example.com>\n\
+From: carol@example.com\n\
+To: bob@example.com\n\
+Subject: Lunch\n\
+Content-Type: text/html\n\
+\n\
+<p>See you at <b>noon</b></p>\n";
+
+    #[test]
+    fn test_parse_single_eml() {
+        let eml = "Message-ID: <1@example.com>\r\n\
+From: Alice <alice@example.com>\r\n\
+To: bob@example.com\r\n\
+Subject: Hello\r\n\
+\r\n\
+Body text\r\n";
+        let messages = parse_messages(eml).unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].subject, "Hello");
+        assert_eq!(messages[0].from, "Alice <alice@example.com>");
+        assert_eq!(messages[0].message_id.as_deref(), Some("1@example.com"));
+        assert!(messages[0].body.contains("Body text"));
+    }
+
+    #[test]
+    fn test_parse_mbox_converts_html() {
+        let messages = parse_messages(MBOX).unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].parent_ids, vec!["1@example.com", "1@example.com"]);
+        assert!(messages[2].body.contains("**noon**"));
+    }
+
+    #[test]
+    fn test_group_messages_by_thread() {
+        let threads = group_messages_by_thread(parse_messages(MBOX).unwrap());
+
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].len(), 2);
+
+        let intermediate = thread_to_intermediate(&threads[0]);
+        assert_eq!(intermediate.title, "Decision on storage");
+        assert_eq!(
+            intermediate.attributes.get("message_id").unwrap(),
+            "1@example.com, 2@example.com"
+        );
+        assert!(intermediate.content.contains("Agreed."));
+    }
+}
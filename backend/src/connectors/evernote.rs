@@ -0,0 +1,168 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use roxmltree::{Document, Node, ParsingOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{models::ImportTaskIntermediate, traits::Connector, webpage::html_to_markdown};
+use crate::utilities::format_unix_timestamp;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvernoteArtifact {
+    /// Raw content of an .enex export
+    pub content: String,
+
+    /// Name of the exported notebook. Notes will be imported into a collection with this title.
+    /// Leave it empty to import into the collection specified by the request.
+    #[serde(default)]
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EvernoteConnector;
+
+#[async_trait]
+impl Connector for EvernoteConnector {
+    async fn get_intermediate(artifact: Value) -> Result<ImportTaskIntermediate> {
+        match Self::get_intermediates(artifact).await?.into_iter().next() {
+            Some(intermediate) => Ok(intermediate),
+            None => Err(anyhow!("No notes were found in the Evernote export")),
+        }
+    }
+
+    async fn get_intermediates(artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        let evernote_artifact: EvernoteArtifact = serde_json::from_value(artifact)?;
+
+        // .enex files come with a DOCTYPE declaration
+        let document = Document::parse_with_options(
+            &evernote_artifact.content,
+            ParsingOptions {
+                allow_dtd: true,
+                ..Default::default()
+            },
+        )?;
+
+        let mut intermediates: Vec<ImportTaskIntermediate> = Vec::new();
+        for note in document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("note"))
+        {
+            let mut intermediate: ImportTaskIntermediate = note_to_intermediate(note)?;
+            intermediate.collection_title = evernote_artifact.notebook.clone();
+            intermediates.push(intermediate);
+        }
+
+        if intermediates.is_empty() {
+            return Err(anyhow!("No notes were found in the Evernote export"));
+        }
+
+        Ok(intermediates)
+    }
+}
+
+fn note_to_intermediate(note: Node) -> Result<ImportTaskIntermediate> {
+    let title: String = child_text(note, "title").unwrap_or_default();
+
+    // ENML is a subset of XHTML wrapped in a `en-note` element
+    let enml: String = child_text(note, "content").unwrap_or_default();
+    let enml: &str = match enml.find("<en-note") {
+        Some(start) => &enml[start..],
+        None => &enml,
+    };
+    let content: String = html_to_markdown(enml, false)?;
+
+    let tags: Vec<String> = note
+        .children()
+        .filter(|node| node.has_tag_name("tag"))
+        .filter_map(|node| node.text())
+        .map(|tag| tag.trim().to_string())
+        .collect();
+
+    // Attachments are referenced with `en-media` in the content, which cannot be imported
+    let unsupported_resources: Vec<String> = note
+        .children()
+        .filter(|node| node.has_tag_name("resource"))
+        .map(|resource| {
+            let mime: String = child_text(resource, "mime").unwrap_or_default();
+            let file_name: Option<String> = resource
+                .children()
+                .find(|node| node.has_tag_name("resource-attributes"))
+                .and_then(|attributes| child_text(attributes, "file-name"));
+
+            format!(
+                "Evernote note `{}`: resource `{}` ({})",
+                title,
+                file_name.unwrap_or("unnamed".to_string()),
+                mime
+            )
+        })
+        .collect();
+
+    Ok(ImportTaskIntermediate {
+        title: title.clone(),
+        content,
+        tags,
+        created_at: child_text(note, "created").and_then(|item| parse_enex_timestamp(&item)),
+        last_modified: child_text(note, "updated").and_then(|item| parse_enex_timestamp(&item)),
+        unsupported_resources,
+        ..Default::default()
+    })
+}
+
+fn child_text(node: Node, tag_name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(tag_name))
+        .and_then(|child| child.text())
+        .map(|text| text.to_string())
+}
+
+/// Timestamps in .enex files look like `20240101T120000Z`
+fn parse_enex_timestamp(timestamp: &str) -> Option<String> {
+    let datetime = NaiveDateTime::parse_from_str(timestamp.trim(), "%Y%m%dT%H%M%SZ").ok()?;
+    format_unix_timestamp(datetime.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_intermediates_from_enex() {
+        let enex = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export3.dtd">
+<en-export export-date="20240102T000000Z" application="Evernote" version="10">
+  <note>
+    <title>Meeting notes</title>
+    <created>20240101T120000Z</created>
+    <updated>20240101T130000Z</updated>
+    <tag>work</tag>
+    <tag>meeting</tag>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd"><en-note><div>Ship the <b>release</b></div><en-media type="image/png" hash="abc"/></en-note>]]></content>
+    <resource>
+      <data encoding="base64">aGVsbG8=</data>
+      <mime>image/png</mime>
+      <resource-attributes><file-name>diagram.png</file-name></resource-attributes>
+    </resource>
+  </note>
+</en-export>"#;
+
+        let intermediates = EvernoteConnector::get_intermediates(serde_json::json!({
+            "content": enex,
+            "notebook": "Work",
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(intermediates.len(), 1);
+        let note = &intermediates[0];
+        assert_eq!(note.title, "Meeting notes");
+        assert!(note.content.contains("**release**"));
+        assert_eq!(note.tags, vec!["work", "meeting"]);
+        assert_eq!(note.collection_title.as_deref(), Some("Work"));
+        assert_eq!(note.created_at, format_unix_timestamp(1704110400));
+        assert_eq!(note.unsupported_resources.len(), 1);
+        assert!(note.unsupported_resources[0].contains("diagram.png"));
+    }
+}
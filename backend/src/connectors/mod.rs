@@ -2,13 +2,23 @@
 //! Currently, we are going to incorporate the following:
 //! 1. Webpage
 //! 2. Text file
+//! 3. Relationship database
+//! 4. Email archive (mbox/EML)
+//! 5. Notion export (Markdown & CSV)
+//! 6. Evernote export (.enex)
+//! 7. Structured data (CSV, JSON Lines)
 
 pub mod models;
+pub mod provenance;
 pub mod requests;
 pub mod responses;
 pub mod traits;
 
+pub mod email;
+pub mod evernote;
+pub mod notion;
 pub mod relationship_database;
+pub mod structured_data;
 pub mod text_file;
 /// Connectors
 pub mod webpage;
@@ -1,7 +1,21 @@
+use std::collections::HashMap;
+
 /// An intermediary state of an import task.
 /// It carries over the title and content of a task.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ImportTaskIntermediate {
     pub title: String,
     pub content: String,
+    /// Source specific attributes, such as email headers.
+    /// They will be kept in the document metadata.
+    pub attributes: HashMap<String, String>,
+    pub tags: Vec<String>,
+    /// Timestamps from the source. Fallback to the import time if they are missing.
+    pub created_at: Option<String>,
+    pub last_modified: Option<String>,
+    /// Title of the collection this document belongs to in the source, like a notebook.
+    /// Leave it empty to import into the collection specified by the request.
+    pub collection_title: Option<String>,
+    /// Embedded resources that cannot be imported, such as images and attachments
+    pub unsupported_resources: Vec<String>,
 }
@@ -0,0 +1,288 @@
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::ZipArchive;
+
+use super::{models::ImportTaskIntermediate, traits::Connector};
+use crate::utilities::format_unix_timestamp;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotionArtifact {
+    /// Base64 encoded zip file, exported from Notion with the `Markdown & CSV` format
+    pub content: String,
+}
+
+/// A markdown page in the export
+#[derive(Debug, Clone)]
+struct NotionPage {
+    /// Title of the directory that contains the page, which maps to a collection
+    parent: Option<String>,
+    title: String,
+    content: String,
+}
+
+/// A row of a database exported as csv
+#[derive(Debug, Clone)]
+struct NotionDatabaseRow {
+    database: String,
+    title: String,
+    properties: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NotionConnector;
+
+#[async_trait]
+impl Connector for NotionConnector {
+    async fn get_intermediate(artifact: Value) -> Result<ImportTaskIntermediate> {
+        match Self::get_intermediates(artifact).await?.into_iter().next() {
+            Some(intermediate) => Ok(intermediate),
+            None => Err(anyhow!("No pages were found in the Notion export")),
+        }
+    }
+
+    async fn get_intermediates(artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        let notion_artifact: NotionArtifact = serde_json::from_value(artifact)?;
+        let bytes: Vec<u8> = STANDARD.decode(notion_artifact.content.trim())?;
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+
+        let mut pages: Vec<NotionPage> = Vec::new();
+        let mut rows: Vec<NotionDatabaseRow> = Vec::new();
+        let mut unsupported_resources: Vec<String> = Vec::new();
+
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if file.is_dir() {
+                continue;
+            }
+
+            let name: String = file.name().to_string();
+            let path: &Path = Path::new(&name);
+            let parent: Option<String> = path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .map(|parent| strip_notion_id(&parent.to_string_lossy()));
+            let stem: String = strip_notion_id(
+                &path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            );
+
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("md") => {
+                    let mut content: String = String::new();
+                    file.read_to_string(&mut content)?;
+
+                    // Pages begin with their titles as the first heading
+                    let title: String = match content.lines().next() {
+                        Some(line) if line.starts_with("# ") => line[2..].trim().to_string(),
+                        _ => stem,
+                    };
+
+                    pages.push(NotionPage {
+                        parent,
+                        title,
+                        content,
+                    });
+                }
+                Some("csv") => {
+                    let mut content: String = String::new();
+                    file.read_to_string(&mut content)?;
+                    rows.extend(parse_database(&stem, &content)?);
+                }
+                _ => unsupported_resources.push(format!("Notion export: file `{}`", name)),
+            }
+        }
+
+        let mut intermediates: Vec<ImportTaskIntermediate> = Vec::new();
+        for page in pages {
+            // Pages of a database carry the properties of their rows
+            let row_index: Option<usize> = rows.iter().position(|row| {
+                Some(&row.database) == page.parent.as_ref() && row.title == page.title
+            });
+            let row: Option<NotionDatabaseRow> = row_index.map(|index| rows.remove(index));
+
+            let mut intermediate = ImportTaskIntermediate {
+                title: page.title,
+                content: page.content,
+                collection_title: page.parent,
+                ..Default::default()
+            };
+            if let Some(row) = row {
+                apply_properties(&mut intermediate, row.properties);
+            }
+
+            intermediates.push(intermediate);
+        }
+
+        // Rows without a page are imported with their properties as the content
+        for row in rows {
+            let mut intermediate = ImportTaskIntermediate {
+                title: row.title,
+                content: row
+                    .properties
+                    .iter()
+                    .map(|(key, value)| format!("**{}**: {}", key, value))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                collection_title: Some(row.database),
+                ..Default::default()
+            };
+            apply_properties(&mut intermediate, row.properties);
+
+            intermediates.push(intermediate);
+        }
+
+        if intermediates.is_empty() {
+            return Err(anyhow!("No pages were found in the Notion export"));
+        }
+
+        // The export is a whole, so the resources are reported with the first document
+        intermediates[0].unsupported_resources = unsupported_resources;
+
+        Ok(intermediates)
+    }
+}
+
+/// Notion appends a 32 digits hex id to the names of the exported files
+fn strip_notion_id(name: &str) -> String {
+    match name.rsplit_once(' ') {
+        Some((title, id)) if id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()) => {
+            title.to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn parse_database(database: &str, content: &str) -> Result<Vec<NotionDatabaseRow>> {
+    // Notion writes a BOM at the beginning of csv files
+    let mut reader = csv::Reader::from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|header| header.to_string())
+        .collect();
+
+    let mut rows: Vec<NotionDatabaseRow> = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let properties: Vec<(String, String)> = headers
+            .iter()
+            .cloned()
+            .zip(record.iter().map(|value| value.to_string()))
+            .collect();
+
+        // The first column is the title of a database
+        let title: String = match properties.first() {
+            Some((_, title)) if !title.is_empty() => title.clone(),
+            _ => "Untitled".to_string(),
+        };
+
+        rows.push(NotionDatabaseRow {
+            database: database.to_string(),
+            title,
+            properties,
+        });
+    }
+
+    Ok(rows)
+}
+
+fn apply_properties(intermediate: &mut ImportTaskIntermediate, properties: Vec<(String, String)>) {
+    for (key, value) in properties {
+        if value.is_empty() {
+            continue;
+        }
+
+        match key.to_lowercase().as_str() {
+            "tags" => {
+                intermediate.tags = value
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            "created" | "created time" => {
+                intermediate.created_at = parse_notion_timestamp(&value);
+            }
+            "last edited time" | "updated" => {
+                intermediate.last_modified = parse_notion_timestamp(&value);
+            }
+            _ => {}
+        }
+
+        intermediate.attributes.insert(key, value);
+    }
+}
+
+/// Timestamps in Notion exports look like `January 1, 2024 12:00 PM`
+fn parse_notion_timestamp(timestamp: &str) -> Option<String> {
+    let datetime = NaiveDateTime::parse_from_str(timestamp.trim(), "%B %d, %Y %I:%M %p").ok()?;
+    format_unix_timestamp(datetime.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_intermediates_from_export() {
+        let mut buffer: Vec<u8> = Vec::new();
+        {
+            let mut writer = ZipWriter::new(Cursor::new(&mut buffer));
+            let files: Vec<(&str, &str)> = vec![
+                (
+                    "Projects 0123456789abcdef0123456789abcdef.csv",
+                    "\u{feff}Name,Tags,Created\nRoadmap,\"plan, q1\",\"January 1, 2024 12:00 PM\"\nBudget,,\n",
+                ),
+                (
+                    "Projects 0123456789abcdef0123456789abcdef/Roadmap 11111111111111111111111111111111.md",
+                    "# Roadmap\n\nShip it.",
+                ),
+                ("Home 22222222222222222222222222222222.md", "# Home\n\nWelcome"),
+                ("Home 22222222222222222222222222222222/image.png", "png"),
+            ];
+            for (name, content) in files {
+                writer.start_file(name, SimpleFileOptions::default()).unwrap();
+                writer.write_all(content.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let intermediates = NotionConnector::get_intermediates(serde_json::json!({
+            "content": STANDARD.encode(&buffer),
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(intermediates.len(), 3);
+
+        let roadmap = &intermediates[0];
+        assert_eq!(roadmap.title, "Roadmap");
+        assert_eq!(roadmap.collection_title.as_deref(), Some("Projects"));
+        assert_eq!(roadmap.tags, vec!["plan", "q1"]);
+        assert_eq!(roadmap.created_at, format_unix_timestamp(1704110400));
+        assert_eq!(roadmap.unsupported_resources.len(), 1);
+
+        let home = &intermediates[1];
+        assert_eq!(home.title, "Home");
+        assert_eq!(home.collection_title, None);
+
+        let budget = &intermediates[2];
+        assert_eq!(budget.title, "Budget");
+        assert_eq!(budget.collection_title.as_deref(), Some("Projects"));
+    }
+}
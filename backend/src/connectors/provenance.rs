@@ -0,0 +1,117 @@
+use actix_web::cookie::time::UtcDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::requests::ImportType;
+
+/// Where an imported document came from.
+/// It is used for refreshing the document from its source.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DocumentProvenance {
+    pub import_type: ImportType,
+
+    /// The artifact of the import, without secrets like passwords.
+    /// Uploaded contents are not kept, as they are already in the document.
+    pub artifact: Value,
+
+    pub fetched_at: String,
+
+    /// SHA-256 of the imported content
+    pub content_hash: String,
+
+    /// The data source that provides the secrets of the artifact
+    #[serde(default)]
+    pub data_source_id: Option<String>,
+}
+
+impl DocumentProvenance {
+    /// The content hash is left empty, as one artifact may yield multiple documents.
+    /// Set it with the content of each document.
+    pub fn new(import_type: ImportType, artifact: &Value) -> Self {
+        Self {
+            artifact: strip_artifact(&import_type, artifact),
+            import_type,
+            fetched_at: UtcDateTime::now().to_string(),
+            content_hash: String::new(),
+            data_source_id: None,
+        }
+    }
+
+    /// Restore the secrets that were stripped from the artifact
+    pub fn artifact_with_secrets(&self, secrets: &serde_json::Map<String, Value>) -> Value {
+        let mut artifact: Value = self.artifact.clone();
+        if let Value::Object(map) = &mut artifact {
+            for (key, value) in secrets {
+                map.insert(key.clone(), value.clone());
+            }
+        }
+
+        artifact
+    }
+}
+
+impl ImportType {
+    /// Only the sources that can be fetched again are refreshable.
+    /// The others are uploaded by the user.
+    pub fn is_refreshable(&self) -> bool {
+        matches!(
+            self,
+            ImportType::Webpage | ImportType::RelationshipDatabase
+        )
+    }
+}
+
+fn strip_artifact(import_type: &ImportType, artifact: &Value) -> Value {
+    match artifact {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !import_type.secret_fields().contains(&key.as_str()))
+                .filter(|(key, _)| import_type.is_refreshable() || key.as_str() != "content")
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        // Text files are uploaded as plain strings
+        _ if !import_type.is_refreshable() => Value::Null,
+        value => value.clone(),
+    }
+}
+
+pub fn hash_content(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance_strips_secrets() {
+        let artifact = serde_json::json!({
+            "database_type": "postgres",
+            "username": "user",
+            "password": "secret",
+            "query": "SELECT body FROM notes",
+        });
+        let provenance = DocumentProvenance::new(ImportType::RelationshipDatabase, &artifact);
+
+        assert!(provenance.artifact.get("password").is_none());
+
+        let mut secrets = serde_json::Map::new();
+        secrets.insert("password".to_string(), Value::String("secret".to_string()));
+        assert_eq!(provenance.artifact_with_secrets(&secrets), artifact);
+    }
+
+    #[test]
+    fn test_provenance_drops_uploaded_content() {
+        let provenance = DocumentProvenance::new(
+            ImportType::Email,
+            &serde_json::json!({ "content": "raw mbox", "group_by_thread": true }),
+        );
+
+        assert_eq!(
+            provenance.artifact,
+            serde_json::json!({ "group_by_thread": true })
+        );
+    }
+}
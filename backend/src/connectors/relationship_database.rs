@@ -129,6 +129,7 @@ impl Connector for RelationshipDatabaseConnector {
                 .table_name
                 .unwrap_or_else(|| "Query Result".to_string()),
             content,
+            ..Default::default()
         })
     }
 }
@@ -1,17 +1,95 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::{
+    email::EmailConnector, evernote::EvernoteConnector, models::ImportTaskIntermediate,
+    notion::NotionConnector, relationship_database::RelationshipDatabaseConnector,
+    structured_data::StructuredDataConnector, text_file::TextFileConnector, traits::Connector,
+    webpage::WebpageConnector,
+};
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, PartialOrd, Ord, Eq, Hash)]
 pub enum ImportType {
     Webpage,
     TextFile,
     RelationshipDatabase,
+    Email,
+    Notion,
+    Evernote,
+    StructuredData,
+}
+
+/// Replaces secrets in the artifacts that are echoed back to the users
+pub const SECRET_MASK: &str = "******";
+
+impl ImportType {
+    /// Fields of the artifact that should never be persisted or echoed back
+    pub fn secret_fields(&self) -> &'static [&'static str] {
+        match self {
+            ImportType::RelationshipDatabase => &["password"],
+            _ => &[],
+        }
+    }
+
+    /// Fields of the artifact that tell where the secrets are sent.
+    /// Imports from a data source can never change them.
+    pub fn connection_fields(&self) -> &'static [&'static str] {
+        match self {
+            ImportType::RelationshipDatabase => {
+                &["database_type", "username", "host", "port", "database_name"]
+            }
+            _ => &[],
+        }
+    }
+
+    pub fn mask_secrets(&self, artifact: &Value) -> Value {
+        let mut artifact: Value = artifact.clone();
+        if let Value::Object(map) = &mut artifact {
+            for field in self.secret_fields() {
+                if let Some(value) = map.get_mut(*field) {
+                    *value = Value::String(SECRET_MASK.to_string());
+                }
+            }
+        }
+
+        artifact
+    }
+
+    /// Select a connector to get the intermediates from the artifact
+    pub async fn get_intermediates(&self, artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        match self {
+            ImportType::TextFile => TextFileConnector::get_intermediates(artifact).await,
+            ImportType::Webpage => WebpageConnector::get_intermediates(artifact).await,
+            ImportType::RelationshipDatabase => {
+                RelationshipDatabaseConnector::get_intermediates(artifact).await
+            }
+            ImportType::Email => EmailConnector::get_intermediates(artifact).await,
+            ImportType::Notion => NotionConnector::get_intermediates(artifact).await,
+            ImportType::Evernote => EvernoteConnector::get_intermediates(artifact).await,
+            ImportType::StructuredData => StructuredDataConnector::get_intermediates(artifact).await,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub struct ImportTask {
     pub import_type: ImportType,
     pub artifact: Value,
+    /// Use the stored credentials of a data source instead of inlining them.
+    /// Fields in the artifact are merged onto the ones of the data source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_source_id: Option<String>,
+}
+
+impl ImportTask {
+    pub fn masked(&self) -> Self {
+        Self {
+            import_type: self.import_type.clone(),
+            artifact: self.import_type.mask_secrets(&self.artifact),
+            data_source_id: self.data_source_id.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
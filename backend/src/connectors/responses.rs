@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-use crate::connectors::requests::ImportTask;
+use crate::{connectors::requests::ImportTask, embedder::VectorizationStatistics};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ImportDocumentsResponse {
     pub failed_import_tasks: Vec<ImportTask>,
     pub document_metadata_ids: Vec<String>,
+    /// Embedded resources in the sources that were not imported
+    pub unsupported_resources: Vec<String>,
+    pub vectorization_statistics: VectorizationStatistics,
 }
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{models::ImportTaskIntermediate, traits::Connector};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuredDataFormat {
+    Csv,
+    /// One json object per line
+    JsonLines,
+    /// An array of json objects
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StructuredDataImportMode {
+    #[default]
+    DocumentPerRecord,
+    SingleDocument,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredDataArtifact {
+    /// Raw content of the file
+    pub content: String,
+
+    pub format: StructuredDataFormat,
+
+    #[serde(default)]
+    pub mode: StructuredDataImportMode,
+
+    /// Field to use as the document title in `document_per_record` mode
+    #[serde(default)]
+    pub title_field: Option<String>,
+
+    /// Template of the document content, like `{{question}}\n{{answer}}`.
+    /// Every field is written as `field: value` lines if it is left empty.
+    #[serde(default)]
+    pub content_template: Option<String>,
+
+    /// Document title in `single_document` mode
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Fields of a record, in the order of the source
+type Record = Vec<(String, String)>;
+
+#[derive(Debug, Clone)]
+pub struct StructuredDataConnector;
+
+#[async_trait]
+impl Connector for StructuredDataConnector {
+    async fn get_intermediate(artifact: Value) -> Result<ImportTaskIntermediate> {
+        match Self::get_intermediates(artifact).await?.into_iter().next() {
+            Some(intermediate) => Ok(intermediate),
+            None => Err(anyhow!("No records were found in the structured data")),
+        }
+    }
+
+    async fn get_intermediates(artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        let structured_data_artifact: StructuredDataArtifact = serde_json::from_value(artifact)?;
+
+        let records: Vec<Record> = match structured_data_artifact.format {
+            StructuredDataFormat::Csv => parse_csv(&structured_data_artifact.content)?,
+            StructuredDataFormat::JsonLines => parse_json_lines(&structured_data_artifact.content)?,
+            StructuredDataFormat::Json => parse_json(&structured_data_artifact.content)?,
+        };
+
+        if records.is_empty() {
+            return Err(anyhow!("No records were found in the structured data"));
+        }
+
+        let template: Option<&str> = structured_data_artifact.content_template.as_deref();
+
+        match structured_data_artifact.mode {
+            StructuredDataImportMode::SingleDocument => Ok(vec![ImportTaskIntermediate {
+                title: structured_data_artifact
+                    .title
+                    .unwrap_or_else(|| "Structured Data".to_string()),
+                content: records
+                    .iter()
+                    .map(|record| render_record(record, template))
+                    .collect::<Vec<String>>()
+                    .join("\n\n"),
+                ..Default::default()
+            }]),
+            StructuredDataImportMode::DocumentPerRecord => Ok(records
+                .into_iter()
+                .enumerate()
+                .map(|(index, record)| {
+                    record_to_intermediate(
+                        index,
+                        record,
+                        structured_data_artifact.title_field.as_deref(),
+                        template,
+                    )
+                })
+                .collect()),
+        }
+    }
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Record>> {
+    let mut reader = csv::Reader::from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|header| header.to_string())
+        .collect();
+
+    let mut records: Vec<Record> = Vec::new();
+    for record in reader.records() {
+        records.push(
+            headers
+                .iter()
+                .cloned()
+                .zip(record?.iter().map(|value| value.to_string()))
+                .collect(),
+        );
+    }
+
+    Ok(records)
+}
+
+fn parse_json_lines(content: &str) -> Result<Vec<Record>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| json_to_record(serde_json::from_str(line)?))
+        .collect()
+}
+
+fn parse_json(content: &str) -> Result<Vec<Record>> {
+    match serde_json::from_str(content)? {
+        Value::Array(values) => values.into_iter().map(json_to_record).collect(),
+        value => Ok(vec![json_to_record(value)?]),
+    }
+}
+
+fn json_to_record(value: Value) -> Result<Record> {
+    match value {
+        Value::Object(map) => Ok(map
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(value) => (key, value),
+                Value::Null => (key, String::new()),
+                value => (key, value.to_string()),
+            })
+            .collect()),
+        _ => Err(anyhow!("Each record should be a json object")),
+    }
+}
+
+/// Fields that are written into the content by the template
+fn template_fields(template: &str) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut rest: &str = template;
+    while let Some(start) = rest.find("{{") {
+        match rest[start + 2..].find("}}") {
+            Some(end) => {
+                fields.push(rest[start + 2..start + 2 + end].trim().to_string());
+                rest = &rest[start + 2 + end + 2..];
+            }
+            None => break,
+        }
+    }
+
+    fields
+}
+
+fn render_record(record: &Record, template: Option<&str>) -> String {
+    match template {
+        Some(template) => {
+            let mut content: String = String::new();
+            let mut rest: &str = template;
+            while let Some(start) = rest.find("{{") {
+                let end: usize = match rest[start + 2..].find("}}") {
+                    Some(end) => start + 2 + end,
+                    None => break,
+                };
+
+                let field: &str = rest[start + 2..end].trim();
+                content.push_str(&rest[..start]);
+                content.push_str(
+                    record
+                        .iter()
+                        .find(|(key, _)| key == field)
+                        .map(|(_, value)| value.as_str())
+                        .unwrap_or(""),
+                );
+                rest = &rest[end + 2..];
+            }
+            content.push_str(rest);
+
+            content
+        }
+        None => record
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    }
+}
+
+fn record_to_intermediate(
+    index: usize,
+    record: Record,
+    title_field: Option<&str>,
+    template: Option<&str>,
+) -> ImportTaskIntermediate {
+    let title: String = title_field
+        .and_then(|title_field| record.iter().find(|(key, _)| key == title_field))
+        .map(|(_, value)| value.clone())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| format!("Record {}", index + 1));
+
+    // Fields that are not in the title or the template are kept as attributes.
+    // Without a template, every field other than the title is kept for filtering.
+    let used_fields: Vec<String> = template.map(template_fields).unwrap_or_default();
+    let attributes: HashMap<String, String> = record
+        .iter()
+        .filter(|(key, _)| Some(key.as_str()) != title_field && !used_fields.contains(key))
+        .cloned()
+        .collect();
+
+    ImportTaskIntermediate {
+        title,
+        content: render_record(&record, template),
+        attributes,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_document_per_record_with_template() {
+        let intermediates = StructuredDataConnector::get_intermediates(serde_json::json!({
+            "content": "question,answer,topic\nWhat is Rust?,A language,programming\n",
+            "format": "csv",
+            "title_field": "question",
+            "content_template": "{{question}}\n{{ answer }}",
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(intermediates.len(), 1);
+        assert_eq!(intermediates[0].title, "What is Rust?");
+        assert_eq!(intermediates[0].content, "What is Rust?\nA language");
+        assert_eq!(intermediates[0].attributes.len(), 1);
+        assert_eq!(intermediates[0].attributes.get("topic").unwrap(), "programming");
+    }
+
+    #[tokio::test]
+    async fn test_single_document_from_json_lines() {
+        let intermediates = StructuredDataConnector::get_intermediates(serde_json::json!({
+            "content": "{\"count\": 1, \"name\": \"a\"}\n\n{\"count\": 2, \"name\": \"b\"}\n",
+            "format": "json_lines",
+            "mode": "single_document",
+            "title": "Counts",
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(intermediates.len(), 1);
+        assert_eq!(intermediates[0].title, "Counts");
+        assert_eq!(intermediates[0].content, "count: 1\nname: a\n\ncount: 2\nname: b");
+    }
+}
@@ -20,6 +20,7 @@ impl Connector for TextFileConnector {
         Ok(ImportTaskIntermediate {
             title,
             content: artifact,
+            ..Default::default()
         })
     }
 }
@@ -7,4 +7,10 @@ use super::models::ImportTaskIntermediate;
 #[async_trait]
 pub trait Connector {
     async fn get_intermediate(artifact: Value) -> Result<ImportTaskIntermediate>;
+
+    /// Some sources, like mailboxes, carry more than one document in a single artifact.
+    /// Connectors for these sources should override this method.
+    async fn get_intermediates(artifact: Value) -> Result<Vec<ImportTaskIntermediate>> {
+        Ok(vec![Self::get_intermediate(artifact).await?])
+    }
 }
@@ -41,24 +41,27 @@ impl Connector for WebpageConnector {
         let response = client.get(webpage_artifact.url.clone()).send().await?;
         let raw_content = response.text().await?;
         
-        let skip_images = if webpage_artifact.preserve_image {
-            false
-        } else {
-            true
-        };
-        
-        let markdown = convert_html(
-            &raw_content,
-            &ConversionOptions {
-                extract_metadata: false,
-                skip_images,
-                ..Default::default()
-            },
-        )?;
+        let markdown = html_to_markdown(&raw_content, webpage_artifact.preserve_image)?;
 
         Ok(ImportTaskIntermediate {
             title: webpage_artifact.url.to_string(),
             content: markdown,
+            ..Default::default()
         })
     }
 }
+
+/// Convert a html document into markdown.
+/// Shared by the connectors that receive html contents.
+pub fn html_to_markdown(html: &str, preserve_image: bool) -> Result<String> {
+    let markdown = convert_html(
+        html,
+        &ConversionOptions {
+            extract_metadata: false,
+            skip_images: !preserve_image,
+            ..Default::default()
+        },
+    )?;
+
+    Ok(markdown)
+}
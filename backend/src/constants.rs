@@ -1,2 +1,53 @@
 pub const QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME: &str = "dense_text_vector";
 pub const QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME: &str = "sparse_text_vector";
+/// Qdrant turns the text into the sparse vector with this model
+pub const QDRANT_SPARSE_TEXT_VECTOR_MODEL: &str = "qdrant/bm25";
+
+/// Number of items that cancellable tasks process concurrently.
+/// Cancellation requests are checked between the batches.
+pub const TASK_BATCH_SIZE: usize = 16;
+
+/// Longest time that finished tasks can be kept, in seconds. About 100 years.
+pub const MAX_TASK_TTL_SECONDS: u64 = 3_153_600_000;
+
+/// How often to look for the scheduled jobs that are due, in seconds
+pub const SCHEDULED_JOBS_TICK_SECONDS: u64 = 30;
+
+/// How often the recorded searches are saved, in seconds
+pub const SEARCH_HISTORY_SAVE_INTERVAL_SECONDS: u64 = 10;
+
+/// How often the newly cached chunk vectors are saved, in seconds.
+/// The cache can be large, so it is saved less often than the search history.
+pub const EMBEDDING_CACHE_SAVE_INTERVAL_SECONDS: u64 = 60;
+
+/// How often the chunks written to the embedded database are saved, in seconds
+pub const EMBEDDED_DATABASE_SAVE_INTERVAL_SECONDS: u64 = 10;
+
+/// Number of document chunks fetched at a time while re-indexing
+pub const REINDEX_PAGE_SIZE: u32 = 256;
+
+/// Hybrid searches take this many times of `top_n` candidates from each method before fusing them
+pub const HYBRID_SEARCH_CANDIDATES_FACTOR: usize = 4;
+
+/// Smooths the reciprocal rank fusion, so that the top few ranks do not dominate
+pub const RECIPROCAL_RANK_FUSION_K: f32 = 60.0;
+
+/// Term frequency saturation of BM25
+pub const BM25_K1: f32 = 1.2;
+
+/// How much BM25 normalizes the scores by the lengths of the chunks
+pub const BM25_B: f32 = 0.75;
+
+/// Keyword searches rank at most this many matching chunks, when the database can't rank them itself
+pub const KEYWORD_SEARCH_CANDIDATES_LIMIT: usize = 1000;
+
+/// Grouped searches take this many times of the chunks needed to fill the documents,
+/// as some documents have more matching chunks than others
+pub const GROUPED_SEARCH_CANDIDATES_FACTOR: usize = 4;
+
+/// Searches reach at most this many results, counting the ones skipped by `offset`
+pub const MAX_SEARCH_RESULTS: usize = 10000;
+
+/// Bumped whenever the document fields copied onto the chunks change,
+/// so that the existing chunks get the new fields at startup
+pub const DOCUMENT_PAYLOADS_VERSION: usize = 1;
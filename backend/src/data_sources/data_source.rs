@@ -0,0 +1,155 @@
+use actix_web::cookie::time::UtcDateTime;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use super::encryption::{decrypt, encrypt};
+use crate::connectors::requests::ImportType;
+
+/// A saved import source, like a database connection.
+/// Secret fields of the artifact are kept encrypted, the others are kept as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSource {
+    pub id: String,
+
+    pub name: String,
+
+    /// Username of the user who created the data source
+    pub owner: String,
+
+    pub import_type: ImportType,
+
+    /// Artifact without the secret fields
+    pub artifact: Map<String, Value>,
+
+    /// Secret fields of the artifact, encrypted as a json object
+    pub encrypted_secrets: String,
+
+    pub created_at: String,
+}
+
+impl DataSource {
+    pub fn new(
+        name: String,
+        owner: String,
+        import_type: ImportType,
+        artifact: Value,
+        encryption_key: &str,
+    ) -> Result<Self> {
+        let mut artifact: Map<String, Value> = match artifact {
+            Value::Object(map) => map,
+            _ => return Err(anyhow!("The artifact of a data source should be a json object")),
+        };
+
+        let mut secrets: Map<String, Value> = Map::new();
+        for field in import_type.secret_fields() {
+            if let Some(value) = artifact.remove(*field) {
+                secrets.insert(field.to_string(), value);
+            }
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            owner,
+            import_type,
+            artifact,
+            encrypted_secrets: encrypt(encryption_key, &serde_json::to_vec(&secrets)?)?,
+            created_at: UtcDateTime::now().to_string(),
+        })
+    }
+
+    pub fn get_secrets(&self, encryption_key: &str) -> Result<Map<String, Value>> {
+        Ok(serde_json::from_slice(&decrypt(
+            encryption_key,
+            &self.encrypted_secrets,
+        )?)?)
+    }
+
+    /// Build the artifact of an import of the same type as the data source.
+    /// Fields of the import artifact override the ones of the data source,
+    /// except that the connection fields and the secrets always come from the data source.
+    pub fn resolve_artifact(
+        &self,
+        import_type: &ImportType,
+        artifact: &Value,
+        encryption_key: &str,
+    ) -> Result<Value> {
+        if *import_type != self.import_type {
+            return Err(anyhow!(
+                "Data source {} is for {:?} imports, not {:?}",
+                self.id,
+                self.import_type,
+                import_type
+            ));
+        }
+
+        let mut resolved: Map<String, Value> = self.artifact.clone();
+        if let Value::Object(map) = artifact {
+            for (key, value) in map {
+                if self.import_type.connection_fields().contains(&key.as_str()) {
+                    continue;
+                }
+
+                resolved.insert(key.clone(), value.clone());
+            }
+        }
+        resolved.extend(self.get_secrets(encryption_key)?);
+
+        Ok(Value::Object(resolved))
+    }
+
+    /// The artifact with the secrets masked, for showing to the users
+    pub fn get_masked_artifact(&self) -> Value {
+        let mut artifact: Map<String, Value> = self.artifact.clone();
+        for field in self.import_type.secret_fields() {
+            artifact.insert(field.to_string(), Value::Null);
+        }
+
+        self.import_type.mask_secrets(&Value::Object(artifact))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_encrypted_and_resolved() {
+        let data_source = DataSource::new(
+            "notes database".to_string(),
+            "user".to_string(),
+            ImportType::RelationshipDatabase,
+            serde_json::json!({ "host": "localhost", "password": "secret" }),
+            "key",
+        )
+        .unwrap();
+
+        assert!(!data_source.artifact.contains_key("password"));
+        assert!(!data_source.encrypted_secrets.contains("secret"));
+        assert_eq!(
+            data_source.get_masked_artifact(),
+            serde_json::json!({ "host": "localhost", "password": "******" })
+        );
+
+        // The import cannot redirect the secrets to another host
+        let artifact = data_source
+            .resolve_artifact(
+                &ImportType::RelationshipDatabase,
+                &serde_json::json!({ "query": "SELECT 1", "host": "attacker.example.com" }),
+                "key",
+            )
+            .unwrap();
+        assert_eq!(
+            artifact,
+            serde_json::json!({ "host": "localhost", "password": "secret", "query": "SELECT 1" })
+        );
+
+        assert!(
+            data_source
+                .resolve_artifact(&ImportType::Webpage, &serde_json::json!({}), "key")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha2::{Digest, Sha256};
+
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LENGTH: usize = 12;
+
+/// The configured secret can be of any length, so we derive a 256 bits key from it
+fn derive_cipher(secret: &str) -> Result<Aes256Gcm> {
+    if secret.is_empty() {
+        return Err(anyhow!(
+            "`data_sources_storage.encryption_key` is not set in the configurations"
+        ));
+    }
+
+    let key = Sha256::digest(secret.as_bytes());
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// Returns base64 encoded nonce followed by the ciphertext
+pub fn encrypt(secret: &str, plaintext: &[u8]) -> Result<String> {
+    let cipher = derive_cipher(secret)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext: Vec<u8> = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt the credentials"))?;
+
+    let mut buffer: Vec<u8> = nonce.to_vec();
+    buffer.extend(ciphertext);
+
+    Ok(STANDARD.encode(buffer))
+}
+
+pub fn decrypt(secret: &str, encrypted: &str) -> Result<Vec<u8>> {
+    let cipher = derive_cipher(secret)?;
+    let buffer: Vec<u8> = STANDARD.decode(encrypted)?;
+    if buffer.len() < NONCE_LENGTH {
+        return Err(anyhow!("The encrypted credentials are corrupted"));
+    }
+
+    let (nonce, ciphertext) = buffer.split_at(NONCE_LENGTH);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            anyhow!("Failed to decrypt the credentials. The encryption key may have been changed")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let encrypted: String = encrypt("key", b"password").unwrap();
+
+        assert_eq!(decrypt("key", &encrypted).unwrap(), b"password");
+        assert!(decrypt("another key", &encrypted).is_err());
+        assert!(encrypt("", b"password").is_err());
+    }
+}
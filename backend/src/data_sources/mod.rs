@@ -0,0 +1,6 @@
+//! Named data sources that keep the credentials of imports on the server side.
+//! Credentials are encrypted at rest with the key from the configurations.
+
+pub mod data_source;
+pub mod encryption;
+pub mod storage;
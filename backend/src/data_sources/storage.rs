@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use super::data_source::DataSource;
+use crate::traits::LoadAndSave;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourcesStorage {
+    pub path: PathBuf,
+    pub data_sources: Vec<DataSource>,
+}
+
+impl LoadAndSave for DataSourcesStorage {
+    fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            data_sources: Vec::new(),
+        }
+    }
+
+    fn get_path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl DataSourcesStorage {
+    pub async fn add_data_source(&mut self, data_source: DataSource) -> Result<String> {
+        if self
+            .data_sources
+            .iter()
+            .any(|item| item.owner == data_source.owner && item.name == data_source.name)
+        {
+            return Err(anyhow!(
+                "Data source `{}` has already existed",
+                data_source.name
+            ));
+        }
+
+        let id: String = data_source.id.clone();
+        self.data_sources.push(data_source);
+        self.save().await?;
+
+        Ok(id)
+    }
+
+    pub async fn remove_data_source(&mut self, id: &str, owner: &str) -> Result<()> {
+        let length: usize = self.data_sources.len();
+        self.data_sources
+            .retain(|item| !(item.id == id && item.owner == owner));
+
+        if self.data_sources.len() == length {
+            return Err(anyhow!("Data source {} was not found", id));
+        }
+
+        self.save().await?;
+        Ok(())
+    }
+
+    /// Users can only access their own data sources
+    pub fn get_data_source(&self, id: &str, owner: &str) -> Result<&DataSource> {
+        match self
+            .data_sources
+            .iter()
+            .find(|item| item.id == id && item.owner == owner)
+        {
+            Some(result) => Ok(result),
+            None => Err(anyhow!("Data source {} was not found", id)),
+        }
+    }
+
+    pub fn get_data_sources_by_owner(&self, owner: &str) -> Vec<&DataSource> {
+        self.data_sources
+            .iter()
+            .filter(|item| item.owner == owner)
+            .collect()
+    }
+}
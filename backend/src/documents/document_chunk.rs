@@ -12,6 +12,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::traits::{GetIndexableFields, IndexableField};
+use crate::constants::QDRANT_SPARSE_TEXT_VECTOR_MODEL;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct DocumentChunk {
@@ -163,7 +164,7 @@ impl From<DocumentChunk> for PointStruct {
                     "sparse_text_vector",
                     qdrant_client::qdrant::Document {
                         text: value.content.clone(),
-                        model: "qdrant/bm25".into(),
+                        model: QDRANT_SPARSE_TEXT_VECTOR_MODEL.into(),
                         ..Default::default()
                     },
                 ),
@@ -207,6 +208,19 @@ pub struct DocumentChunkSearchResult {
     pub document_chunk: DocumentChunk,
     /// Similarity score
     pub score: f32,
+    /// Where the keywords matched in the content. Only keyword searches fill it in.
+    #[serde(default)]
+    pub highlights: Vec<HighlightSpan>,
+    /// Relevance score given by the reranker. `score` keeps the retrieval score.
+    #[serde(default)]
+    pub rerank_score: Option<f32>,
+}
+
+/// A matched part of the content, counted in characters. `end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
 }
 
 impl Default for DocumentChunkSearchResult {
@@ -216,6 +230,8 @@ impl Default for DocumentChunkSearchResult {
             collection_title: None,
             document_chunk: DocumentChunk::default(),
             score: 0.0,
+            highlights: Vec::new(),
+            rerank_score: None,
         }
     }
 }
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use actix_web::cookie::time::UtcDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::traits::ValidateDataMutabilitiesForAPICaller;
+use super::traits::{GetIndexableFields, IndexableField, ValidateDataMutabilitiesForAPICaller};
+use crate::connectors::{provenance::DocumentProvenance, requests::ImportType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentMetadata {
@@ -18,6 +22,17 @@ pub struct DocumentMetadata {
     pub title: String,
 
     pub chunks: Vec<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Source specific attributes carried over from imports, such as email headers
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+
+    /// Where the document was imported from. Empty for documents added by users.
+    #[serde(default)]
+    pub provenance: Option<DocumentProvenance>,
 }
 
 impl DocumentMetadata {
@@ -30,6 +45,9 @@ impl DocumentMetadata {
             collection_metadata_id,
             title,
             chunks: Vec::new(),
+            tags: Vec::new(),
+            attributes: HashMap::new(),
+            provenance: None,
         }
     }
 }
@@ -57,3 +75,80 @@ impl ValidateDataMutabilitiesForAPICaller for DocumentMetadata {
         Ok(())
     }
 }
+
+/// Fields of a document copied onto the payloads of its chunks,
+/// so that the searches can filter the chunks by them in the database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentPayload {
+    pub document_metadata_id: String,
+    /// Documents may move to other collections, so it is kept up to date as well
+    pub collection_metadata_id: String,
+    pub document_title: String,
+    pub document_tags: Vec<String>,
+    /// Empty for documents added by users
+    pub document_import_type: Option<ImportType>,
+    /// Unix timestamp in seconds
+    pub document_created_at: i64,
+    /// Unix timestamp in seconds
+    pub document_last_modified: i64,
+}
+
+impl From<&DocumentMetadata> for DocumentPayload {
+    fn from(value: &DocumentMetadata) -> Self {
+        Self {
+            document_metadata_id: value.id.clone(),
+            collection_metadata_id: value.collection_metadata_id.clone(),
+            document_title: value.title.clone(),
+            document_tags: value.tags.clone(),
+            document_import_type: value
+                .provenance
+                .as_ref()
+                .map(|provenance| provenance.import_type.clone()),
+            document_created_at: parse_date_time(&value.created_at)
+                .map_or(0, |date_time| date_time.timestamp()),
+            document_last_modified: parse_date_time(&value.last_modified)
+                .map_or(0, |date_time| date_time.timestamp()),
+        }
+    }
+}
+
+impl GetIndexableFields for DocumentPayload {
+    fn get_indexable_fields() -> Vec<IndexableField> {
+        vec![
+            IndexableField::FullText("document_title".to_string()),
+            IndexableField::Keyword("document_tags".to_string()),
+            IndexableField::Keyword("document_import_type".to_string()),
+            IndexableField::Integer("document_created_at".to_string()),
+            IndexableField::Integer("document_last_modified".to_string()),
+        ]
+    }
+}
+
+/// Dates of the metadatas are written by `UtcDateTime`, like `2025-01-02 3:04:05.5 +00`.
+/// RFC 3339 dates are accepted as well.
+pub fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(value.trim_end_matches(" +00"), "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|date_time| date_time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_time() {
+        let now = UtcDateTime::now();
+        let parsed = parse_date_time(&now.to_string()).unwrap();
+        assert_eq!(parsed.timestamp(), now.unix_timestamp());
+
+        let parsed = parse_date_time("2025-01-02T03:04:05+08:00").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2025-01-01T19:04:05+00:00");
+
+        assert!(parse_date_time("").is_none());
+    }
+}
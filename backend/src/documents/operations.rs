@@ -1,10 +1,24 @@
-use tokio::sync::MutexGuard;
+use std::{collections::HashMap, sync::Arc};
+
+use actix_web::cookie::time::UtcDateTime;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use tokio::sync::{Mutex, MutexGuard};
 
 use crate::{
+    api_models::document::RefreshCredentials,
+    configurations::{
+        search::UserSearchConfiguration,
+        system::{Config, DatabaseConfig},
+    },
+    connectors::provenance::{DocumentProvenance, hash_content},
+    data_sources::storage::DataSourcesStorage,
     documents::{document_chunk::DocumentChunk, document_metadata::DocumentMetadata},
-    identities::storage::IdentitiesStorage,
+    embedder::VectorizationStatistics,
+    identities::{storage::IdentitiesStorage, user::User},
     metadata_storage::MetadataStorage,
-    search::SearchScope,
+    search::{SearchFilter, SearchScope},
+    vector_database::traits::VectorDatabase,
 };
 
 pub fn retrieve_document_ids_by_scope(
@@ -39,6 +53,101 @@ pub fn retrieve_document_ids_by_scope(
     document_metadata_ids
 }
 
+/// The user who owns the scope, or `None` if the owner can't be found
+pub fn retrieve_owner_by_scope<'a>(
+    metadata_storage: &MetadataStorage,
+    identities_storage: &'a IdentitiesStorage,
+    search_scope: SearchScope,
+    id: &str,
+) -> Option<&'a User> {
+    let collection_metadata_id: Option<String> = match search_scope {
+        SearchScope::Userspace => {
+            return identities_storage.users.iter().find(|user| user.username == id);
+        }
+        SearchScope::Collection => Some(id.to_string()),
+        SearchScope::Document => metadata_storage
+            .documents
+            .get(id)
+            .map(|metadata| metadata.collection_metadata_id.clone()),
+    };
+
+    collection_metadata_id.and_then(|id| {
+        identities_storage
+            .get_users_by_resource_id(&id)
+            .into_iter()
+            .next()
+    })
+}
+
+/// Search configurations of the user who owns the scope.
+/// Falls back to the defaults if the owner can't be found.
+pub fn retrieve_search_configuration_by_scope(
+    metadata_storage: &MutexGuard<'_, MetadataStorage>,
+    identities_storage: &MutexGuard<'_, IdentitiesStorage>,
+    search_scope: SearchScope,
+    id: &str,
+) -> UserSearchConfiguration {
+    retrieve_owner_by_scope(metadata_storage, identities_storage, search_scope, id)
+        .map(|user| user.configuration.search.clone())
+        .unwrap_or_default()
+}
+
+/// Keep the documents matching the search filter, by their metadatas
+pub fn filter_document_ids_by_search_filter(
+    metadata_storage: &MetadataStorage,
+    document_metadata_ids: Vec<String>,
+    filter: &SearchFilter,
+) -> Vec<String> {
+    if filter.is_empty() {
+        return document_metadata_ids;
+    }
+
+    document_metadata_ids
+        .into_iter()
+        .filter(|id| {
+            metadata_storage
+                .documents
+                .get(id)
+                .is_some_and(|metadata| filter.matches(metadata))
+        })
+        .collect()
+}
+
+/// Number of chunks of the documents.
+/// Every chunk in the scope is a hit of the semantic search.
+pub fn count_document_chunks(
+    metadata_storage: &MutexGuard<'_, MetadataStorage>,
+    document_metadata_ids: &[String],
+) -> usize {
+    document_metadata_ids
+        .iter()
+        .filter_map(|id| metadata_storage.documents.get(id))
+        .map(|metadata| metadata.chunks.len())
+        .sum()
+}
+
+/// Keep the documents whose attributes match all the given ones.
+/// Attributes come from imports, such as the extra fields of structured data.
+pub fn filter_document_ids_by_attributes(
+    metadata_storage: &MutexGuard<'_, MetadataStorage>,
+    document_metadata_ids: Vec<String>,
+    attributes: &HashMap<String, String>,
+) -> Vec<String> {
+    if attributes.is_empty() {
+        return document_metadata_ids;
+    }
+
+    document_metadata_ids
+        .into_iter()
+        .filter(|id| match metadata_storage.documents.get(id) {
+            Some(metadata) => attributes
+                .iter()
+                .all(|(key, value)| metadata.attributes.get(key) == Some(value)),
+            None => false,
+        })
+        .collect()
+}
+
 pub fn preprocess_document(
     title: &str,
     content: &str,
@@ -61,3 +170,229 @@ pub fn preprocess_document(
     let metadata_id = metadata.id.clone();
     (metadata, chunks, metadata_id)
 }
+
+/// Find the user's collection with the given title, or create one if it does not exist.
+/// Used by the importers that map notebooks to collections.
+/// Returns the collection metadata id, and whether the collection was created.
+pub async fn get_or_create_collection_by_title(
+    metadata_storage: &Arc<Mutex<MetadataStorage>>,
+    identities_storage: &Arc<Mutex<IdentitiesStorage>>,
+    username: &str,
+    title: &str,
+) -> Result<(String, bool)> {
+    // The storages are locked one at a time, so that this does not hold one while waiting for the other
+    let resource_ids: Vec<String> = identities_storage
+        .lock()
+        .await
+        .get_resource_ids_by_username(username)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    let collection_metadata_id: String = {
+        let mut metadata_storage = metadata_storage.lock().await;
+        let existing: Option<&String> = resource_ids.iter().find(|id| {
+            metadata_storage
+                .collections
+                .get(*id)
+                .is_some_and(|collection| collection.title == title)
+        });
+        if let Some(collection_metadata_id) = existing {
+            return Ok((collection_metadata_id.clone(), false));
+        }
+
+        metadata_storage.create_collection(title).await?
+    };
+
+    identities_storage
+        .lock()
+        .await
+        .add_authorized_resources(username, vec![collection_metadata_id.clone()])
+        .await?;
+
+    Ok((collection_metadata_id, true))
+}
+
+/// Re-run the connector that imported the document.
+/// The document is re-chunked and re-embedded only if the content hash has changed.
+///
+/// Secrets are not kept in the provenance. They come from the data source of the import,
+/// or from the credentials if the secrets were inlined in the import.
+/// Returns the vectorization statistics if the content has changed, or `None` if it has not.
+pub async fn refresh_document_from_source(
+    vector_database: &Arc<dyn VectorDatabase>,
+    metadata_storage: &Arc<Mutex<MetadataStorage>>,
+    data_sources_storage: &Arc<Mutex<DataSourcesStorage>>,
+    config: &Config,
+    document_metadata_id: &str,
+    credentials: &RefreshCredentials,
+    chunk_size: usize,
+) -> Result<Option<VectorizationStatistics>> {
+    let mut metadata: DocumentMetadata = match metadata_storage
+        .lock()
+        .await
+        .get_document(document_metadata_id)
+        .await
+    {
+        Some(result) => result.to_owned(),
+        None => return Err(anyhow!("Document {} was not found", document_metadata_id)),
+    };
+
+    let mut provenance: DocumentProvenance = match metadata.provenance.clone() {
+        Some(result) if result.import_type.is_refreshable() => result,
+        Some(result) => {
+            return Err(anyhow!(
+                "Document {} was uploaded as {:?}, which cannot be refreshed",
+                document_metadata_id,
+                result.import_type
+            ));
+        }
+        None => {
+            return Err(anyhow!(
+                "Document {} was not imported from a source",
+                document_metadata_id
+            ));
+        }
+    };
+
+    let artifact: Value = match &provenance.data_source_id {
+        Some(data_source_id) => data_sources_storage
+            .lock()
+            .await
+            .get_data_source(data_source_id, &credentials.username)?
+            .resolve_artifact(
+                &provenance.import_type,
+                &provenance.artifact,
+                &config.data_sources_storage.encryption_key,
+            )?,
+        None => provenance.artifact_with_secrets(&credentials.secrets),
+    };
+
+    let content: String = match provenance
+        .import_type
+        .get_intermediates(artifact)
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(intermediate) => intermediate.content,
+        None => return Err(anyhow!("The source of document {} is empty", document_metadata_id)),
+    };
+
+    let content_hash: String = hash_content(&content);
+    let is_changed: bool = content_hash != provenance.content_hash;
+    provenance.fetched_at = UtcDateTime::now().to_string();
+
+    let mut statistics: Option<VectorizationStatistics> = None;
+    if is_changed {
+        let chunks: Vec<DocumentChunk> = DocumentChunk::slice_document_automatically(
+            &content,
+            chunk_size,
+            &metadata.id,
+            &metadata.collection_metadata_id,
+        );
+        metadata.chunks = chunks.iter().map(|chunk| chunk.id.clone()).collect();
+        metadata.last_modified = provenance.fetched_at.clone();
+        provenance.content_hash = content_hash;
+
+        vector_database
+            .delete_documents_from_database(&config.database, &vec![metadata.id.clone()])
+            .await?;
+        statistics = Some(
+            vector_database
+                .add_document_chunks_to_database(&config.embedder, &config.database, chunks)
+                .await?,
+        );
+    }
+
+    let document_metadata_id: String = metadata.id.clone();
+    metadata.provenance = Some(provenance);
+    metadata_storage
+        .lock()
+        .await
+        .update_documents_with_new_chunks(vec![metadata])
+        .await?;
+    sync_document_payloads(
+        vector_database,
+        metadata_storage,
+        &config.database,
+        &[document_metadata_id],
+    )
+    .await?;
+
+    Ok(statistics)
+}
+
+/// Remove documents from both the database and the metadata storage.
+/// Used for undoing the partial writes of cancelled tasks.
+pub async fn remove_documents(
+    vector_database: &Arc<dyn VectorDatabase>,
+    metadata_storage: &Arc<Mutex<MetadataStorage>>,
+    database_config: &DatabaseConfig,
+    document_metadata_ids: &Vec<String>,
+) -> Result<()> {
+    {
+        let mut metadata_storage = metadata_storage.lock().await;
+        for document_metadata_id in document_metadata_ids {
+            metadata_storage.remove_document(document_metadata_id).await;
+        }
+    }
+
+    vector_database
+        .delete_documents_from_database(database_config, document_metadata_ids)
+        .await
+}
+
+/// Remove the given collections that have no documents, along with the user's access to them.
+/// Used for undoing the collections that cancelled imports created.
+pub async fn remove_empty_collections(
+    metadata_storage: &Arc<Mutex<MetadataStorage>>,
+    identities_storage: &Arc<Mutex<IdentitiesStorage>>,
+    username: &str,
+    collection_metadata_ids: &[String],
+) -> Result<()> {
+    let mut removed_collection_metadata_ids: Vec<String> = Vec::new();
+    {
+        let mut metadata_storage = metadata_storage.lock().await;
+        for collection_metadata_id in collection_metadata_ids {
+            let is_empty: bool = metadata_storage
+                .collections
+                .get(collection_metadata_id)
+                .is_some_and(|collection| collection.documents_metadata_ids.is_empty());
+            if is_empty {
+                metadata_storage
+                    .delete_collection(collection_metadata_id)
+                    .await;
+                removed_collection_metadata_ids.push(collection_metadata_id.clone());
+            }
+        }
+    }
+
+    if removed_collection_metadata_ids.is_empty() {
+        return Ok(());
+    }
+
+    identities_storage
+        .lock()
+        .await
+        .remove_authorized_resources(username, removed_collection_metadata_ids)
+        .await
+}
+
+/// Copy the current metadata of the documents onto their chunks in the database,
+/// so that the search filters see the latest titles, tags and dates.
+pub async fn sync_document_payloads(
+    vector_database: &Arc<dyn VectorDatabase>,
+    metadata_storage: &Arc<Mutex<MetadataStorage>>,
+    database_config: &DatabaseConfig,
+    document_metadata_ids: &[String],
+) -> Result<()> {
+    let payloads = metadata_storage
+        .lock()
+        .await
+        .get_document_payloads(document_metadata_ids);
+
+    vector_database
+        .update_document_payloads(database_config, payloads)
+        .await
+}
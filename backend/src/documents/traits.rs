@@ -11,6 +11,7 @@ pub trait ValidateDataMutabilitiesForAPICaller {
 pub enum IndexableField {
     Keyword(String),
     FullText(String),
+    Integer(String),
 }
 
 /// Get the fields that will be indexed in the database
@@ -1,9 +1,266 @@
-use std::time::Duration;
+use std::{ops::AddAssign, time::Duration};
 
 use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use tokio::sync::Semaphore;
 
-use crate::documents::document_chunk::DocumentChunk;
+use crate::{
+    configurations::system::{EmbedderConfig, EmbedderRetryConfig},
+    documents::document_chunk::DocumentChunk,
+};
+
+/// How much effort it took to vectorize the document chunks.
+/// It is reported in the task results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorizationStatistics {
+    /// Requests that were sent again after transient failures
+    pub retries: usize,
+    /// Chunks that were too long for the embedding model, so they were split
+    pub splits: usize,
+    /// Chunks whose vectors were taken from the embedding cache, instead of being vectorized again
+    #[serde(default)]
+    pub cached: usize,
+}
+
+impl AddAssign for VectorizationStatistics {
+    fn add_assign(&mut self, other: Self) {
+        self.retries += other.retries;
+        self.splits += other.splits;
+        self.cached += other.cached;
+    }
+}
+
+/// The embedding service has responded with an error status
+#[derive(Debug)]
+pub struct EmbedderResponseError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl std::fmt::Display for EmbedderResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for EmbedderResponseError {}
+
+enum VectorizationErrorKind {
+    /// Timeouts, rate limits and server errors, which may succeed later
+    Transient,
+    /// The input is longer than the context window of the embedding model
+    InputTooLong,
+    Permanent,
+}
+
+fn classify_vectorization_error(error: &anyhow::Error) -> VectorizationErrorKind {
+    if error
+        .to_string()
+        .to_lowercase()
+        .contains("reduce the length of the input")
+    {
+        return VectorizationErrorKind::InputTooLong;
+    }
+
+    if let Some(error) = error.downcast_ref::<EmbedderResponseError>() {
+        if error.status == 429 || error.status >= 500 {
+            return VectorizationErrorKind::Transient;
+        }
+
+        return VectorizationErrorKind::Permanent;
+    }
+
+    // Connection errors and timeouts, or errors from the providers, which are not structured
+    VectorizationErrorKind::Transient
+}
+
+/// Exponential backoff with equal jitter, so that the concurrent batches do not retry at once
+fn get_backoff_delay(retry_config: &EmbedderRetryConfig, attempt: usize) -> Duration {
+    let exponent: u32 = attempt.saturating_sub(1).min(16) as u32;
+    let delay: u64 = retry_config
+        .initial_backoff_milliseconds
+        .saturating_mul(2u64.pow(exponent))
+        .min(retry_config.max_backoff_milliseconds);
+
+    Duration::from_millis(delay / 2 + rand::random_range(0..=delay / 2))
+}
+
+/// Split a text in halves, preferably at a whitespace to keep the words whole
+fn split_text(text: &str) -> Option<(String, String)> {
+    let characters: Vec<char> = text.chars().collect();
+    if characters.len() < 2 {
+        return None;
+    }
+
+    let middle: usize = characters.len() / 2;
+    let index: usize = ((middle / 2).max(1)..middle)
+        .rev()
+        .find(|index| characters[*index].is_whitespace())
+        .unwrap_or(middle);
+
+    Some((
+        characters[..index].iter().collect(),
+        characters[index..].iter().collect(),
+    ))
+}
+
+/// Average the vectors, weighted by the lengths of their texts
+fn combine_vectors(vectors: &[Vec<f32>], weights: &[usize]) -> Vec<f32> {
+    let total: f32 = weights.iter().sum::<usize>() as f32;
+    let mut combined: Vec<f32> = vec![0.0; vectors.first().map_or(0, |vector| vector.len())];
+    for (vector, weight) in vectors.iter().zip(weights) {
+        for (value, combined_value) in vector.iter().zip(combined.iter_mut()) {
+            *combined_value += value * (*weight as f32) / total;
+        }
+    }
+
+    combined
+}
+
+/// Vectorize the texts of the document chunks, each with the document prefix of the model
+async fn request_vectors(embedder_config: &EmbedderConfig, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    let document_prefix: &str = embedder_config.get_document_prefix();
+    let queries: Vec<DocumentChunk> = texts
+        .iter()
+        .map(|text| DocumentChunk::new(format!("{}{}", document_prefix, text), "", ""))
+        .collect();
+
+    if !embedder_config.provider.is_empty() {
+        send_vectorization_queries_to_multiple_providers(
+            &embedder_config.api_key,
+            &embedder_config.model,
+            &embedder_config.provider,
+            None,
+            &queries,
+        )
+        .await
+    } else {
+        send_vectorization_queries(
+            &embedder_config.base_url,
+            &embedder_config.api_key,
+            &embedder_config.model,
+            &embedder_config.encoding_format,
+            &queries,
+        )
+        .await
+    }
+}
+
+/// Vectorize the texts, retrying the transient failures.
+/// When the input is too long, the batch is halved until the offending text is found,
+/// then the text is split and its vector is combined from the vectors of its parts.
+/// Each request holds one of the embedding permits, which is released before backing off.
+async fn vectorize_texts(
+    embedder_config: &EmbedderConfig,
+    embedding_permits: &Semaphore,
+    texts: Vec<String>,
+    split_depth: usize,
+    statistics: &mut VectorizationStatistics,
+) -> Result<Vec<Vec<f32>>> {
+    let retry_config: &EmbedderRetryConfig = &embedder_config.retry;
+    let mut attempt: usize = 0;
+    let error: anyhow::Error = loop {
+        let result: Result<Vec<Vec<f32>>> = {
+            let _permit = embedding_permits.acquire().await?;
+            request_vectors(embedder_config, &texts).await
+        };
+        match result {
+            Ok(result) => return Ok(result),
+            Err(error) => match classify_vectorization_error(&error) {
+                VectorizationErrorKind::Transient if attempt < retry_config.max_retries => {
+                    attempt += 1;
+                    statistics.retries += 1;
+                    let delay: Duration = get_backoff_delay(retry_config, attempt);
+                    log::warn!(
+                        "Vectorization failed due to {}. Retry {}/{} in {} ms",
+                        error,
+                        attempt,
+                        retry_config.max_retries,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                VectorizationErrorKind::InputTooLong => break error,
+                _ => {
+                    log::error!("Vectorization failed due to {}", error);
+                    return Err(error);
+                }
+            },
+        }
+    };
+
+    if texts.len() > 1 {
+        let mut left: Vec<String> = texts;
+        let right: Vec<String> = left.split_off(left.len() / 2);
+        let mut vectors: Vec<Vec<f32>> =
+            Box::pin(vectorize_texts(
+                embedder_config,
+                embedding_permits,
+                left,
+                split_depth,
+                statistics,
+            ))
+            .await?;
+        vectors.extend(
+            Box::pin(vectorize_texts(
+                embedder_config,
+                embedding_permits,
+                right,
+                split_depth,
+                statistics,
+            ))
+            .await?,
+        );
+
+        return Ok(vectors);
+    }
+
+    if split_depth >= retry_config.max_split_depth {
+        log::error!(
+            "A chunk is still too long after being split {} times. Please set the chunk size smaller",
+            split_depth
+        );
+        return Err(error);
+    }
+
+    let (left, right) = match texts.first().and_then(|text| split_text(text)) {
+        Some(result) => result,
+        None => return Err(error),
+    };
+    statistics.splits += 1;
+    log::warn!("A chunk is too long for the embedding model, so it is split and retried");
+
+    let weights: Vec<usize> = vec![left.chars().count(), right.chars().count()];
+    let vectors: Vec<Vec<f32>> = Box::pin(vectorize_texts(
+        embedder_config,
+        embedding_permits,
+        vec![left, right],
+        split_depth + 1,
+        statistics,
+    ))
+    .await?;
+
+    Ok(vec![combine_vectors(&vectors, &weights)])
+}
+
+/// Vectorize the document chunks with retries, see `EmbedderRetryConfig`
+pub async fn send_vectorization_with_retries(
+    embedder_config: &EmbedderConfig,
+    embedding_permits: &Semaphore,
+    mut queries: Vec<DocumentChunk>,
+) -> Result<(Vec<DocumentChunk>, VectorizationStatistics)> {
+    let mut statistics = VectorizationStatistics::default();
+    let texts: Vec<String> = queries.iter().map(|item| item.content.clone()).collect();
+    let vectors: Vec<Vec<f32>> =
+        vectorize_texts(embedder_config, embedding_permits, texts, 0, &mut statistics).await?;
+
+    for (vector, chunk) in vectors.into_iter().zip(&mut queries) {
+        chunk.dense_text_vector = vector;
+    }
+
+    Ok((queries, statistics))
+}
 
 pub async fn send_vectorization(
     provider: &str,
@@ -104,11 +361,14 @@ pub async fn send_vectorization_queries(
                 log::error!("Error response body: {}", error_response_body);
             }
 
-            return Err(anyhow!(
-                "Vectorization request has failed. Error: {}. Message: {}",
-                error,
-                error_response_body
-            ));
+            return Err(EmbedderResponseError {
+                status: error.status().map_or(0, |status| status.as_u16()),
+                message: format!(
+                    "Vectorization request has failed. Error: {}. Message: {}",
+                    error, error_response_body
+                ),
+            }
+            .into());
         }
     }
 
@@ -130,3 +390,37 @@ pub async fn send_vectorization_queries(
 
     Ok(vectors)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text_and_combine_vectors() {
+        let (left, right) = split_text("the quick brown fox jumps").unwrap();
+        assert_eq!(format!("{}{}", left, right), "the quick brown fox jumps");
+        assert!(right.starts_with(' '));
+        assert!(split_text("a").is_none());
+
+        let (left, right) = split_text("你好世界").unwrap();
+        assert_eq!((left.as_str(), right.as_str()), ("你好", "世界"));
+
+        let combined: Vec<f32> = combine_vectors(&[vec![1.0, 0.0], vec![0.0, 1.0]], &[3, 1]);
+        assert_eq!(combined, vec![0.75, 0.25]);
+    }
+
+    #[test]
+    fn test_backoff_delay_is_capped() {
+        let retry_config = EmbedderRetryConfig {
+            max_retries: 10,
+            initial_backoff_milliseconds: 100,
+            max_backoff_milliseconds: 1000,
+            max_split_depth: 4,
+        };
+
+        let delay: Duration = get_backoff_delay(&retry_config, 1);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        let delay: Duration = get_backoff_delay(&retry_config, 10);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1000));
+    }
+}
@@ -0,0 +1,297 @@
+//! Caches of the embedding vectors, so that the same text is not vectorized twice by the same model.
+//! Query vectors are kept in memory, while chunk vectors are kept on disk by the hashes of their contents,
+//! so that re-indexing, updating and restoring documents only pay for the chunks that have changed.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    configurations::system::EmbeddingCacheConfig, connectors::provenance::hash_content,
+    traits::{LoadAndSave, write_file_atomically},
+};
+
+/// Least recently used query vectors, keyed by the model and the query
+#[derive(Debug, Default)]
+pub struct QueryEmbeddingCache {
+    capacity: usize,
+    /// Increases on every access, to tell which entry was used the least recently
+    tick: u64,
+    entries: HashMap<(String, String), (Vec<f32>, u64)>,
+}
+
+impl QueryEmbeddingCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn get(&mut self, model: &str, query: &str) -> Option<Vec<f32>> {
+        self.tick += 1;
+        let (vector, last_used) = self
+            .entries
+            .get_mut(&(model.to_string(), query.to_string()))?;
+        *last_used = self.tick;
+
+        Some(vector.clone())
+    }
+
+    pub fn insert(&mut self, model: &str, query: &str, vector: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+        let key: (String, String) = (model.to_string(), query.to_string());
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && let Some(least_recently_used) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&least_recently_used);
+        }
+
+        self.entries.insert(key, (vector, self.tick));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedEmbedding {
+    pub vector: Vec<f32>,
+    pub last_used: u64,
+}
+
+/// Chunk vectors on disk, keyed by the model, then by the hash of the chunk content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkEmbeddingCache {
+    pub path: PathBuf,
+    pub tick: u64,
+    pub models: HashMap<String, HashMap<String, CachedEmbedding>>,
+    /// Vectors are saved in batches, rather than every time they are inserted
+    #[serde(skip)]
+    pub has_unsaved_vectors: bool,
+}
+
+impl LoadAndSave for ChunkEmbeddingCache {
+    fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            tick: 0,
+            models: HashMap::new(),
+            has_unsaved_vectors: false,
+        }
+    }
+
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The cache can be large, so it is saved compactly
+    fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+impl ChunkEmbeddingCache {
+    /// Cached vectors of the texts, in the same order. Texts that are not cached get `None`.
+    pub fn get_vectors(&mut self, model: &str, texts: &[String]) -> Vec<Option<Vec<f32>>> {
+        self.tick += 1;
+        let tick: u64 = self.tick;
+        let entries: Option<&mut HashMap<String, CachedEmbedding>> = self.models.get_mut(model);
+        let Some(entries) = entries else {
+            return vec![None; texts.len()];
+        };
+
+        texts
+            .iter()
+            .map(|text| {
+                let entry: &mut CachedEmbedding = entries.get_mut(&hash_content(text))?;
+                entry.last_used = tick;
+                Some(entry.vector.clone())
+            })
+            .collect()
+    }
+
+    /// Cache the vectors of the texts, then drop the least recently used ones beyond `max_entries`
+    pub fn insert_vectors(
+        &mut self,
+        model: &str,
+        texts: &[String],
+        vectors: &[Vec<f32>],
+        max_entries: usize,
+    ) {
+        self.tick += 1;
+        let entries: &mut HashMap<String, CachedEmbedding> =
+            self.models.entry(model.to_string()).or_default();
+        for (text, vector) in texts.iter().zip(vectors) {
+            entries.insert(
+                hash_content(text),
+                CachedEmbedding {
+                    vector: vector.clone(),
+                    last_used: self.tick,
+                },
+            );
+        }
+
+        self.evict(max_entries);
+        self.has_unsaved_vectors = true;
+    }
+
+    pub fn count_entries(&self) -> usize {
+        self.models.values().map(|entries| entries.len()).sum()
+    }
+
+    fn evict(&mut self, max_entries: usize) {
+        let excess: usize = self.count_entries().saturating_sub(max_entries);
+        if excess == 0 {
+            return;
+        }
+
+        let mut keys: Vec<(u64, String, String)> = self
+            .models
+            .iter()
+            .flat_map(|(model, entries)| {
+                entries
+                    .iter()
+                    .map(|(hash, entry)| (entry.last_used, model.clone(), hash.clone()))
+            })
+            .collect();
+        keys.sort_unstable();
+
+        for (_, model, hash) in keys.into_iter().take(excess) {
+            if let Some(entries) = self.models.get_mut(&model) {
+                entries.remove(&hash);
+            }
+        }
+        self.models.retain(|_, entries| !entries.is_empty());
+    }
+}
+
+/// Both caches, shared by the searches and the vectorization of the document chunks
+pub struct EmbeddingCache {
+    pub config: EmbeddingCacheConfig,
+    pub queries: Mutex<QueryEmbeddingCache>,
+    pub chunks: Mutex<ChunkEmbeddingCache>,
+}
+
+impl EmbeddingCache {
+    /// A cache file that can't be read is only a loss of the cached vectors, so it starts empty instead
+    pub fn new(config: &EmbeddingCacheConfig) -> Self {
+        let chunks: ChunkEmbeddingCache = match ChunkEmbeddingCache::load(&config.path) {
+            Ok(result) => result,
+            Err(error) => {
+                log::warn!(
+                    "Failed to load the embedding cache at `{}`, so it starts empty: {}",
+                    config.path,
+                    error
+                );
+                ChunkEmbeddingCache::new(&config.path)
+            }
+        };
+
+        Self {
+            config: config.clone(),
+            queries: Mutex::new(QueryEmbeddingCache::new(config.max_query_entries)),
+            chunks: Mutex::new(chunks),
+        }
+    }
+
+    /// Save the chunk vectors inserted since the last save, if any.
+    /// Only the serialization holds the lock, so the vectorization is not held up by the writing.
+    pub async fn save_inserted_vectors(&self) -> Result<()> {
+        let (path, buffer): (PathBuf, Vec<u8>) = {
+            let mut chunks = self.chunks.lock().await;
+            if !chunks.has_unsaved_vectors {
+                return Ok(());
+            }
+            chunks.has_unsaved_vectors = false;
+            (chunks.path.clone(), chunks.to_json()?)
+        };
+
+        if let Err(error) = write_file_atomically(&path, &buffer).await {
+            // Try again on the next save
+            self.chunks.lock().await.has_unsaved_vectors = true;
+            return Err(error);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::TemporaryFile;
+
+    #[test]
+    fn test_query_embedding_cache_evicts_least_recently_used() {
+        let mut cache = QueryEmbeddingCache::new(2);
+        cache.insert("model", "rust", vec![1.0]);
+        cache.insert("model", "tea", vec![2.0]);
+        // Using `rust` makes `tea` the least recently used
+        assert_eq!(cache.get("model", "rust"), Some(vec![1.0]));
+        cache.insert("model", "coffee", vec![3.0]);
+
+        assert_eq!(cache.get("model", "tea"), None);
+        assert_eq!(cache.get("model", "coffee"), Some(vec![3.0]));
+        // Keyed by the model as well
+        assert_eq!(cache.get("another model", "rust"), None);
+    }
+
+    #[test]
+    fn test_chunk_embedding_cache() {
+        let mut cache = ChunkEmbeddingCache::new("./data/embedding_cache.json");
+        let texts: Vec<String> = vec!["rust".to_string(), "tea".to_string()];
+        cache.insert_vectors("model", &texts, &[vec![1.0], vec![2.0]], 3);
+
+        let lookup: Vec<String> = vec!["tea".to_string(), "coffee".to_string()];
+        assert_eq!(cache.get_vectors("model", &lookup), vec![Some(vec![2.0]), None]);
+        assert_eq!(cache.get_vectors("another model", &lookup), vec![None, None]);
+
+        // `rust` was not used since it was inserted, so it goes first
+        cache.insert_vectors(
+            "model",
+            &["coffee".to_string(), "milk".to_string()],
+            &[vec![3.0], vec![4.0]],
+            3,
+        );
+        assert_eq!(cache.count_entries(), 3);
+        assert_eq!(cache.get_vectors("model", &texts), vec![None, Some(vec![2.0])]);
+    }
+
+    #[tokio::test]
+    async fn test_save_inserted_vectors() {
+        let path = TemporaryFile::new("embedding_cache");
+        let config = EmbeddingCacheConfig {
+            path: path.to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+
+        // A corrupt file, as if the server crashed while writing it
+        std::fs::write(&path, "{\"path\": ").unwrap();
+        let cache = EmbeddingCache::new(&config);
+        assert_eq!(cache.chunks.lock().await.count_entries(), 0);
+
+        let texts: Vec<String> = vec!["rust".to_string()];
+        cache
+            .chunks
+            .lock()
+            .await
+            .insert_vectors("model", &texts, &[vec![1.0]], 10);
+        cache.save_inserted_vectors().await.unwrap();
+        assert!(!cache.chunks.lock().await.has_unsaved_vectors);
+
+        let mut saved = EmbeddingCache::new(&config).chunks.into_inner();
+        assert_eq!(saved.get_vectors("model", &texts), vec![Some(vec![1.0])]);
+    }
+}
@@ -12,11 +12,12 @@ use crate::{
         callbacks::GenericResponse,
     },
     app_state::AppState,
-    backup::{base::Backup, list_item::BackupListItem},
+    backup::{base::Backup, list_item::BackupListItem, scope::BackupScopeIndicator},
     documents::{
         collection_metadata::CollectionMetadata,
         document_chunk::DocumentChunk,
         document_metadata::DocumentMetadata,
+        operations::sync_document_payloads,
     },
     identities::user::User,
     tasks_scheduler::TaskStatus,
@@ -93,126 +94,155 @@ pub async fn backup(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(Some(&request.scope.id)).await;
     let task_id_cloned = task_id.clone();
 
-    tokio::spawn(async move {
-        // Pull what we need out of AppState without holding the lock during I/O
-        let (
-            vector_database,
-            metadata_storage,
-            tasks_scheduler,
-            _,
-            identities_storage,
-            backups_storage,
-        ) = acquire_data(&data).await;
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue
+        .submit(&task_id_cloned, run_backup_task(data, task_id, request.0.scope))
+        .await;
 
-        let user_information_snapshots: Vec<User> = identities_storage
-            .lock()
-            .await
-            .users
-            .iter()
-            .filter(|item| item.username == request.0.scope.id)
-            .map(|item| item.to_owned())
-            .collect();
+    // Return an immediate response with a task id
+    Ok(HttpResponse::Ok()
+        .json(GenericResponse::in_progress(task_id_cloned))
+        .into())
+}
 
-        if user_information_snapshots.is_empty() {
-            // Failed to fetch user information when trying to backup, need to use the pre-acquired variables instead
-            log::error!(
-                "Can't fetch user information when trying to backup: no backup targets found"
-            );
-            tasks_scheduler.lock().await.update_status_by_task_id(
-                &task_id,
-                TaskStatus::Failed,
-                Some("no backup targets found".to_string()),
-            );
-            return;
-        }
+/// Back up the resources in the scope. Shared by the endpoint and the scheduled jobs.
+pub async fn run_backup_task(
+    data: web::Data<RwLock<AppState>>,
+    task_id: String,
+    scope: BackupScopeIndicator,
+) {
+    // Pull what we need out of AppState without holding the lock during I/O
+    let (
+        vector_database,
+        metadata_storage,
+        tasks_scheduler,
+        _,
+        identities_storage,
+        backups_storage,
+    ) = acquire_data(&data).await;
+
+    // Collecting snapshots, fetching chunks, and saving the backup
+    tasks_scheduler
+        .lock()
+        .await
+        .update_progress_by_task_id(&task_id, "collecting", 0, 3);
 
-        let mut collection_metadata_snapshots: HashMap<String, CollectionMetadata> =
-            metadata_storage.lock().await.collections.clone();
-        let mut document_metadata_snapshots: HashMap<String, DocumentMetadata> =
-            metadata_storage.lock().await.documents.clone();
-
-        for user_information_snapshot in user_information_snapshots.iter() {
-            let mut collection_metadata_ids: Vec<String> = Vec::new();
-
-            collection_metadata_snapshots = collection_metadata_snapshots
-                .into_iter()
-                .filter(|(collection_metadata_id, _)| {
-                    let is_contained: bool = user_information_snapshot
-                        .resources
-                        .contains(collection_metadata_id);
-
-                    if is_contained {
-                        collection_metadata_ids.push(collection_metadata_id.clone());
-                    }
-
-                    is_contained
-                })
-                .collect();
-
-            document_metadata_snapshots = document_metadata_snapshots
-                .into_iter()
-                .filter(|(_, document_metadata)| {
-                    collection_metadata_ids.contains(&&document_metadata.collection_metadata_id)
-                })
-                .collect();
-        }
+    let user_information_snapshots: Vec<User> = identities_storage
+        .lock()
+        .await
+        .users
+        .iter()
+        .filter(|item| item.username == scope.id)
+        .map(|item| item.to_owned())
+        .collect();
 
-        // Backup database entries
-        let document_chunks_ids: Vec<String> = document_metadata_snapshots
-            .iter()
-            .flat_map(|(_, document_metadata)| document_metadata.chunks.clone())
-            .collect();
-        
-        let document_chunks_snapshots: Vec<DocumentChunk> =
-            match vector_database.get_document_chunks(document_chunks_ids).await {
-                Ok(points) => points,
-                Err(e) => {
-                    // Failed to get document chunks when trying to backup, need to use the pre-acquired variables instead
-                    log::error!("Can't get document chunks when trying to backup: {}", e);
-                    tasks_scheduler.lock().await.update_status_by_task_id(
-                        &task_id,
-                        TaskStatus::Failed,
-                        Some(e.to_string()),
-                    );
-                    return;
-                }
-            };
-
-        let backup: Backup = Backup::new(
-            request.0.scope.clone(),
-            user_information_snapshots,
-            collection_metadata_snapshots,
-            document_metadata_snapshots,
-            document_chunks_snapshots,
+    if user_information_snapshots.is_empty() {
+        // Failed to fetch user information when trying to backup, need to use the pre-acquired variables instead
+        log::error!(
+            "Can't fetch user information when trying to backup: no backup targets found"
         );
-        let backup_id = backup.id.clone();
+        tasks_scheduler.lock().await.update_status_by_task_id(
+            &task_id,
+            TaskStatus::Failed,
+            Some("no backup targets found".to_string()),
+        ).await;
+        return;
+    }
 
-        match backups_storage.lock().await.add_backup(backup).await {
-            Ok(_) => {}
+    let mut collection_metadata_snapshots: HashMap<String, CollectionMetadata> =
+        metadata_storage.lock().await.collections.clone();
+    let mut document_metadata_snapshots: HashMap<String, DocumentMetadata> =
+        metadata_storage.lock().await.documents.clone();
+
+    for user_information_snapshot in user_information_snapshots.iter() {
+        let mut collection_metadata_ids: Vec<String> = Vec::new();
+
+        collection_metadata_snapshots = collection_metadata_snapshots
+            .into_iter()
+            .filter(|(collection_metadata_id, _)| {
+                let is_contained: bool = user_information_snapshot
+                    .resources
+                    .contains(collection_metadata_id);
+
+                if is_contained {
+                    collection_metadata_ids.push(collection_metadata_id.clone());
+                }
+
+                is_contained
+            })
+            .collect();
+
+        document_metadata_snapshots = document_metadata_snapshots
+            .into_iter()
+            .filter(|(_, document_metadata)| {
+                collection_metadata_ids.contains(&&document_metadata.collection_metadata_id)
+            })
+            .collect();
+    }
+
+    // Backup database entries
+    let document_chunks_ids: Vec<String> = document_metadata_snapshots
+        .iter()
+        .flat_map(|(_, document_metadata)| document_metadata.chunks.clone())
+        .collect();
+    
+    tasks_scheduler
+        .lock()
+        .await
+        .update_progress_by_task_id(&task_id, "fetching", 1, 3);
+    let document_chunks_snapshots: Vec<DocumentChunk> =
+        match vector_database.get_document_chunks(document_chunks_ids).await {
+            Ok(points) => points,
             Err(e) => {
-                log::error!("Failed to save backup: {}", e);
+                // Failed to get document chunks when trying to backup, need to use the pre-acquired variables instead
+                log::error!("Can't get document chunks when trying to backup: {}", e);
                 tasks_scheduler.lock().await.update_status_by_task_id(
                     &task_id,
                     TaskStatus::Failed,
-                    Some(format!("Failed to save backup: {}", e)),
-                );
+                    Some(e.to_string()),
+                ).await;
                 return;
             }
         };
 
-        tasks_scheduler.lock().await.set_status_to_complete(
-            &task_id,
-            serde_json::to_value(BackupResponse { backup_id }).unwrap(),
-        );
-    });
+    let backup: Backup = Backup::new(
+        scope,
+        user_information_snapshots,
+        collection_metadata_snapshots,
+        document_metadata_snapshots,
+        document_chunks_snapshots,
+    );
+    let backup_id = backup.id.clone();
+    tasks_scheduler
+        .lock()
+        .await
+        .update_progress_by_task_id(&task_id, "saving", 2, 3);
+    if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+        log::info!("Task {} was cancelled before saving the backup", task_id);
+        tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+        return;
+    }
 
-    // Return an immediate response with a task id
-    Ok(HttpResponse::Ok()
-        .json(GenericResponse::in_progress(task_id_cloned))
-        .into())
+    match backups_storage.lock().await.add_backup(backup).await {
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Failed to save backup: {}", e);
+            tasks_scheduler.lock().await.update_status_by_task_id(
+                &task_id,
+                TaskStatus::Failed,
+                Some(format!("Failed to save backup: {}", e)),
+            ).await;
+            return;
+        }
+    };
+
+    tasks_scheduler.lock().await.set_status_to_complete(
+        &task_id,
+        serde_json::to_value(BackupResponse { backup_id }).unwrap(),
+    ).await;
 }
 
 // Async endpoint
@@ -226,10 +256,11 @@ pub async fn restore_backup(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(request.username.as_deref()).await;
     let task_id_cloned = task_id.clone();
 
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (
             vector_database,
@@ -253,7 +284,7 @@ pub async fn restore_backup(
                     &task_id,
                     TaskStatus::Failed,
                     Some("backup not found".to_string()),
-                );
+                ).await;
                 return;
             }
         };
@@ -298,7 +329,7 @@ pub async fn restore_backup(
                         "Failed to delete old documents from database: {}",
                         e
                     )),
-                );
+                ).await;
                 return;
             }
         }
@@ -310,6 +341,8 @@ pub async fn restore_backup(
             .users
             .extend(backup.user_information_snapshots);
 
+        let restored_document_metadata_ids: Vec<String> =
+            backup.document_metadata_snapshots.keys().cloned().collect();
         {
             let mut metadata_storage = metadata_storage.lock().await;
             metadata_storage
@@ -337,11 +370,22 @@ pub async fn restore_backup(
                     &task_id,
                     TaskStatus::Failed,
                     Some(format!("Failed to add document chunks to database: {}", e)),
-                );
+                ).await;
                 return;
             }
         }
 
+        if let Err(e) = sync_document_payloads(
+            &vector_database,
+            &metadata_storage,
+            &config.database,
+            &restored_document_metadata_ids,
+        )
+        .await
+        {
+            log::error!("Failed to update the document payloads during restore: {}", e);
+        }
+
         match metadata_storage.lock().await.save().await {
             Ok(_) => {}
             Err(e) => {
@@ -350,7 +394,7 @@ pub async fn restore_backup(
                     &task_id,
                     TaskStatus::Failed,
                     Some(format!("Failed to save metadata storage: {}", e)),
-                );
+                ).await;
                 return;
             }
         };
@@ -363,7 +407,7 @@ pub async fn restore_backup(
                     &task_id,
                     TaskStatus::Failed,
                     Some(format!("Failed to save metadata storage: {}", e)),
-                );
+                ).await;
                 return;
             }
         }
@@ -371,8 +415,8 @@ pub async fn restore_backup(
         tasks_scheduler
             .lock()
             .await
-            .set_status_to_complete(&task_id, serde_json::to_value("").unwrap());
-    });
+            .set_status_to_complete(&task_id, serde_json::to_value("").unwrap()).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
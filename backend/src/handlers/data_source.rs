@@ -0,0 +1,100 @@
+use actix_web::{HttpResponse, Result, web};
+use tokio::sync::RwLock;
+
+use crate::{
+    api_models::{
+        callbacks::GenericResponse,
+        data_source::{
+            CreateDataSourceRequest, CreateDataSourceResponse, DataSourceResponse,
+            DeleteDataSourceRequest, GetDataSourcesRequest,
+        },
+    },
+    app_state::AppState,
+    data_sources::data_source::DataSource,
+};
+
+// Sync endpoint
+pub async fn create_data_source(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<CreateDataSourceRequest>,
+) -> Result<HttpResponse> {
+    let (data_sources_storage, encryption_key) = {
+        let state = data.read().await;
+        (
+            state.data_sources_storage.clone(),
+            state.config.data_sources_storage.encryption_key.clone(),
+        )
+    };
+
+    let request = request.into_inner();
+    let data_source: DataSource = match DataSource::new(
+        request.name,
+        request.username,
+        request.import_type,
+        request.artifact,
+        &encryption_key,
+    ) {
+        Ok(result) => result,
+        Err(error) => {
+            log::error!("Failed to create a data source: {}", error);
+            return Ok(
+                HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string()))
+            );
+        }
+    };
+
+    match data_sources_storage
+        .lock()
+        .await
+        .add_data_source(data_source)
+        .await
+    {
+        Ok(data_source_id) => Ok(HttpResponse::Ok().json(GenericResponse::succeed(
+            "".to_string(),
+            &CreateDataSourceResponse { data_source_id },
+        ))),
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn delete_data_source(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<DeleteDataSourceRequest>,
+) -> Result<HttpResponse> {
+    let data_sources_storage = data.read().await.data_sources_storage.clone();
+
+    match data_sources_storage
+        .lock()
+        .await
+        .remove_data_source(&request.data_source_id, &request.username)
+        .await
+    {
+        Ok(_) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &"".to_string())))
+        }
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn get_data_sources(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<GetDataSourcesRequest>,
+) -> Result<HttpResponse> {
+    let data_sources_storage = data.read().await.data_sources_storage.clone();
+
+    let data_sources: Vec<DataSourceResponse> = data_sources_storage
+        .lock()
+        .await
+        .get_data_sources_by_owner(&request.username)
+        .into_iter()
+        .map(DataSourceResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &data_sources)))
+}
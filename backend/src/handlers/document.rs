@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use actix_web::{HttpResponse, Result, web};
 use futures::future::join_all;
 use log::{error, info};
-use serde_json::json;
+use serde_json::{Value, json};
 use tokio::sync::RwLock;
 
 use crate::{
@@ -11,25 +11,28 @@ use crate::{
         callbacks::GenericResponse,
         document::{
             AddDocumentRequest, AddDocumentResponse, DeleteDocumentRequest, DeleteDocumentResponse,
-            GetDocumentRequest, GetDocumentsMetadataQuery, ReindexRequest, ReindexResponse,
+            GetDocumentRequest, GetDocumentsMetadataQuery, RefreshCollectionRequest,
+            RefreshDocumentRequest, RefreshDocumentsResponse, ReindexRequest, ReindexResponse,
             UpdateDocumentContentRequest, UpdateDocumentMetadataRequest, UpdateDocumentResponse,
         },
     },
     app_state::AppState,
     configurations::user::UserConfigurations,
+    constants::TASK_BATCH_SIZE,
     connectors::{
         models::ImportTaskIntermediate,
-        relationship_database::RelationshipDatabaseConnector,
-        requests::{ImportDocumentsRequest, ImportTask, ImportType},
+        provenance::{DocumentProvenance, hash_content},
+        requests::{ImportDocumentsRequest, ImportTask},
         responses::ImportDocumentsResponse,
-        text_file::TextFileConnector,
-        traits::Connector,
-        webpage::WebpageConnector,
     },
     documents::{
         document_chunk::DocumentChunk, document_metadata::DocumentMetadata,
-        operations::preprocess_document,
+        operations::{
+            get_or_create_collection_by_title, preprocess_document, refresh_document_from_source,
+            remove_documents, remove_empty_collections, sync_document_payloads,
+        },
     },
+    embedder::VectorizationStatistics,
     tasks_scheduler::TaskStatus,
     utilities::acquire_data,
 };
@@ -44,11 +47,12 @@ pub async fn add_document(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(Some(&request.username)).await;
     let task_id_cloned = task_id.clone();
 
     // Perform operations asynchronously
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
             acquire_data(&data).await;
@@ -70,7 +74,7 @@ pub async fn add_document(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
         };
@@ -82,11 +86,11 @@ pub async fn add_document(
             user_configurations.search.document_chunk_size,
         );
 
-        match vector_database
+        let vectorization_statistics: VectorizationStatistics = match vector_database
             .add_document_chunks_to_database(&config.embedder, &config.database, chunks)
             .await
         {
-            Ok(_) => {
+            Ok(statistics) => {
                 match metadata_storage.lock().await.add_document(metadata).await {
                     Ok(_) => {}
                     Err(error) => {
@@ -95,11 +99,20 @@ pub async fn add_document(
                             &task_id,
                             TaskStatus::Failed,
                             Some(error.to_string()),
-                        );
+                        ).await;
                         return;
                     }
                 }
+                if let Err(error) = sync_document_payloads(
+                    &vector_database,
+                    &metadata_storage,
+                    &config.database,
+                    std::slice::from_ref(&metadata_id),
+                ).await {
+                    error!("Failed to update the document payloads: {}", error);
+                }
                 info!("Task {} has finished adding documents.", task_id);
+                statistics
             }
             Err(error) => {
                 // Failed to write the task status back to the scheduler, need to use the pre-acquired variables instead
@@ -108,19 +121,20 @@ pub async fn add_document(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
-        }
+        };
 
         tasks_scheduler.lock().await.set_status_to_complete(
             &task_id,
             serde_json::to_value(AddDocumentResponse {
                 document_metadata_id: metadata_id.clone(),
+                vectorization_statistics,
             })
             .unwrap(),
-        );
-    });
+        ).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
@@ -138,14 +152,16 @@ pub async fn import_documents(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(Some(&request.username)).await;
     let task_id_cloned = task_id.clone();
 
     // Perform operations asynchronously
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
             acquire_data(&data).await;
+        let data_sources_storage = data.read().await.data_sources_storage.clone();
 
         let user_configurations: UserConfigurations = match identities_storage
             .lock()
@@ -164,89 +180,245 @@ pub async fn import_documents(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
         };
 
+        // Fill in the credentials of the data sources
+        let mut artifacts: Vec<anyhow::Result<Value>> = Vec::new();
+        for import_task in request.0.imports.iter() {
+            artifacts.push(match &import_task.data_source_id {
+                Some(data_source_id) => data_sources_storage
+                    .lock()
+                    .await
+                    .get_data_source(data_source_id, &request.0.username)
+                    .and_then(|data_source| {
+                        data_source.resolve_artifact(
+                            &import_task.import_type,
+                            &import_task.artifact,
+                            &config.data_sources_storage.encryption_key,
+                        )
+                    }),
+                None => Ok(import_task.artifact.clone()),
+            });
+        }
+
         // Select a connector
+        tasks_scheduler.lock().await.update_progress_by_task_id(
+            &task_id,
+            "fetching",
+            0,
+            request.0.imports.len(),
+        );
         let mut import_tasks = Vec::new();
-        for import_task in request.0.imports.iter() {
-            import_tasks.push(match import_task.import_type {
-                ImportType::TextFile => {
-                    TextFileConnector::get_intermediate(import_task.artifact.clone())
-                }
-                ImportType::Webpage => {
-                    WebpageConnector::get_intermediate(import_task.artifact.clone())
-                }
-                ImportType::RelationshipDatabase => {
-                    RelationshipDatabaseConnector::get_intermediate(import_task.artifact.clone())
-                }
+        for (import_task, artifact) in request.0.imports.iter().zip(artifacts.iter()) {
+            let tasks_scheduler = &tasks_scheduler;
+            let task_id = &task_id;
+            import_tasks.push(async move {
+                let result = match artifact {
+                    Ok(artifact) => import_task.import_type.get_intermediates(artifact.clone()).await,
+                    Err(error) => Err(anyhow::anyhow!("{}", error)),
+                };
+                tasks_scheduler.lock().await.advance_progress_by_task_id(task_id);
+
+                result
             });
         }
 
-        // Get intermediates
-        let results = join_all(import_tasks).await;
+        // Get intermediates in batches, so that the task can be cancelled in between.
+        // Nothing has been written yet, so there is nothing to undo.
+        let mut results = Vec::new();
+        let mut import_tasks = import_tasks.into_iter().peekable();
+        while import_tasks.peek().is_some() {
+            if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+                info!("Task {} was cancelled when fetching the imports", task_id);
+                tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+                return;
+            }
+
+            let batch: Vec<_> = import_tasks.by_ref().take(TASK_BATCH_SIZE).collect();
+            results.extend(join_all(batch).await);
+        }
+
+        // One import task may yield multiple documents, so we keep the index of the
+        // import task that each document comes from for reporting failures.
         let mut preprocess_tasks = Vec::new();
         let mut failures: HashSet<ImportTask> = HashSet::new();
+        let mut unsupported_resources: Vec<String> = Vec::new();
+        // Collections created for this import, removed if the task is cancelled
+        let mut created_collection_metadata_ids: Vec<String> = Vec::new();
         for (index, result) in results.into_iter().enumerate() {
-            let result: ImportTaskIntermediate = match result {
-                Ok(intermediate) => intermediate,
+            let intermediates: Vec<ImportTaskIntermediate> = match result {
+                Ok(intermediates) => intermediates,
                 Err(err) => {
                     error!("Failed to get intermediate: {}", err);
-                    failures.insert(request.0.imports[index].clone());
+                    failures.insert(request.0.imports[index].masked());
                     continue;
                 }
             };
-            let request: ImportDocumentsRequest = request.clone();
-            preprocess_tasks.push(tokio::spawn(async move {
-                preprocess_document(
-                    &result.title,
-                    &result.content,
-                    &request.collection_metadata_id,
-                    user_configurations.search.document_chunk_size,
-                )
-            }));
+
+            let mut provenance: DocumentProvenance = match &artifacts[index] {
+                Ok(artifact) => {
+                    DocumentProvenance::new(request.0.imports[index].import_type.clone(), artifact)
+                }
+                Err(_) => continue,
+            };
+            provenance.data_source_id = request.0.imports[index].data_source_id.clone();
+
+            for mut result in intermediates {
+                unsupported_resources.append(&mut result.unsupported_resources);
+
+                // Map the notebooks in the sources to the user's collections
+                let collection_metadata_id: String = match &result.collection_title {
+                    Some(title) => {
+                        match get_or_create_collection_by_title(
+                            &metadata_storage,
+                            &identities_storage,
+                            &request.0.username,
+                            title,
+                        )
+                        .await
+                        {
+                            Ok((collection_metadata_id, created)) => {
+                                if created {
+                                    created_collection_metadata_ids
+                                        .push(collection_metadata_id.clone());
+                                }
+                                collection_metadata_id
+                            }
+                            Err(err) => {
+                                error!("Failed to get collection `{}`: {}", title, err);
+                                failures.insert(request.0.imports[index].masked());
+                                continue;
+                            }
+                        }
+                    }
+                    None => request.0.collection_metadata_id.clone(),
+                };
+
+                let mut provenance: DocumentProvenance = provenance.clone();
+                preprocess_tasks.push((
+                    index,
+                    tokio::spawn(async move {
+                        let (mut metadata, chunks, metadata_id) = preprocess_document(
+                            &result.title,
+                            &result.content,
+                            &collection_metadata_id,
+                            user_configurations.search.document_chunk_size,
+                        );
+                        metadata.attributes = result.attributes;
+                        metadata.tags = result.tags;
+                        if let Some(created_at) = result.created_at {
+                            metadata.created_at = created_at;
+                        }
+                        if let Some(last_modified) = result.last_modified {
+                            metadata.last_modified = last_modified;
+                        }
+                        provenance.content_hash = hash_content(&result.content);
+                        metadata.provenance = Some(provenance);
+
+                        (metadata, chunks, metadata_id)
+                    }),
+                ));
+            }
         }
 
         // Preprocess the intermediates
+        tasks_scheduler.lock().await.update_progress_by_task_id(
+            &task_id,
+            "embedding",
+            0,
+            preprocess_tasks.len(),
+        );
         let mut store_tasks = Vec::new();
-        for (index, task) in preprocess_tasks.into_iter().enumerate() {
+        let mut store_tasks_indices = Vec::new();
+        for (index, task) in preprocess_tasks.into_iter() {
             match task.await {
                 Ok((metadata, chunks, _)) => {
-                    store_tasks.push({
+                    let store_task =
                         vector_database.add_document_chunks_to_database_and_metadata_storage(
                             &config.embedder,
                             &config.database,
                             chunks,
                             metadata_storage.clone(),
                             metadata,
-                        )
+                        );
+                    let tasks_scheduler = &tasks_scheduler;
+                    let task_id = &task_id;
+                    store_tasks.push(async move {
+                        let result = store_task.await;
+                        tasks_scheduler.lock().await.advance_progress_by_task_id(task_id);
+
+                        result
                     });
+                    store_tasks_indices.push(index);
                 }
                 Err(err) => {
                     error!("Failed to preprocess: {}", err);
-                    failures.insert(request.0.imports[index].clone());
+                    failures.insert(request.0.imports[index].masked());
                     continue;
                 }
             }
         }
 
-        let store_results = join_all(store_tasks).await;
+        // Store the documents in batches.
+        // If the task is cancelled, the documents stored so far are removed.
+        let mut store_results = Vec::new();
+        let mut store_tasks = store_tasks.into_iter().peekable();
+        while store_tasks.peek().is_some() {
+            if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+                let stored_document_metadata_ids: Vec<String> = store_results
+                    .into_iter()
+                    .filter_map(|result: anyhow::Result<(String, VectorizationStatistics)>| {
+                        result.ok().map(|(document_metadata_id, _)| document_metadata_id)
+                    })
+                    .collect();
+                if let Err(error) = remove_documents(
+                    &vector_database,
+                    &metadata_storage,
+                    &config.database,
+                    &stored_document_metadata_ids,
+                )
+                .await
+                {
+                    error!("Failed to undo the cancelled import: {}", error);
+                }
+                if let Err(error) = remove_empty_collections(
+                    &metadata_storage,
+                    &identities_storage,
+                    &request.0.username,
+                    &created_collection_metadata_ids,
+                )
+                .await
+                {
+                    error!("Failed to remove the collections of the cancelled import: {}", error);
+                }
+
+                info!("Task {} was cancelled when storing the imports", task_id);
+                tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+                return;
+            }
+
+            let batch: Vec<_> = store_tasks.by_ref().take(TASK_BATCH_SIZE).collect();
+            store_results.extend(join_all(batch).await);
+        }
         let mut document_metadata_ids = Vec::new();
+        let mut vectorization_statistics = VectorizationStatistics::default();
 
-        for (index, store_result) in store_results.into_iter().enumerate() {
+        for (index, store_result) in store_tasks_indices.into_iter().zip(store_results) {
             match store_result {
-                Ok(result) => {
+                Ok((result, statistics)) => {
                     info!(
                         "Task {} has finished importing document id {}.",
                         task_id, result
                     );
                     document_metadata_ids.push(result);
+                    vectorization_statistics += statistics;
                 }
                 Err(err) => {
                     error!("Failed to store an imported document: {}", err);
-                    failures.insert(request.0.imports[index].clone());
+                    failures.insert(request.0.imports[index].masked());
                     continue;
                 }
             }
@@ -266,7 +438,7 @@ pub async fn import_documents(
                             failures.len()
                         )
                     ),
-                );
+                ).await;
                 return;
             }
         }
@@ -276,10 +448,12 @@ pub async fn import_documents(
             serde_json::to_value(ImportDocumentsResponse {
                 failed_import_tasks: failures.into_iter().map(|item| item).collect(),
                 document_metadata_ids,
+                unsupported_resources,
+                vectorization_statistics,
             })
             .unwrap(),
-        );
-    });
+        ).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
@@ -297,11 +471,12 @@ pub async fn delete_document(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(request.username.as_deref()).await;
     let task_id_cloned = task_id.clone();
 
     // Perform operations asynchronously
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (vector_database, metadata_storage, tasks_scheduler, config, _, _) =
             acquire_data(&data).await;
@@ -334,7 +509,7 @@ pub async fn delete_document(
                     &task_id,
                     TaskStatus::Failed,
                     None,
-                );
+                ).await;
                 return;
             }
         }
@@ -345,8 +520,8 @@ pub async fn delete_document(
                 document_metadata_id: request.document_metadata_id.clone(),
             })
             .unwrap(),
-        );
-    });
+        ).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
@@ -359,7 +534,7 @@ pub async fn update_documents_metadata(
     data: web::Data<RwLock<AppState>>,
     mut request: web::Json<UpdateDocumentMetadataRequest>,
 ) -> Result<HttpResponse> {
-    let (_, metadata_storage, _, _, _, _) = acquire_data(&data).await;
+    let (vector_database, metadata_storage, _, config, _, _) = acquire_data(&data).await;
 
     let mut metadata_storage = metadata_storage.lock().await;
 
@@ -379,11 +554,30 @@ pub async fn update_documents_metadata(
         }
     };
 
+    let document_metadata_ids: Vec<String> = request
+        .0
+        .document_metadatas
+        .iter()
+        .map(|metadata| metadata.id.clone())
+        .collect();
+
     match metadata_storage
         .update_documents(request.0.document_metadatas)
         .await
     {
-        Ok(_) => Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &json!({})))),
+        Ok(_) => {
+            // The titles and tags are filtered on the chunks as well
+            let payloads = metadata_storage.get_document_payloads(&document_metadata_ids);
+            drop(metadata_storage);
+            if let Err(error) = vector_database
+                .update_document_payloads(&config.database, payloads)
+                .await
+            {
+                error!("Failed to update the document payloads: {}", error);
+            }
+
+            Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &json!({}))))
+        }
         Err(error) => {
             error!("Failed when trying updating documents metadata: {}", error);
             return Ok(
@@ -403,11 +597,12 @@ pub async fn update_document_content(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(Some(&request.username)).await;
     let task_id_cloned = task_id.clone();
 
     // Perform operations asynchronously
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
             acquire_data(&data).await;
@@ -429,7 +624,7 @@ pub async fn update_document_content(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
         };
@@ -456,7 +651,7 @@ pub async fn update_document_content(
                         &task_id,
                         TaskStatus::Failed,
                         Some(message),
-                    );
+                    ).await;
                     return;
                 }
             };
@@ -474,7 +669,7 @@ pub async fn update_document_content(
                         &task_id,
                         TaskStatus::Failed,
                         Some(error.to_string()),
-                    );
+                    ).await;
                     return;
                 }
             }
@@ -493,11 +688,11 @@ pub async fn update_document_content(
 
         metadata.chunks = chunks.iter().map(|chunk| chunk.id.clone()).collect();
 
-        match vector_database
+        let vectorization_statistics: VectorizationStatistics = match vector_database
             .add_document_chunks_to_database(&config.embedder, &config.database, chunks)
             .await
         {
-            Ok(_) => {
+            Ok(statistics) => {
                 match metadata_storage
                     .lock()
                     .await
@@ -511,30 +706,40 @@ pub async fn update_document_content(
                             &task_id,
                             TaskStatus::Failed,
                             Some(error.to_string()),
-                        );
+                        ).await;
                         return;
                     }
                 }
+                if let Err(error) = sync_document_payloads(
+                    &vector_database,
+                    &metadata_storage,
+                    &config.database,
+                    std::slice::from_ref(&metdata_id),
+                ).await {
+                    error!("Failed to update the document payloads: {}", error);
+                }
                 info!("Task {} has finished updating documents.", task_id);
+                statistics
             }
             Err(error) => {
                 tasks_scheduler.lock().await.update_status_by_task_id(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
-        }
+        };
 
         tasks_scheduler.lock().await.set_status_to_complete(
             &task_id,
             serde_json::to_value(UpdateDocumentResponse {
                 document_metadata_id: metdata_id,
+                vectorization_statistics,
             })
             .unwrap(),
-        );
-    });
+        ).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
@@ -542,6 +747,216 @@ pub async fn update_document_content(
         .into())
 }
 
+pub async fn refresh_document(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<RefreshDocumentRequest>,
+) -> Result<HttpResponse> {
+    let task_id = data
+        .write()
+        .await
+        .tasks_scheduler
+        .lock()
+        .await
+        .create_new_task(Some(&request.credentials.username)).await;
+    let task_id_cloned = task_id.clone();
+
+    // Perform operations asynchronously
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
+        // Pull what we need out of AppState without holding the lock during I/O
+        let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
+            acquire_data(&data).await;
+        let data_sources_storage = data.read().await.data_sources_storage.clone();
+
+        let user_configurations: UserConfigurations = match identities_storage
+            .lock()
+            .await
+            .get_user_configurations(&request.0.credentials.username)
+            .await
+        {
+            Ok(result) => result,
+            Err(error) => {
+                error!(
+                    "Can't fetch user configurations when trying refreshing a document: {}",
+                    error
+                );
+                tasks_scheduler.lock().await.update_status_by_task_id(
+                    &task_id,
+                    TaskStatus::Failed,
+                    Some(error.to_string()),
+                ).await;
+                return;
+            }
+        };
+
+        let mut response = RefreshDocumentsResponse::default();
+        match refresh_document_from_source(
+            &vector_database,
+            &metadata_storage,
+            &data_sources_storage,
+            &config,
+            &request.0.document_metadata_id,
+            &request.0.credentials,
+            user_configurations.search.document_chunk_size,
+        )
+        .await
+        {
+            Ok(Some(statistics)) => {
+                response
+                    .refreshed_document_metadata_ids
+                    .push(request.0.document_metadata_id.clone());
+                response.vectorization_statistics += statistics;
+            }
+            Ok(None) => response
+                .unchanged_document_metadata_ids
+                .push(request.0.document_metadata_id.clone()),
+            Err(error) => {
+                error!("Failed to refresh document: {}", error);
+                tasks_scheduler.lock().await.update_status_by_task_id(
+                    &task_id,
+                    TaskStatus::Failed,
+                    Some(error.to_string()),
+                ).await;
+                return;
+            }
+        }
+
+        info!("Task {} has finished refreshing documents.", task_id);
+        tasks_scheduler
+            .lock()
+            .await
+            .set_status_to_complete(&task_id, serde_json::to_value(response).unwrap()).await;
+    }).await;
+
+    // Return an immediate response with a task id
+    Ok(HttpResponse::Ok().json(GenericResponse::in_progress(task_id_cloned)))
+}
+
+pub async fn refresh_collection(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<RefreshCollectionRequest>,
+) -> Result<HttpResponse> {
+    let task_id = data
+        .write()
+        .await
+        .tasks_scheduler
+        .lock()
+        .await
+        .create_new_task(Some(&request.credentials.username)).await;
+    let task_id_cloned = task_id.clone();
+
+    // Perform operations asynchronously
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue
+        .submit(&task_id_cloned, run_refresh_collection_task(data, task_id, request.0))
+        .await;
+
+    // Return an immediate response with a task id
+    Ok(HttpResponse::Ok().json(GenericResponse::in_progress(task_id_cloned)))
+}
+
+/// Refresh the documents of a collection from their sources.
+/// Shared by the endpoint and the scheduled jobs.
+pub async fn run_refresh_collection_task(
+    data: web::Data<RwLock<AppState>>,
+    task_id: String,
+    request: RefreshCollectionRequest,
+) {
+    // Pull what we need out of AppState without holding the lock during I/O
+    let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
+        acquire_data(&data).await;
+    let data_sources_storage = data.read().await.data_sources_storage.clone();
+
+    let user_configurations: UserConfigurations = match identities_storage
+        .lock()
+        .await
+        .get_user_configurations(&request.credentials.username)
+        .await
+    {
+        Ok(result) => result,
+        Err(error) => {
+            error!(
+                "Can't fetch user configurations when trying refreshing a collection: {}",
+                error
+            );
+            tasks_scheduler.lock().await.update_status_by_task_id(
+                &task_id,
+                TaskStatus::Failed,
+                Some(error.to_string()),
+            ).await;
+            return;
+        }
+    };
+
+    // Split the documents into the refreshable ones and the others
+    let mut response = RefreshDocumentsResponse::default();
+    let mut document_metadata_ids: Vec<String> = Vec::new();
+    {
+        let metadata_storage = metadata_storage.lock().await;
+        for document_metadata_id in
+            metadata_storage.get_document_ids_by_collection(&request.collection_metadata_id)
+        {
+            let is_refreshable: bool = metadata_storage
+                .documents
+                .get(document_metadata_id)
+                .and_then(|metadata| metadata.provenance.as_ref())
+                .is_some_and(|provenance| provenance.import_type.is_refreshable());
+
+            if is_refreshable {
+                document_metadata_ids.push(document_metadata_id.clone());
+            } else {
+                response
+                    .skipped_document_metadata_ids
+                    .push(document_metadata_id.clone());
+            }
+        }
+    }
+
+    // Refresh one by one to avoid flooding the sources.
+    // Each document is refreshed as a whole, so cancelling leaves nothing to undo.
+    for document_metadata_id in document_metadata_ids {
+        if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+            info!("Task {} was cancelled when refreshing documents", task_id);
+            tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+            return;
+        }
+
+        match refresh_document_from_source(
+            &vector_database,
+            &metadata_storage,
+            &data_sources_storage,
+            &config,
+            &document_metadata_id,
+            &request.credentials,
+            user_configurations.search.document_chunk_size,
+        )
+        .await
+        {
+            Ok(Some(statistics)) => {
+                response
+                    .refreshed_document_metadata_ids
+                    .push(document_metadata_id);
+                response.vectorization_statistics += statistics;
+            }
+            Ok(None) => response
+                .unchanged_document_metadata_ids
+                .push(document_metadata_id),
+            Err(error) => {
+                error!("Failed to refresh document {}: {}", document_metadata_id, error);
+                response
+                    .failed_document_metadata_ids
+                    .push(document_metadata_id);
+            }
+        }
+    }
+
+    info!("Task {} has finished refreshing documents.", task_id);
+    tasks_scheduler
+        .lock()
+        .await
+        .set_status_to_complete(&task_id, serde_json::to_value(response).unwrap()).await;
+}
+
 // Sync endpoint
 pub async fn get_documents_metadata(
     data: web::Data<RwLock<AppState>>,
@@ -644,11 +1059,12 @@ pub async fn reindex(
         .tasks_scheduler
         .lock()
         .await
-        .create_new_task();
+        .create_new_task(Some(&request.username)).await;
     let task_id_cloned = task_id.clone();
 
     // Perform operations asynchronously
-    tokio::spawn(async move {
+    let tasks_queue = data.read().await.tasks_queue.clone();
+    tasks_queue.submit(&task_id_cloned, async move {
         // Pull what we need out of AppState without holding the lock during I/O
         let (vector_database, metadata_storage, tasks_scheduler, config, identities_storage, _) =
             acquire_data(&data).await;
@@ -670,7 +1086,7 @@ pub async fn reindex(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
         };
@@ -726,6 +1142,12 @@ pub async fn reindex(
         // 2. Get the document contents
         // There is no mutation to the document metadata chunks ids yet.
         // We will save that for the final updating phase to avoid losing data when failing getting document chunks.
+        tasks_scheduler.lock().await.update_progress_by_task_id(
+            &task_id,
+            "fetching",
+            0,
+            metadata_ids_to_delete.len(),
+        );
         let mut get_document_contents_tasks = Vec::new();
         for (collection_metadata_id, document_metadatas) in get_document_contents_tasks_data {
             for document_metadata in document_metadatas {
@@ -740,14 +1162,39 @@ pub async fn reindex(
                     for chunk in chunks {
                         content.push_str(&chunk.content);
                     }
+                    tasks_scheduler.lock().await.advance_progress_by_task_id(&task_id);
 
                     (collection_metadata_id, document_metadata, content)
                 });
             }
         }
 
+        // Fetch in batches, so that the task can be cancelled before anything is changed
+        let mut results = Vec::new();
+        let mut get_document_contents_tasks = get_document_contents_tasks.into_iter().peekable();
+        while get_document_contents_tasks.peek().is_some() {
+            if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+                info!("Task {} was cancelled when fetching the documents", task_id);
+                tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+                return;
+            }
+
+            let batch: Vec<_> = get_document_contents_tasks
+                .by_ref()
+                .take(TASK_BATCH_SIZE)
+                .collect();
+            results.extend(join_all(batch).await);
+        }
+
+        // Old chunks are removed below and cannot be restored afterwards,
+        // so this is the last chance to cancel.
+        if tasks_scheduler.lock().await.is_cancellation_requested(&task_id) {
+            info!("Task {} was cancelled before removing the old chunks", task_id);
+            tasks_scheduler.lock().await.set_status_to_cancelled(&task_id).await;
+            return;
+        }
+
         // 3. Re-slice the document contents, then put the chunk ids to corresponding DocumentMetadata
-        let results = join_all(get_document_contents_tasks).await;
         let mut slicing_tasks = Vec::new();
         for (collection_metadata_id, mut document_metadata, document_content) in results {
             slicing_tasks.push(tokio::spawn(async move {
@@ -768,7 +1215,6 @@ pub async fn reindex(
         }
 
         // Remove old chunks from the database before updating the new ones to prevent conflicts.
-
         match vector_database
             .delete_documents_from_database(&config.database, &metadata_ids_to_delete)
             .await
@@ -779,21 +1225,35 @@ pub async fn reindex(
                     &task_id,
                     TaskStatus::Failed,
                     None,
-                );
+                ).await;
                 return;
             }
         }
 
+        tasks_scheduler.lock().await.update_progress_by_task_id(
+            &task_id,
+            "embedding",
+            0,
+            slicing_tasks.len(),
+        );
         let mut final_update_tasks = Vec::new();
         let mut metadatas_to_update = Vec::new();
         for task in slicing_tasks {
             match task.await {
                 Ok((document_metadata, document_chunks)) => {
-                    final_update_tasks.push(vector_database.add_document_chunks_to_database(
+                    let update_task = vector_database.add_document_chunks_to_database(
                         &config.embedder,
                         &config.database,
                         document_chunks,
-                    ));
+                    );
+                    let tasks_scheduler = &tasks_scheduler;
+                    let task_id = &task_id;
+                    final_update_tasks.push(async move {
+                        let result = update_task.await;
+                        tasks_scheduler.lock().await.advance_progress_by_task_id(task_id);
+
+                        result
+                    });
 
                     metadatas_to_update.push(document_metadata);
                 }
@@ -806,16 +1266,17 @@ pub async fn reindex(
                         &task_id,
                         TaskStatus::Failed,
                         Some(error.to_string()),
-                    );
+                    ).await;
                     return;
                 }
             }
         }
 
         let results = join_all(final_update_tasks).await;
+        let mut vectorization_statistics = VectorizationStatistics::default();
         for result in results {
             match result {
-                Ok(_) => {}
+                Ok(statistics) => vectorization_statistics += statistics,
                 Err(error) => {
                     error!(
                         "Failed to re-index the user {} collections: {}",
@@ -825,7 +1286,7 @@ pub async fn reindex(
                         &task_id,
                         TaskStatus::Failed,
                         Some(error.to_string()),
-                    );
+                    ).await;
                     return;
                 }
             }
@@ -839,7 +1300,15 @@ pub async fn reindex(
             .update_documents_with_new_chunks(metadatas_to_update)
             .await
         {
-            Ok(_) => {}
+            Ok(_) => {
+                let payloads = metadata_storage.get_document_payloads(&metadata_ids_to_delete);
+                if let Err(error) = vector_database
+                    .update_document_payloads(&config.database, payloads)
+                    .await
+                {
+                    error!("Failed to update the document payloads: {}", error);
+                }
+            }
             Err(error) => {
                 error!(
                     "Failed to re-index the user {} collections: {}",
@@ -849,7 +1318,7 @@ pub async fn reindex(
                     &task_id,
                     TaskStatus::Failed,
                     Some(error.to_string()),
-                );
+                ).await;
                 return;
             }
         }
@@ -858,10 +1327,11 @@ pub async fn reindex(
             &task_id,
             serde_json::to_value(ReindexResponse {
                 documents_reindexed: metadatas_count,
+                vectorization_statistics,
             })
             .unwrap(),
-        );
-    });
+        ).await;
+    }).await;
 
     // Return an immediate response with a task id
     Ok(HttpResponse::Ok()
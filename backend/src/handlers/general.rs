@@ -1,15 +1,29 @@
-use actix_web::{HttpResponse, Result, web};
-use tokio::sync::RwLock;
+use std::time::Duration;
+
+use actix_web::{
+    HttpResponse, Result,
+    web::{self, Bytes},
+};
+use tokio::sync::{
+    RwLock,
+    broadcast::{Receiver, error::RecvError},
+};
 
 use crate::{
     api_models::{
-        callbacks::{GenericResponse, RetrieveTaskResultRequest},
+        callbacks::{
+            CancelTaskRequest, GenericResponse, GetTasksRequest, RetrieveTaskResultRequest,
+            SubscribeTaskUpdatesQuery,
+        },
         general::{HealthResponse, InfoResponse},
     },
     app_state::AppState,
-    tasks_scheduler::TaskStatus,
+    tasks_scheduler::{TaskRecord, TaskStatus},
 };
 
+/// Send a comment periodically to keep the connections from timing out
+const TASK_UPDATES_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
 pub async fn health_check() -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(HealthResponse {
         status: "ok".to_string(),
@@ -60,9 +74,19 @@ pub async fn retrieve_task_result(
                     )));
                 }
                 TaskStatus::InProgress => {
+                    if let Some(queue_position) = result.queue_position {
+                        return Ok(HttpResponse::Ok().json(GenericResponse::queued(
+                            request.task_id.clone(),
+                            queue_position,
+                        )));
+                    }
+
                     return Ok(HttpResponse::Ok()
                         .json(GenericResponse::in_progress(request.task_id.clone())));
                 }
+                TaskStatus::Cancelled => Ok(
+                    HttpResponse::Ok().json(GenericResponse::cancelled(request.task_id.clone()))
+                ),
                 TaskStatus::Completed => {
                     // Now acquire write lock separately to get the result
                     let result = data
@@ -71,7 +95,7 @@ pub async fn retrieve_task_result(
                         .tasks_scheduler
                         .lock()
                         .await
-                        .get_task_result(&request.task_id);
+                        .get_task_result(&request.task_id).await;
 
                     if let Some(result) = result {
                         return Ok(HttpResponse::Ok()
@@ -91,3 +115,171 @@ pub async fn retrieve_task_result(
         }
     }
 }
+
+// Sync endpoint
+pub async fn get_tasks(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<GetTasksRequest>,
+) -> Result<HttpResponse> {
+    let tasks_scheduler = data.read().await.tasks_scheduler.clone();
+    let tasks: Vec<TaskRecord> = tasks_scheduler
+        .lock()
+        .await
+        .get_tasks_by_owner(&request.username, request.status.as_ref())
+        .into_iter()
+        .filter(|task| {
+            request.scheduled_job_id.is_none()
+                || task.scheduled_job_id == request.scheduled_job_id
+        })
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &tasks)))
+}
+
+// Sync endpoint
+pub async fn cancel_task(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<CancelTaskRequest>,
+) -> Result<HttpResponse> {
+    let (tasks_scheduler, tasks_queue) = {
+        let state = data.read().await;
+        (state.tasks_scheduler.clone(), state.tasks_queue.clone())
+    };
+
+    match tasks_scheduler
+        .lock()
+        .await
+        .request_cancellation(&request.task_id, &request.username)
+        .await
+    {
+        Ok(_) => {}
+        Err(error) => {
+            return Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                request.task_id.clone(),
+                error.to_string(),
+            )));
+        }
+    }
+
+    // Tasks that have not started yet are cancelled right away
+    if tasks_queue.remove(&request.task_id).await {
+        tasks_scheduler
+            .lock()
+            .await
+            .set_status_to_cancelled(&request.task_id)
+            .await;
+        return Ok(HttpResponse::Ok().json(GenericResponse::cancelled(request.task_id.clone())));
+    }
+
+    Ok(HttpResponse::Ok().json(GenericResponse::in_progress(request.task_id.clone())))
+}
+
+/// State of a Server-Sent Events stream of task updates
+struct TaskUpdatesStream {
+    receiver: Receiver<TaskRecord>,
+    username: String,
+    task_id: Option<String>,
+    /// The current record of the subscribed task, which is sent first
+    pending: Option<TaskRecord>,
+    is_finished: bool,
+}
+
+impl TaskUpdatesStream {
+    /// Whether the update is pushed to the subscriber
+    fn is_subscribed(&self, task: &TaskRecord) -> bool {
+        task.owner.as_deref() == Some(self.username.as_str())
+            && self
+                .task_id
+                .as_ref()
+                .is_none_or(|task_id| *task_id == task.task_id)
+    }
+
+    /// Encode a record as an event.
+    /// The stream of a single task ends after the task has finished.
+    fn encode_event(&mut self, task: &TaskRecord) -> Bytes {
+        if self.task_id.is_some() && task.status != TaskStatus::InProgress {
+            self.is_finished = true;
+        }
+
+        Bytes::from(format!(
+            "data: {}\n\n",
+            serde_json::to_string(task).unwrap_or_default()
+        ))
+    }
+}
+
+/// Push the status and progress of tasks as Server-Sent Events
+pub async fn subscribe_task_updates(
+    data: web::Data<RwLock<AppState>>,
+    query: web::Query<SubscribeTaskUpdatesQuery>,
+) -> Result<HttpResponse> {
+    let tasks_scheduler = data.read().await.tasks_scheduler.clone();
+    let (receiver, pending) = {
+        let tasks_scheduler = tasks_scheduler.lock().await;
+        (
+            tasks_scheduler.subscribe(),
+            query
+                .task_id
+                .as_ref()
+                .and_then(|task_id| tasks_scheduler.search_by_task_id(task_id))
+                .filter(|task| task.owner.as_deref() == Some(query.username.as_str()))
+                .cloned(),
+        )
+    };
+
+    if let Some(task_id) = &query.task_id
+        && pending.is_none()
+    {
+        return Ok(HttpResponse::NotFound().json(GenericResponse::fail(
+            task_id.clone(),
+            "Task not found.".to_string(),
+        )));
+    }
+
+    let query: SubscribeTaskUpdatesQuery = query.into_inner();
+    let state = TaskUpdatesStream {
+        receiver,
+        username: query.username,
+        task_id: query.task_id,
+        pending,
+        is_finished: false,
+    };
+
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        if state.is_finished {
+            return None;
+        }
+
+        if let Some(task) = state.pending.take() {
+            let event: Bytes = state.encode_event(&task);
+            return Some((Ok::<Bytes, actix_web::Error>(event), state));
+        }
+
+        loop {
+            tokio::select! {
+                result = state.receiver.recv() => match result {
+                    Ok(task) => {
+                        if !state.is_subscribed(&task) {
+                            continue;
+                        }
+
+                        let event: Bytes = state.encode_event(&task);
+                        return Some((Ok(event), state));
+                    }
+                    // Skipped updates are superseded by the later ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = tokio::time::sleep(TASK_UPDATES_KEEP_ALIVE) => {
+                    return Some((Ok(Bytes::from_static(b": keep-alive\n\n")), state));
+                }
+            }
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream))
+}
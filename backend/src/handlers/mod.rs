@@ -1,6 +1,9 @@
 pub mod backup;
 pub mod collection;
+pub mod data_source;
 pub mod document;
 pub mod general;
+pub mod scheduled_job;
 pub mod search;
+pub mod search_history;
 pub mod user;
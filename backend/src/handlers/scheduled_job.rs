@@ -0,0 +1,93 @@
+use actix_web::{HttpResponse, Result, web};
+use tokio::sync::RwLock;
+
+use crate::{
+    api_models::{
+        callbacks::GenericResponse,
+        scheduled_job::{
+            CreateScheduledJobRequest, CreateScheduledJobResponse, DeleteScheduledJobRequest,
+            GetScheduledJobsRequest,
+        },
+    },
+    app_state::AppState,
+    scheduled_jobs::scheduled_job::ScheduledJob,
+};
+
+// Sync endpoint
+pub async fn create_scheduled_job(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<CreateScheduledJobRequest>,
+) -> Result<HttpResponse> {
+    let scheduled_jobs_storage = data.read().await.scheduled_jobs_storage.clone();
+
+    let request = request.into_inner();
+    let scheduled_job: ScheduledJob = match ScheduledJob::new(
+        request.name,
+        request.username,
+        request.cron_expression,
+        request.kind,
+    ) {
+        Ok(result) => result,
+        Err(error) => {
+            log::error!("Failed to create a scheduled job: {}", error);
+            return Ok(
+                HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string()))
+            );
+        }
+    };
+
+    match scheduled_jobs_storage
+        .lock()
+        .await
+        .add_scheduled_job(scheduled_job)
+        .await
+    {
+        Ok(scheduled_job_id) => Ok(HttpResponse::Ok().json(GenericResponse::succeed(
+            "".to_string(),
+            &CreateScheduledJobResponse { scheduled_job_id },
+        ))),
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn delete_scheduled_job(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<DeleteScheduledJobRequest>,
+) -> Result<HttpResponse> {
+    let scheduled_jobs_storage = data.read().await.scheduled_jobs_storage.clone();
+
+    match scheduled_jobs_storage
+        .lock()
+        .await
+        .remove_scheduled_job(&request.scheduled_job_id, &request.username)
+        .await
+    {
+        Ok(_) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &"".to_string())))
+        }
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn get_scheduled_jobs(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<GetScheduledJobsRequest>,
+) -> Result<HttpResponse> {
+    let scheduled_jobs_storage = data.read().await.scheduled_jobs_storage.clone();
+
+    let scheduled_jobs: Vec<ScheduledJob> = scheduled_jobs_storage
+        .lock()
+        .await
+        .get_scheduled_jobs_by_owner(&request.username)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &scheduled_jobs)))
+}
@@ -1,13 +1,42 @@
-use actix_web::{HttpResponse, Result, web};
+use actix_web::{
+    HttpResponse, Result,
+    web::{self, Bytes},
+};
+use futures::StreamExt;
 use log::error;
+use serde::Serialize;
+use serde_json::json;
 use tokio::sync::RwLock;
 
 use crate::{
-    api_models::{callbacks::GenericResponse, search::SearchDocumentRequest},
+    api_models::{
+        callbacks::GenericResponse,
+        search::{
+            AskRequest, AskResponse, RelatedDocumentsRequest, SearchDocumentRequest,
+            SearchResultsPage,
+        },
+    },
     app_state::AppState,
-    documents::
-        operations::retrieve_document_ids_by_scope
-    ,
+    configurations::{
+        search::{SupportedSearchMethod, UserSearchConfiguration},
+        system::{Config, LlmConfig},
+    },
+    constants::MAX_SEARCH_RESULTS,
+    documents::document_chunk::DocumentChunkSearchResult,
+    documents::operations::{
+        count_document_chunks, filter_document_ids_by_attributes,
+        filter_document_ids_by_search_filter, retrieve_document_ids_by_scope,
+        retrieve_owner_by_scope, retrieve_search_configuration_by_scope,
+    },
+    llm::{ChatCompletionStream, ChatMessage, send_chat_completion, stream_chat_completion},
+    search::{
+        SearchScope,
+        ask::{Citation, build_citations, build_messages},
+        grouping::{DocumentSearchResult, get_grouping_candidates, group_search_results},
+        hybrid::HybridSearchOptions,
+        rerank::rerank_search_results,
+    },
+    search_history::entry::SearchHistoryEntry,
     utilities::acquire_data,
 };
 
@@ -16,6 +45,11 @@ pub async fn intelligent_search(
     data: web::Data<RwLock<AppState>>,
     request: web::Json<SearchDocumentRequest>,
 ) -> Result<HttpResponse> {
+    if let Some(response) = check_search_window(&request.0) {
+        return Ok(response);
+    }
+    record_search_history(&data, &request.0, SupportedSearchMethod::Semantic).await;
+
     // Perform operations synchronously
     // Pull what we need out of AppState without holding the lock during I/O
     let (vector_database, metadata_storage, _, config, identities_storage, _) =
@@ -29,30 +63,90 @@ pub async fn intelligent_search(
         request.0.scope.search_scope,
         &request.0.scope.id,
     );
+    let document_metadata_ids: Vec<String> = filter_document_ids_by_attributes(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.attributes,
+    );
+    // Counted by the metadatas, as some databases apply the filter while searching
+    let matching_document_ids: Vec<String> = filter_document_ids_by_search_filter(
+        &metadata_storage,
+        document_metadata_ids.clone(),
+        &request.0.filter,
+    );
+    let document_metadata_ids: Vec<String> = vector_database.narrow_document_ids(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.filter,
+    );
 
-    if document_metadata_ids.is_empty() {
+    if matching_document_ids.is_empty() {
         log::warn!("No search results found for request {:?}", request);
-        let vec: Vec<String> = Vec::new();
-        return Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &vec)));
+        return Ok(build_search_response(&request.0, Vec::new(), 0));
     }
+    // Every chunk in the scope is a semantic hit
+    let total_hits: usize = get_total_hits(
+        &request.0,
+        count_document_chunks(&metadata_storage, &matching_document_ids),
+        matching_document_ids.len(),
+    );
 
-    match vector_database
+    // Qdrant groups the chunks by documents itself, unless they need reranking first
+    if let Some(grouping) = &request.0.group_by_document
+        && config.reranker.is_none()
+    {
+        return match vector_database
+            .search_documents_semantically_grouped(
+                &mut metadata_storage,
+                document_metadata_ids,
+                &request.0.filter,
+                &request.0.query,
+                request.0.offset.saturating_add(request.0.top_n),
+                grouping.chunks_per_document,
+                &config.embedder,
+            )
+            .await
+        {
+            Ok(groups) => {
+                let groups: Vec<DocumentSearchResult> =
+                    groups.into_iter().skip(request.0.offset).collect();
+                let page = SearchResultsPage::new(
+                    groups,
+                    request.0.offset,
+                    request.0.top_n,
+                    total_hits,
+                );
+                Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &page)))
+            }
+            Err(error) => {
+                error!("Failed when trying searching: {}", error);
+                Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                    "".to_string(),
+                    "Failed to talk to the database. Please check the connection.".to_string(),
+                )))
+            }
+        };
+    }
+
+    let limit: usize = get_search_limit(&request.0);
+    // Fetch more candidates when they will be reranked
+    let candidates: usize = match &config.reranker {
+        Some(reranker) => reranker.candidates.max(limit),
+        None => limit,
+    };
+
+    let results = match vector_database
         .search_documents_semantically(
             &mut metadata_storage,
             document_metadata_ids,
+            &request.0.filter,
             &request.0.query,
-            request.0.top_n,
-            &config.embedder.provider,
-            &config.embedder.base_url,
-            &config.embedder.api_key,
-            &config.embedder.model,
-            &config.embedder.encoding_format,
+            candidates,
+            &config.embedder,
         )
         .await
     {
-        Ok(results) => {
-            return Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &results)));
-        }
+        Ok(results) => results,
         Err(error) => {
             error!("Failed when trying searching: {}", error);
             return Ok(HttpResponse::Ok().json(GenericResponse::fail(
@@ -61,6 +155,12 @@ pub async fn intelligent_search(
             )));
         }
     };
+    drop(metadata_storage);
+
+    let results: Vec<DocumentChunkSearchResult> =
+        rerank_if_configured(&config, &request.0.query, results, limit).await;
+
+    Ok(build_search_response(&request.0, results, total_hits))
 }
 
 // Sync endpoint
@@ -68,6 +168,11 @@ pub async fn search(
     data: web::Data<RwLock<AppState>>,
     request: web::Json<SearchDocumentRequest>,
 ) -> Result<HttpResponse> {
+    if let Some(response) = check_search_window(&request.0) {
+        return Ok(response);
+    }
+    record_search_history(&data, &request.0, SupportedSearchMethod::Keyword).await;
+
     // Perform operations synchronously
     // Pull what we need out of AppState without holding the lock during I/O
     let (vector_database, metadata_storage, _, _, identities_storage, _) =
@@ -81,25 +186,467 @@ pub async fn search(
         request.0.scope.search_scope,
         &request.0.scope.id,
     );
+    let document_metadata_ids: Vec<String> = filter_document_ids_by_attributes(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.attributes,
+    );
+    // Counted by the metadatas, as some databases apply the filter while searching
+    let matching_document_ids: Vec<String> = filter_document_ids_by_search_filter(
+        &metadata_storage,
+        document_metadata_ids.clone(),
+        &request.0.filter,
+    );
+    let document_metadata_ids: Vec<String> = vector_database.narrow_document_ids(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.filter,
+    );
+
+    // Either list can be emptied by the filter
+    if matching_document_ids.is_empty() || document_metadata_ids.is_empty() {
+        log::warn!("No search results found for request {:?}", request);
+        return Ok(build_search_response(&request.0, Vec::new(), 0));
+    }
+
+    let search = vector_database.search_documents(
+        &mut metadata_storage,
+        document_metadata_ids.clone(),
+        &request.0.filter,
+        &request.0.query,
+        get_search_limit(&request.0),
+    );
+    let count = vector_database.count_keyword_hits(
+        document_metadata_ids,
+        &request.0.filter,
+        &request.0.query,
+    );
+
+    match tokio::try_join!(search, count) {
+        Ok((results, chunk_hits)) => {
+            let total_hits: usize =
+                get_total_hits(&request.0, chunk_hits, matching_document_ids.len());
+            Ok(build_search_response(&request.0, results, total_hits))
+        }
+        Err(error) => {
+            error!("Failed when trying searching: {}", error);
+            Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                "".to_string(),
+                "Failed to talk to the database. Please check the connection.".to_string(),
+            )))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn hybrid_search(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<SearchDocumentRequest>,
+) -> Result<HttpResponse> {
+    if let Some(response) = check_search_window(&request.0) {
+        return Ok(response);
+    }
+    record_search_history(&data, &request.0, SupportedSearchMethod::Hybrid).await;
+
+    // Perform operations synchronously
+    // Pull what we need out of AppState without holding the lock during I/O
+    let (vector_database, metadata_storage, _, config, identities_storage, _) =
+        acquire_data(&data).await;
+
+    let mut metadata_storage = metadata_storage.lock().await;
+    let mut identities_storage = identities_storage.lock().await;
+
+    let document_metadata_ids: Vec<String> = retrieve_document_ids_by_scope(
+        &mut metadata_storage,
+        &mut identities_storage,
+        request.0.scope.search_scope,
+        &request.0.scope.id,
+    );
+    let document_metadata_ids: Vec<String> = filter_document_ids_by_attributes(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.attributes,
+    );
+    // Counted by the metadatas, as some databases apply the filter while searching
+    let matching_document_ids: Vec<String> = filter_document_ids_by_search_filter(
+        &metadata_storage,
+        document_metadata_ids.clone(),
+        &request.0.filter,
+    );
+    let document_metadata_ids: Vec<String> = vector_database.narrow_document_ids(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.filter,
+    );
+    // The weights come from the configurations of the user who owns the scope
+    let search_configuration: UserSearchConfiguration = retrieve_search_configuration_by_scope(
+        &metadata_storage,
+        &identities_storage,
+        request.0.scope.search_scope,
+        &request.0.scope.id,
+    );
+    drop(identities_storage);
+
+    if matching_document_ids.is_empty() {
+        log::warn!("No search results found for request {:?}", request);
+        return Ok(build_search_response(&request.0, Vec::new(), 0));
+    }
+    // Every chunk in the scope is a semantic hit
+    let total_hits: usize = get_total_hits(
+        &request.0,
+        count_document_chunks(&metadata_storage, &matching_document_ids),
+        matching_document_ids.len(),
+    );
+
+    match vector_database
+        .search_documents_hybrid(
+            &mut metadata_storage,
+            HybridSearchOptions {
+                document_metadata_ids,
+                filter: &request.0.filter,
+                query: &request.0.query,
+                top_n: get_search_limit(&request.0),
+                embedder_config: &config.embedder,
+                configuration: &search_configuration.hybrid_search,
+            },
+        )
+        .await
+    {
+        Ok(results) => Ok(build_search_response(&request.0, results, total_hits)),
+        Err(error) => {
+            error!("Failed when trying searching: {}", error);
+            Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                "".to_string(),
+                "Failed to talk to the database. Please check the connection.".to_string(),
+            )))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn related_documents(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<RelatedDocumentsRequest>,
+) -> Result<HttpResponse> {
+    if let Some(response) = check_top_n(request.0.top_n) {
+        return Ok(response);
+    }
+
+    // Pull what we need out of AppState without holding the lock during I/O
+    let (vector_database, metadata_storage, _, _, identities_storage, _) =
+        acquire_data(&data).await;
+
+    let mut metadata_storage = metadata_storage.lock().await;
+
+    // Only the documents in the userspace are related, including the given one
+    let document_metadata_ids: Vec<String> = retrieve_document_ids_by_scope(
+        &mut metadata_storage,
+        &mut identities_storage.lock().await,
+        SearchScope::Userspace,
+        &request.0.username,
+    );
+    if !document_metadata_ids.contains(&request.0.document_metadata_id) {
+        return Ok(HttpResponse::Ok().json(GenericResponse::fail(
+            "".to_string(),
+            format!(
+                "Document {} was not found in the userspace of {}",
+                request.0.document_metadata_id, request.0.username
+            ),
+        )));
+    }
 
     match vector_database
-        .search_documents(
+        .search_related_documents(
             &mut metadata_storage,
+            &request.0.document_metadata_id,
             document_metadata_ids,
-            &request.0.query,
             request.0.top_n,
         )
         .await
     {
-        Ok(results) => {
-            return Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &results)));
+        Ok(results) => Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &results))),
+        Err(error) => {
+            error!("Failed when trying searching related documents: {}", error);
+            Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                "".to_string(),
+                "Failed to talk to the database. Please check the connection.".to_string(),
+            )))
+        }
+    }
+}
+
+// Sync endpoint, or Server-Sent Events when streaming
+pub async fn ask(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<AskRequest>,
+) -> Result<HttpResponse> {
+    // Pull what we need out of AppState without holding the lock during I/O
+    let (vector_database, metadata_storage, _, config, identities_storage, _) =
+        acquire_data(&data).await;
+
+    let llm: &LlmConfig = match &config.llm {
+        Some(llm) => llm,
+        None => {
+            return Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                "".to_string(),
+                "No LLM is configured. Please add `llm` to the configurations.".to_string(),
+            )));
         }
+    };
+    let top_n: usize = request.0.top_n.unwrap_or(llm.context_chunks);
+    if let Some(response) = check_top_n(top_n) {
+        return Ok(response);
+    }
+
+    let mut metadata_storage = metadata_storage.lock().await;
+
+    let document_metadata_ids: Vec<String> = retrieve_document_ids_by_scope(
+        &mut metadata_storage,
+        &mut identities_storage.lock().await,
+        request.0.scope.search_scope,
+        &request.0.scope.id,
+    );
+    let document_metadata_ids: Vec<String> = filter_document_ids_by_attributes(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.attributes,
+    );
+    let document_metadata_ids: Vec<String> = vector_database.narrow_document_ids(
+        &metadata_storage,
+        document_metadata_ids,
+        &request.0.filter,
+    );
+
+    // Without any documents, the model is asked anyway, and tells that the notes do not cover it
+    let mut results: Vec<DocumentChunkSearchResult> = Vec::new();
+    if !document_metadata_ids.is_empty() {
+        let candidates: usize = match &config.reranker {
+            Some(reranker) => reranker.candidates.max(top_n),
+            None => top_n,
+        };
+
+        results = match vector_database
+            .search_documents_semantically(
+                &mut metadata_storage,
+                document_metadata_ids,
+                &request.0.filter,
+                &request.0.query,
+                candidates,
+                &config.embedder,
+            )
+            .await
+        {
+            Ok(results) => results,
+            Err(error) => {
+                error!("Failed when trying searching: {}", error);
+                return Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                    "".to_string(),
+                    "Failed to talk to the database. Please check the connection.".to_string(),
+                )));
+            }
+        };
+    }
+    drop(metadata_storage);
+
+    let results: Vec<DocumentChunkSearchResult> =
+        rerank_if_configured(&config, &request.0.query, results, top_n).await;
+    let citations: Vec<Citation> = build_citations(&results);
+    let messages: Vec<ChatMessage> = build_messages(&request.0.query, &results);
+
+    if !request.0.stream {
+        return match send_chat_completion(llm, &messages).await {
+            Ok(answer) => Ok(HttpResponse::Ok().json(GenericResponse::succeed(
+                "".to_string(),
+                &AskResponse { answer, citations },
+            ))),
+            Err(error) => {
+                error!("Failed when trying answering the question: {}", error);
+                Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                    "".to_string(),
+                    "Failed to talk to the LLM. Please check the connection.".to_string(),
+                )))
+            }
+        };
+    }
+
+    let chat_completion_stream: ChatCompletionStream = match stream_chat_completion(llm, &messages).await {
+        Ok(result) => result,
         Err(error) => {
-            error!("Failed when trying searching: {}", error);
+            error!("Failed when trying answering the question: {}", error);
             return Ok(HttpResponse::Ok().json(GenericResponse::fail(
                 "".to_string(),
-                "Failed to talk to the database. Please check the connection.".to_string(),
+                "Failed to talk to the LLM. Please check the connection.".to_string(),
             )));
         }
     };
+
+    // The citations go first, then the answer piece by piece, and `done` or `error` last
+    let citations_event = futures::stream::once(async move {
+        Ok::<Bytes, actix_web::Error>(encode_answer_event("citations", &citations))
+    });
+    let answer_events = futures::stream::unfold(
+        Some(chat_completion_stream),
+        |chat_completion_stream| async move {
+            let mut chat_completion_stream = chat_completion_stream?;
+            let event: Bytes = match chat_completion_stream.next_token().await {
+                Ok(Some(token)) => {
+                    return Some((
+                        Ok(encode_answer_event("token", &json!({ "content": token }))),
+                        Some(chat_completion_stream),
+                    ));
+                }
+                Ok(None) => encode_answer_event("done", &json!({})),
+                Err(error) => {
+                    error!("Failed when trying streaming the answer: {}", error);
+                    encode_answer_event("error", &json!({ "message": error.to_string() }))
+                }
+            };
+
+            Some((Ok(event), None))
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(citations_event.chain(answer_events)))
+}
+
+fn encode_answer_event<T: Serialize>(event: &str, data: &T) -> Bytes {
+    Bytes::from(format!(
+        "event: {}\ndata: {}\n\n",
+        event,
+        serde_json::to_string(data).unwrap_or_default()
+    ))
+}
+
+/// Record the first page of a search in the history of the user who owns the scope.
+/// It is recorded in memory, and saved in batches in the background.
+async fn record_search_history(
+    data: &web::Data<RwLock<AppState>>,
+    request: &SearchDocumentRequest,
+    method: SupportedSearchMethod,
+) {
+    if request.offset > 0 {
+        return;
+    }
+
+    let (_, metadata_storage, _, config, identities_storage, _) = acquire_data(data).await;
+    let owner: Option<String> = retrieve_owner_by_scope(
+        &*metadata_storage.lock().await,
+        &*identities_storage.lock().await,
+        request.scope.search_scope,
+        &request.scope.id,
+    )
+    .map(|user| user.username.clone());
+    let owner: String = match owner {
+        Some(result) => result,
+        None => return,
+    };
+
+    let search_history_storage = data.read().await.search_history_storage.clone();
+    search_history_storage.lock().await.record_search(
+        SearchHistoryEntry::new(owner, request.query.clone(), request.scope.clone(), method),
+        config.search_history.max_entries_per_user,
+    );
+}
+
+/// Rerank the semantic search results when a reranker is configured, and keep the best `top_n`.
+/// It falls back to the semantic ranking, so an unreachable reranker does not break the search.
+async fn rerank_if_configured(
+    config: &Config,
+    query: &str,
+    mut results: Vec<DocumentChunkSearchResult>,
+    top_n: usize,
+) -> Vec<DocumentChunkSearchResult> {
+    let reranker = match &config.reranker {
+        Some(reranker) => reranker,
+        None => return results,
+    };
+
+    match rerank_search_results(reranker, query, results.clone(), top_n).await {
+        Ok(reranked_results) => reranked_results,
+        Err(error) => {
+            log::warn!("Failed to rerank the search results: {}", error);
+            results.truncate(top_n);
+            results
+        }
+    }
+}
+
+/// Reject the searches that reach too many results, as `offset` and `top_n` come from the clients
+fn check_search_window(request: &SearchDocumentRequest) -> Option<HttpResponse> {
+    if request.offset.saturating_add(request.top_n) <= MAX_SEARCH_RESULTS {
+        return None;
+    }
+
+    Some(HttpResponse::Ok().json(GenericResponse::fail(
+        "".to_string(),
+        format!(
+            "`offset` and `top_n` can reach at most {} results together",
+            MAX_SEARCH_RESULTS
+        ),
+    )))
+}
+
+/// Reject the `top_n` that reaches too many results, as it comes from the clients
+fn check_top_n(top_n: usize) -> Option<HttpResponse> {
+    if top_n <= MAX_SEARCH_RESULTS {
+        return None;
+    }
+
+    Some(HttpResponse::Ok().json(GenericResponse::fail(
+        "".to_string(),
+        format!("`top_n` can be at most {}", MAX_SEARCH_RESULTS),
+    )))
+}
+
+/// How many chunks to search for, so that the pages up to the requested one are filled.
+/// Grouped searches need more chunks, to fill the documents.
+fn get_search_limit(request: &SearchDocumentRequest) -> usize {
+    match &request.group_by_document {
+        Some(grouping) => get_grouping_candidates(
+            request.offset.saturating_add(request.top_n),
+            grouping.chunks_per_document,
+        ),
+        None => request.offset.saturating_add(request.top_n),
+    }
+}
+
+/// Approximate number of the hits, counting documents when grouping by document.
+/// Documents without hits can't be told apart cheaply, so they are counted when there are fewer of them than chunk hits.
+fn get_total_hits(request: &SearchDocumentRequest, chunk_hits: usize, documents: usize) -> usize {
+    match &request.group_by_document {
+        Some(_) => chunk_hits.min(documents),
+        None => chunk_hits,
+    }
+}
+
+/// Respond with a page of the chunks, or of the documents when grouping by document
+fn build_search_response(
+    request: &SearchDocumentRequest,
+    results: Vec<DocumentChunkSearchResult>,
+    total_hits: usize,
+) -> HttpResponse {
+    match &request.group_by_document {
+        Some(grouping) => {
+            let groups: Vec<DocumentSearchResult> =
+                group_search_results(results, grouping.chunks_per_document)
+                    .into_iter()
+                    .skip(request.offset)
+                    .take(request.top_n)
+                    .collect();
+            let page = SearchResultsPage::new(groups, request.offset, request.top_n, total_hits);
+            HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &page))
+        }
+        None => {
+            let results: Vec<DocumentChunkSearchResult> = results
+                .into_iter()
+                .skip(request.offset)
+                .take(request.top_n)
+                .collect();
+            let page = SearchResultsPage::new(results, request.offset, request.top_n, total_hits);
+            HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &page))
+        }
+    }
 }
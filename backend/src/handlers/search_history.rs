@@ -0,0 +1,153 @@
+use actix_web::{HttpResponse, Result, web};
+use tokio::sync::RwLock;
+
+use crate::{
+    api_models::{
+        callbacks::GenericResponse,
+        search_history::{
+            ClearSearchHistoryRequest, CreateSavedSearchRequest, CreateSavedSearchResponse,
+            DeleteSavedSearchRequest, GetSavedSearchesRequest, GetSearchHistoryRequest,
+            RunSavedSearchRequest,
+        },
+    },
+    app_state::AppState,
+    configurations::search::SupportedSearchMethod,
+    handlers::search::{hybrid_search, intelligent_search, search},
+    search_history::{entry::SearchHistoryEntry, saved_search::SavedSearch},
+};
+
+// Sync endpoint
+pub async fn get_search_history(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<GetSearchHistoryRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    let entries: Vec<SearchHistoryEntry> = search_history_storage
+        .lock()
+        .await
+        .get_history_by_owner(&request.username, request.limit)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &entries)))
+}
+
+// Sync endpoint
+pub async fn clear_search_history(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<ClearSearchHistoryRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    match search_history_storage
+        .lock()
+        .await
+        .clear_history(&request.username)
+        .await
+    {
+        Ok(_) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &"".to_string())))
+        }
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn create_saved_search(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<CreateSavedSearchRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    let request = request.into_inner();
+    let saved_search = SavedSearch::new(request.name, request.username, request.method, request.search);
+
+    match search_history_storage
+        .lock()
+        .await
+        .add_saved_search(saved_search)
+        .await
+    {
+        Ok(saved_search_id) => Ok(HttpResponse::Ok().json(GenericResponse::succeed(
+            "".to_string(),
+            &CreateSavedSearchResponse { saved_search_id },
+        ))),
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn delete_saved_search(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<DeleteSavedSearchRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    match search_history_storage
+        .lock()
+        .await
+        .remove_saved_search(&request.saved_search_id, &request.username)
+        .await
+    {
+        Ok(_) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &"".to_string())))
+        }
+        Err(error) => {
+            Ok(HttpResponse::Ok().json(GenericResponse::fail("".to_string(), error.to_string())))
+        }
+    }
+}
+
+// Sync endpoint
+pub async fn get_saved_searches(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<GetSavedSearchesRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    let saved_searches: Vec<SavedSearch> = search_history_storage
+        .lock()
+        .await
+        .get_saved_searches_by_owner(&request.username)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(GenericResponse::succeed("".to_string(), &saved_searches)))
+}
+
+/// Run a saved search with its method. It responds the same as the search endpoint of the method.
+// Sync endpoint
+pub async fn run_saved_search(
+    data: web::Data<RwLock<AppState>>,
+    request: web::Json<RunSavedSearchRequest>,
+) -> Result<HttpResponse> {
+    let search_history_storage = data.read().await.search_history_storage.clone();
+
+    let saved_search: SavedSearch = match search_history_storage
+        .lock()
+        .await
+        .get_saved_search(&request.saved_search_id, &request.username)
+    {
+        Some(result) => result.clone(),
+        None => {
+            return Ok(HttpResponse::Ok().json(GenericResponse::fail(
+                "".to_string(),
+                format!("Saved search {} was not found", request.saved_search_id),
+            )));
+        }
+    };
+
+    let search_request = web::Json(saved_search.to_request(request.offset));
+    match saved_search.method {
+        SupportedSearchMethod::Semantic => intelligent_search(data, search_request).await,
+        SupportedSearchMethod::Keyword => search(data, search_request).await,
+        SupportedSearchMethod::Hybrid => hybrid_search(data, search_request).await,
+    }
+}
@@ -0,0 +1,237 @@
+//! Client of OpenAI compatible chat completion endpoints,
+//! which answer the questions over the notes.
+
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::configurations::system::LlmConfig;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// `system`, `user` or `assistant`
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: String) -> Self {
+        Self {
+            role: role.to_string(),
+            content,
+        }
+    }
+}
+
+fn build_request_body(config: &LlmConfig, messages: &[ChatMessage], stream: bool) -> Value {
+    let mut body: Value = json!({
+        "model": config.model,
+        "messages": messages,
+        "temperature": config.temperature,
+        "stream": stream,
+    });
+    if let Some(max_tokens) = config.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+
+    body
+}
+
+async fn send_chat_completion_request(
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+    stream: bool,
+) -> Result<reqwest::Response> {
+    let timeout: Duration = Duration::from_secs(config.timeout_seconds);
+    let client: reqwest::Client = if stream {
+        // The timeout would cut a long answer off halfway,
+        // so it only applies to connecting and to each read
+        reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .read_timeout(timeout)
+            .build()?
+    } else {
+        reqwest::Client::builder().timeout(timeout).build()?
+    };
+
+    let mut request = client
+        .post(&config.base_url)
+        .json(&build_request_body(config, messages, stream));
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+
+    let response = request.send().await?;
+    if let Err(error) = response.error_for_status_ref() {
+        let error_response_body: String = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Chat completion request has failed. Error: {}. Message: {}",
+            error,
+            error_response_body
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Ask the chat model and wait for the whole answer
+pub async fn send_chat_completion(config: &LlmConfig, messages: &[ChatMessage]) -> Result<String> {
+    let response: Value = send_chat_completion_request(config, messages, false)
+        .await?
+        .json()
+        .await?;
+
+    match response["choices"][0]["message"]["content"].as_str() {
+        Some(answer) => Ok(answer.to_string()),
+        None => Err(anyhow!(
+            "Cannot find an answer in the chat completion response: {}",
+            response
+        )),
+    }
+}
+
+/// Ask the chat model and receive the answer piece by piece
+pub async fn stream_chat_completion(
+    config: &LlmConfig,
+    messages: &[ChatMessage],
+) -> Result<ChatCompletionStream> {
+    Ok(ChatCompletionStream {
+        response: send_chat_completion_request(config, messages, true).await?,
+        buffer: Vec::new(),
+        tokens: VecDeque::new(),
+        is_finished: false,
+    })
+}
+
+/// Answer of a chat model, streamed as Server-Sent Events
+pub struct ChatCompletionStream {
+    response: reqwest::Response,
+    /// Received bytes of an incomplete line. Lines may be split anywhere, even within a character.
+    buffer: Vec<u8>,
+    /// Decoded pieces of the answer that have not been taken yet
+    tokens: VecDeque<String>,
+    is_finished: bool,
+}
+
+impl ChatCompletionStream {
+    /// The next piece of the answer, or `None` once the answer is complete
+    pub async fn next_token(&mut self) -> Result<Option<String>> {
+        loop {
+            if let Some(token) = self.tokens.pop_front() {
+                return Ok(Some(token));
+            }
+            if self.is_finished {
+                return Ok(None);
+            }
+
+            match self.response.chunk().await? {
+                Some(bytes) => self.buffer.extend_from_slice(&bytes),
+                None => {
+                    // The last line may come without a line break
+                    self.buffer.push(b'\n');
+                    self.is_finished = true;
+                }
+            }
+
+            while let Some(position) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=position).collect();
+                self.decode_line(&String::from_utf8_lossy(&line))?;
+            }
+        }
+    }
+
+    /// Take the piece of the answer in a `data:` line. Comments, empty lines and other fields are skipped.
+    fn decode_line(&mut self, line: &str) -> Result<()> {
+        let data: &str = match line.trim().strip_prefix("data:") {
+            Some(data) => data.trim(),
+            None => return Ok(()),
+        };
+        if data == "[DONE]" {
+            self.is_finished = true;
+            return Ok(());
+        }
+
+        let chunk: Value = serde_json::from_str(data)?;
+        if let Some(error) = chunk.get("error") {
+            return Err(anyhow!("Chat completion has failed: {}", error));
+        }
+        if let Some(token) = chunk["choices"][0]["delta"]["content"].as_str()
+            && !token.is_empty()
+        {
+            self.tokens.push_back(token.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::spawn_stub_server;
+
+    fn create_config(base_url: String) -> LlmConfig {
+        LlmConfig {
+            base_url,
+            model: "qwen2.5".to_string(),
+            api_key: String::new(),
+            context_chunks: 8,
+            temperature: 0.2,
+            max_tokens: Some(256),
+            timeout_seconds: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_chat_completion() {
+        let (base_url, handle) = spawn_stub_server(
+            "/v1/chat/completions",
+            "application/json",
+            vec![r#"{"choices": [{"message": {"role": "assistant", "content": "Rust [1]"}}]}"#],
+        )
+        .await;
+
+        let messages = vec![ChatMessage::new("user", "Which language?".to_string())];
+        let answer = send_chat_completion(&create_config(base_url), &messages)
+            .await
+            .unwrap();
+        assert_eq!(answer, "Rust [1]");
+
+        let request_body = handle.await.unwrap();
+        assert_eq!(request_body["model"], "qwen2.5");
+        assert_eq!(request_body["stream"], false);
+        assert_eq!(request_body["max_tokens"], 256);
+        assert_eq!(request_body["messages"][0]["content"], "Which language?");
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_completion() {
+        // Events are split across the chunks, and the keep-alive comment and role are skipped
+        let (base_url, handle) = spawn_stub_server(
+            "/v1/chat/completions",
+            "text/event-stream",
+            vec![
+                ": keep-alive\n\ndata: {\"choices\": [{\"delta\": {\"role\": \"assistant\"}}]}\n\n",
+                "data: {\"choices\": [{\"delta\": {\"content\": \"Rust \"}}]}\n\ndata: {\"choices\": [{\"delta\": {\"content\": \"\u{e9}",
+                "\"}}]}\n\ndata: [DONE]\n\n",
+            ],
+        )
+        .await;
+
+        let messages = vec![ChatMessage::new("user", "Which language?".to_string())];
+        let mut stream = stream_chat_completion(&create_config(base_url), &messages)
+            .await
+            .unwrap();
+
+        let mut tokens: Vec<String> = Vec::new();
+        while let Some(token) = stream.next_token().await.unwrap() {
+            tokens.push(token);
+        }
+        assert_eq!(tokens, vec!["Rust ", "\u{e9}"]);
+
+        let request_body = handle.await.unwrap();
+        assert_eq!(request_body["stream"], true);
+    }
+}
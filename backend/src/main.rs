@@ -5,14 +5,22 @@ mod checkups;
 mod configurations;
 mod connectors;
 mod constants;
+mod data_sources;
 mod documents;
+mod embedding_cache;
 mod embedder;
 mod handlers;
 mod identities;
+mod llm;
 mod metadata_storage;
 mod routes;
+mod scheduled_jobs;
 mod search;
+mod search_history;
+mod tasks_queue;
 mod tasks_scheduler;
+#[cfg(test)]
+mod test_utilities;
 mod traits;
 mod utilities;
 mod mcp;
@@ -33,7 +41,7 @@ use routes::configure_routes;
 use sqlx::any::install_default_drivers;
 use tokio::sync::RwLock;
 
-use crate::{checkups::{align_embedder_model, handshake_embedding_service}, mcp::service::MCPService};
+use crate::{checkups::{align_document_payloads, align_embedder_model, handshake_embedding_service}, constants::{EMBEDDED_DATABASE_SAVE_INTERVAL_SECONDS, EMBEDDING_CACHE_SAVE_INTERVAL_SECONDS, SEARCH_HISTORY_SAVE_INTERVAL_SECONDS}, mcp::service::MCPService, scheduled_jobs::runner::run_scheduled_jobs};
 
 #[actix_web::main]
 async fn main() -> Result<(), std::io::Error> {
@@ -103,6 +111,26 @@ async fn main() -> Result<(), std::io::Error> {
                 "Task scheduler has {} registered tasks",
                 state.tasks_scheduler.lock().await.registered_tasks.len()
             );
+            match state.tasks_scheduler.lock().await.fail_interrupted_tasks().await {
+                Ok(count) => info!("{} interrupted tasks were marked as failed", count),
+                Err(error) => error!("Failed to mark interrupted tasks: {}", error),
+            }
+            match state
+                .scheduled_jobs_storage
+                .lock()
+                .await
+                .sync_configured_jobs(&config.scheduled_jobs.jobs)
+                .await
+            {
+                Ok(_) => info!(
+                    "{} scheduled jobs are defined in the configurations",
+                    config.scheduled_jobs.jobs.len()
+                ),
+                Err(error) => {
+                    error!("Failed to load the scheduled jobs in the configurations: {}", error);
+                    std::process::exit(1);
+                }
+            }
             info!("Database will connect to {}", config.database.base_url);
 
             // Checkups
@@ -119,6 +147,14 @@ async fn main() -> Result<(), std::io::Error> {
                 }
             }
 
+            match align_document_payloads(&config, &state).await {
+                Ok(_) => info!("Document payloads alignment completed successfully"),
+                Err(e) => {
+                    error!("Failed to align document payloads: {}", e);
+                    std::process::exit(1);
+                }
+            }
+
             web::Data::new(RwLock::new(state))
         }
         Err(e) => {
@@ -129,6 +165,83 @@ async fn main() -> Result<(), std::io::Error> {
 
     info!("Application state initialized successfully");
 
+    // Clean up the expired tasks periodically
+    let tasks_scheduler = app_state.read().await.tasks_scheduler.clone();
+    let tasks_scheduler_config = config.tasks_scheduler.clone();
+    // The ttl is bounded by the validation, so it always fits
+    let ttl: chrono::TimeDelta = i64::try_from(tasks_scheduler_config.ttl_seconds)
+        .ok()
+        .and_then(chrono::TimeDelta::try_seconds)
+        .unwrap_or(chrono::TimeDelta::MAX);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            tasks_scheduler_config.sweep_interval_seconds,
+        ));
+        loop {
+            interval.tick().await;
+            match tasks_scheduler
+                .lock()
+                .await
+                .sweep(ttl)
+                .await
+            {
+                Ok(count) if count > 0 => info!("{} expired tasks were removed", count),
+                Ok(_) => {}
+                Err(error) => error!("Failed to remove expired tasks: {}", error),
+            }
+        }
+    });
+
+    // Run the scheduled jobs when they are due
+    tokio::spawn(run_scheduled_jobs(app_state.clone()));
+
+    // Save the recorded searches in batches, rather than on every search
+    let search_history_storage = app_state.read().await.search_history_storage.clone();
+    let search_history_saver = tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(SEARCH_HISTORY_SAVE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            if let Err(error) = search_history_storage
+                .lock()
+                .await
+                .save_recorded_searches()
+                .await
+            {
+                error!("Failed to save the search history: {}", error);
+            }
+        }
+    });
+    let search_history_storage = app_state.read().await.search_history_storage.clone();
+
+    // Save the newly cached chunk vectors in batches, rather than after every vectorization
+    let embedding_cache = app_state.read().await.embedding_cache.clone();
+    let embedding_cache_saver = tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(EMBEDDING_CACHE_SAVE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            if let Err(error) = embedding_cache.save_inserted_vectors().await {
+                error!("Failed to save the embedding cache: {}", error);
+            }
+        }
+    });
+    let embedding_cache = app_state.read().await.embedding_cache.clone();
+
+    // Save the chunks written to the database in batches, for the databases that don't persist every write
+    let vector_database = app_state.read().await.database.clone();
+    let vector_database_saver = tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(EMBEDDED_DATABASE_SAVE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            if let Err(error) = vector_database.save_pending_changes().await {
+                error!("Failed to save the vector database: {}", error);
+            }
+        }
+    });
+    let vector_database = app_state.read().await.database.clone();
+
     // Start HTTP server
     let bind_address = format!("{}:{}", config.server.host, config.server.port);
     info!("Starting HTTP server on {}", bind_address);
@@ -164,10 +277,31 @@ async fn main() -> Result<(), std::io::Error> {
         info!("Using {} worker threads", workers);
     }
 
-    server
+    let result = server
         .bind(&bind_address)
         .with_context(|| format!("Failed to bind to {}", bind_address))
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
         .run()
+        .await;
+
+    // Keep what was recorded since the last saves
+    search_history_saver.abort();
+    if let Err(error) = search_history_storage
+        .lock()
+        .await
+        .save_recorded_searches()
         .await
+    {
+        error!("Failed to save the search history: {}", error);
+    }
+    embedding_cache_saver.abort();
+    if let Err(error) = embedding_cache.save_inserted_vectors().await {
+        error!("Failed to save the embedding cache: {}", error);
+    }
+    vector_database_saver.abort();
+    if let Err(error) = vector_database.save_pending_changes().await {
+        error!("Failed to save the vector database: {}", error);
+    }
+
+    result
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use schemars::JsonSchema;
 use serde::Deserialize;
 
@@ -20,4 +22,30 @@ pub struct MCPSearchDocumentRequest {
     #[schemars(description = "in which range, you want to search")]
     #[serde(flatten)]
     pub scope: SearchScopeIndicator,
-}
\ No newline at end of file
+
+    #[schemars(description = "only search the documents with these attributes, like imported fields of structured data. leave it empty to search all documents")]
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+
+    #[schemars(description = "number of results to skip. pass the next_offset of the previous results to load more")]
+    #[serde(default)]
+    pub offset: usize,
+}
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MCPRelatedDocumentsRequest {
+    #[schemars(description = "metadata id of the document to find related documents for")]
+    pub document_metadata_id: String,
+
+    #[schemars(description = "number of related documents you want. 5 is recommended for first try")]
+    pub top_n: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MCPRunSavedSearchRequest {
+    #[schemars(description = "id of the saved search. list the saved searches to find it")]
+    pub saved_search_id: String,
+
+    #[schemars(description = "number of results to skip. pass the next_offset of the previous results to load more")]
+    #[serde(default)]
+    pub offset: usize,
+}
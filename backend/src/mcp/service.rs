@@ -16,12 +16,26 @@ use serde_json::Value;
 use tokio::sync::{Mutex, RwLock};
 
 use crate::{
-    api_models::{document::GetDocumentRequest, search::SearchDocumentRequest},
+    api_models::{
+        document::GetDocumentRequest,
+        search::{RelatedDocumentsRequest, SearchDocumentRequest},
+        search_history::{GetSavedSearchesRequest, RunSavedSearchRequest},
+    },
     app_state::AppState,
     documents::document_metadata::DocumentMetadata,
-    handlers::{document::get_document_content, search::intelligent_search},
-    mcp::{requests::{MCPGetCollectionMetadata, MCPSearchDocumentRequest}, responses::MCPServiceGenericResponse},
-    search::SearchScope,
+    handlers::{
+        document::get_document_content,
+        search::{intelligent_search, related_documents},
+        search_history::{get_saved_searches, run_saved_search},
+    },
+    mcp::{
+        requests::{
+            MCPGetCollectionMetadata, MCPRelatedDocumentsRequest, MCPRunSavedSearchRequest,
+            MCPSearchDocumentRequest,
+        },
+        responses::MCPServiceGenericResponse,
+    },
+    search::{SearchFilter, SearchScope},
     utilities::acquire_data,
 };
 
@@ -49,6 +63,8 @@ impl MCPService {
             query,
             top_n,
             mut scope,
+            attributes,
+            offset,
         }): Parameters<MCPSearchDocumentRequest>,
     ) -> Json<MCPServiceGenericResponse> {
         let token = self.authorization.lock().await;
@@ -64,6 +80,116 @@ impl MCPService {
                     query,
                     top_n,
                     scope,
+                    attributes,
+                    filter: SearchFilter::default(),
+                    group_by_document: None,
+                    offset,
+                }),
+            )
+            .await
+            {
+                Ok(result) => {
+                    let result = result.into_body().try_into_bytes().unwrap().reader();
+                    let value: Value = serde_json::from_reader(result).unwrap();
+                    return Json(MCPServiceGenericResponse {
+                        results: Some(value),
+                    });
+                }
+                Err(error) => {
+                    log::warn!("MCP service reported error: {}", error);
+                    return Json(MCPServiceGenericResponse { results: None });
+                }
+            }
+        }
+
+        Json(MCPServiceGenericResponse { results: None })
+    }
+
+    #[tool(description = "Find the user's OpenNote documents that are semantically the closest to a document")]
+    pub async fn find_related_documents(
+        &self,
+        Parameters(MCPRelatedDocumentsRequest {
+            document_metadata_id,
+            top_n,
+        }): Parameters<MCPRelatedDocumentsRequest>,
+    ) -> Json<MCPServiceGenericResponse> {
+        let token = self.authorization.lock().await;
+
+        if let Some(token) = token.as_ref() {
+            match related_documents(
+                self.app_state.clone(),
+                actix_web::web::Json(RelatedDocumentsRequest {
+                    username: token.to_string(),
+                    document_metadata_id,
+                    top_n,
+                }),
+            )
+            .await
+            {
+                Ok(result) => {
+                    let result = result.into_body().try_into_bytes().unwrap().reader();
+                    let value: Value = serde_json::from_reader(result).unwrap();
+                    return Json(MCPServiceGenericResponse {
+                        results: Some(value),
+                    });
+                }
+                Err(error) => {
+                    log::warn!("MCP service reported error: {}", error);
+                    return Json(MCPServiceGenericResponse { results: None });
+                }
+            }
+        }
+
+        Json(MCPServiceGenericResponse { results: None })
+    }
+
+    #[tool(description = "List the searches that the user has saved in OpenNote")]
+    pub async fn get_saved_searches(&self) -> Json<MCPServiceGenericResponse> {
+        let token = self.authorization.lock().await;
+
+        if let Some(token) = token.as_ref() {
+            match get_saved_searches(
+                self.app_state.clone(),
+                actix_web::web::Json(GetSavedSearchesRequest {
+                    username: token.to_string(),
+                }),
+            )
+            .await
+            {
+                Ok(result) => {
+                    let result = result.into_body().try_into_bytes().unwrap().reader();
+                    let value: Value = serde_json::from_reader(result).unwrap();
+                    return Json(MCPServiceGenericResponse {
+                        results: Some(value),
+                    });
+                }
+                Err(error) => {
+                    log::warn!("MCP service reported error: {}", error);
+                    return Json(MCPServiceGenericResponse { results: None });
+                }
+            }
+        }
+
+        Json(MCPServiceGenericResponse { results: None })
+    }
+
+    #[tool(description = "Run a search that the user has saved in OpenNote")]
+    pub async fn run_saved_search(
+        &self,
+        Parameters(MCPRunSavedSearchRequest {
+            saved_search_id,
+            offset,
+        }): Parameters<MCPRunSavedSearchRequest>,
+    ) -> Json<MCPServiceGenericResponse> {
+        let token = self.authorization.lock().await;
+
+        if let Some(token) = token.as_ref() {
+            match run_saved_search(
+                self.app_state.clone(),
+                actix_web::web::Json(RunSavedSearchRequest {
+                    username: token.to_string(),
+                    saved_search_id,
+                    offset,
                 }),
             )
             .await
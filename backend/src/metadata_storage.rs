@@ -6,7 +6,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     documents::{
-        collection_metadata::CollectionMetadata, document_metadata::DocumentMetadata,
+        collection_metadata::CollectionMetadata,
+        document_metadata::{DocumentMetadata, DocumentPayload},
         traits::ValidateDataMutabilitiesForAPICaller,
     },
     traits::LoadAndSave,
@@ -22,6 +23,14 @@ pub struct MetadataStorage {
     #[serde(default)]
     pub embedder_model_vector_size_in_use: usize,
 
+    /// Document prefix that the chunks in the database were vectorized with
+    #[serde(default)]
+    pub embedder_document_prefix_in_use: String,
+
+    /// Version of the document fields copied onto the chunks in the database
+    #[serde(default)]
+    pub document_payloads_version: usize,
+
     // key-value pair: collection id, DocumentMetadata
     pub collections: HashMap<String, CollectionMetadata>,
 
@@ -35,6 +44,8 @@ impl Default for MetadataStorage {
             path: PathBuf::new(),
             embedder_model_in_use: String::new(),
             embedder_model_vector_size_in_use: usize::default(),
+            embedder_document_prefix_in_use: String::new(),
+            document_payloads_version: usize::default(),
             documents: HashMap::new(),
             collections: HashMap::new(),
         }
@@ -154,6 +165,7 @@ impl MetadataStorage {
                 // Swap the immutable fields values in
                 metadata.chunks = original_document_metadata.chunks.clone();
                 metadata.created_at = original_document_metadata.created_at.clone();
+                metadata.provenance = original_document_metadata.provenance.clone();
 
                 // If the document is being moved to another collection, verify if the destination collection exists
                 if metadata.collection_metadata_id
@@ -275,6 +287,15 @@ impl MetadataStorage {
         document_metadata
     }
 
+    /// Payloads of the documents, for copying onto their chunks in the database
+    pub fn get_document_payloads(&self, document_metadata_ids: &[String]) -> Vec<DocumentPayload> {
+        document_metadata_ids
+            .iter()
+            .filter_map(|id| self.documents.get(id))
+            .map(DocumentPayload::from)
+            .collect()
+    }
+
     pub fn get_document_ids_by_collection(&self, collection_metadata_id: &str) -> Vec<&String> {
         if let Some(collection_metadata) = self.collections.get(collection_metadata_id) {
             return collection_metadata
@@ -5,12 +5,21 @@ use crate::handlers::{
     collection::{
         create_collection, delete_collection, get_collections, update_collections_metadata,
     },
+    data_source::{create_data_source, delete_data_source, get_data_sources},
     document::{
         add_document, delete_document, get_document_content, get_documents_metadata,
-        import_documents, reindex, update_document_content, update_documents_metadata,
+        import_documents, refresh_collection, refresh_document, reindex, update_document_content,
+        update_documents_metadata,
+    },
+    general::{
+        cancel_task, get_info, get_tasks, health_check, retrieve_task_result, subscribe_task_updates,
+    },
+    scheduled_job::{create_scheduled_job, delete_scheduled_job, get_scheduled_jobs},
+    search::{ask, hybrid_search, intelligent_search, related_documents, search},
+    search_history::{
+        clear_search_history, create_saved_search, delete_saved_search, get_saved_searches,
+        get_search_history, run_saved_search,
     },
-    general::{get_info, health_check, retrieve_task_result},
-    search::{intelligent_search, search},
     user::{create_user, get_user_configurations, get_user_configurations_schemars, login, update_user_configurations},
 };
 
@@ -22,6 +31,12 @@ pub fn configure_routes() -> Scope {
             "/retrieve_task_result",
             web::post().to(retrieve_task_result),
         )
+        .route(
+            "/subscribe_task_updates",
+            web::get().to(subscribe_task_updates),
+        )
+        .route("/get_tasks", web::post().to(get_tasks))
+        .route("/cancel_task", web::post().to(cancel_task))
         .service(
             web::scope("users")
                 .route("/sync/create_user", web::post().to(create_user))
@@ -60,6 +75,8 @@ pub fn configure_routes() -> Scope {
                     "/async/update_document_content",
                     web::post().to(update_document_content),
                 )
+                .route("/async/refresh_document", web::post().to(refresh_document))
+                .route("/async/refresh_collection", web::post().to(refresh_collection))
                 .route(
                     "/sync/get_document_content",
                     web::post().to(get_document_content),
@@ -69,13 +86,22 @@ pub fn configure_routes() -> Scope {
                     web::post().to(get_documents_metadata),
                 ),
         )
+        .service(
+            web::scope("data_sources")
+                .route("/sync/create_data_source", web::post().to(create_data_source))
+                .route("/sync/delete_data_source", web::post().to(delete_data_source))
+                .route("/sync/get_data_sources", web::post().to(get_data_sources)),
+        )
         .service(
             web::scope("search")
                 .route(
                     "/sync/intelligent_search",
                     web::post().to(intelligent_search),
                 )
-                .route("/sync/search", web::post().to(search)),
+                .route("/sync/search", web::post().to(search))
+                .route("/sync/hybrid_search", web::post().to(hybrid_search))
+                .route("/sync/related_documents", web::post().to(related_documents))
+                .route("/sync/ask", web::post().to(ask)),
         )
         .service(
             web::scope("backup")
@@ -84,4 +110,19 @@ pub fn configure_routes() -> Scope {
                 .route("/async/backup", web::post().to(backup))
                 .route("/async/restore_backup", web::post().to(restore_backup)),
         )
+        .service(
+            web::scope("scheduled_jobs")
+                .route("/sync/create_scheduled_job", web::post().to(create_scheduled_job))
+                .route("/sync/delete_scheduled_job", web::post().to(delete_scheduled_job))
+                .route("/sync/get_scheduled_jobs", web::post().to(get_scheduled_jobs)),
+        )
+        .service(
+            web::scope("search_history")
+                .route("/sync/get_search_history", web::post().to(get_search_history))
+                .route("/sync/clear_search_history", web::post().to(clear_search_history))
+                .route("/sync/create_saved_search", web::post().to(create_saved_search))
+                .route("/sync/delete_saved_search", web::post().to(delete_saved_search))
+                .route("/sync/get_saved_searches", web::post().to(get_saved_searches))
+                .route("/sync/run_saved_search", web::post().to(run_saved_search)),
+        )
 }
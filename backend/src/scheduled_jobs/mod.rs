@@ -0,0 +1,6 @@
+//! Jobs that run periodically on cron schedules, like nightly backups.
+//! Each run is a task, so the run history of a job is kept by the tasks scheduler.
+
+pub mod runner;
+pub mod scheduled_job;
+pub mod storage;
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde_json::Map;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::scheduled_job::{ScheduledJob, ScheduledJobKind};
+use crate::{
+    api_models::document::{RefreshCollectionRequest, RefreshCredentials},
+    app_state::AppState,
+    backup::scope::{BackupScope, BackupScopeIndicator},
+    constants::SCHEDULED_JOBS_TICK_SECONDS,
+    handlers::{backup::run_backup_task, document::run_refresh_collection_task},
+    tasks_scheduler::TaskStatus,
+    traits::LoadAndSave,
+};
+
+/// Start the due jobs periodically. It never returns.
+pub async fn run_scheduled_jobs(data: web::Data<RwLock<AppState>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULED_JOBS_TICK_SECONDS));
+    loop {
+        interval.tick().await;
+        start_due_jobs(&data, Utc::now()).await;
+    }
+}
+
+async fn start_due_jobs(data: &web::Data<RwLock<AppState>>, now: DateTime<Utc>) {
+    let (scheduled_jobs_storage, tasks_scheduler, tasks_queue) = {
+        let state = data.read().await;
+        (
+            state.scheduled_jobs_storage.clone(),
+            state.tasks_scheduler.clone(),
+            state.tasks_queue.clone(),
+        )
+    };
+
+    let mut scheduled_jobs_storage = scheduled_jobs_storage.lock().await;
+    let due_jobs: Vec<ScheduledJob> = scheduled_jobs_storage
+        .scheduled_jobs
+        .iter()
+        .filter(|scheduled_job| scheduled_job.is_due(now))
+        .cloned()
+        .collect();
+
+    if due_jobs.is_empty() {
+        return;
+    }
+
+    for scheduled_job in due_jobs {
+        // Prevent overlapping runs of the same job
+        let is_running: bool = match &scheduled_job.last_task_id {
+            Some(task_id) => tasks_scheduler
+                .lock()
+                .await
+                .search_by_task_id(task_id)
+                .is_some_and(|task| task.status == TaskStatus::InProgress),
+            None => false,
+        };
+
+        if is_running {
+            warn!(
+                "Scheduled job `{}` is skipped, as its previous run is still in progress",
+                scheduled_job.name
+            );
+            scheduled_jobs_storage.set_skipped(&scheduled_job.id, now);
+            continue;
+        }
+
+        let task_id: String = tasks_scheduler
+            .lock()
+            .await
+            .create_new_scheduled_task(&scheduled_job.owner, &scheduled_job.id)
+            .await;
+
+        match &scheduled_job.kind {
+            ScheduledJobKind::Backup => {
+                let scope = BackupScopeIndicator {
+                    scope: BackupScope::User,
+                    id: scheduled_job.owner.clone(),
+                    backup_id: Uuid::new_v4().to_string(),
+                };
+                tasks_queue
+                    .submit(&task_id, run_backup_task(data.clone(), task_id.clone(), scope))
+                    .await;
+            }
+            ScheduledJobKind::RefreshCollection {
+                collection_metadata_id,
+            } => {
+                let request = RefreshCollectionRequest {
+                    collection_metadata_id: collection_metadata_id.clone(),
+                    credentials: RefreshCredentials {
+                        username: scheduled_job.owner.clone(),
+                        secrets: Map::new(),
+                    },
+                };
+                tasks_queue
+                    .submit(
+                        &task_id,
+                        run_refresh_collection_task(data.clone(), task_id.clone(), request),
+                    )
+                    .await;
+            }
+        }
+
+        info!(
+            "Scheduled job `{}` has started as task {}",
+            scheduled_job.name, task_id
+        );
+        scheduled_jobs_storage.set_started(&scheduled_job.id, &task_id, now);
+    }
+
+    if let Err(error) = scheduled_jobs_storage.save().await {
+        error!("Failed to save the scheduled jobs: {}", error);
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduledJobKind {
+    /// Back up the resources of the user
+    Backup,
+    /// Refresh the imported documents of a collection from their sources.
+    /// Secrets are not kept, so only the documents imported with data sources
+    /// or without secrets can be refreshed.
+    RefreshCollection { collection_metadata_id: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub id: String,
+
+    pub name: String,
+
+    /// Username of the user that the job runs on behalf of
+    pub owner: String,
+
+    /// Cron expression in UTC. Both the standard 5 fields and 6 fields with seconds are accepted.
+    pub cron_expression: String,
+
+    #[serde(flatten)]
+    pub kind: ScheduledJobKind,
+
+    /// Defined in the configurations, rather than through the API
+    #[serde(default)]
+    pub is_from_config: bool,
+
+    pub created_at: DateTime<Utc>,
+
+    #[serde(default)]
+    pub last_run_at: Option<DateTime<Utc>>,
+
+    /// Task of the last run. Its status tells how the last run went.
+    #[serde(default)]
+    pub last_task_id: Option<String>,
+
+    /// Runs that were skipped, because the previous run was still in progress
+    #[serde(default)]
+    pub skipped_runs: usize,
+
+    /// Empty if the cron expression has no more occurrences
+    #[serde(default)]
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    pub fn new(
+        name: String,
+        owner: String,
+        cron_expression: String,
+        kind: ScheduledJobKind,
+    ) -> Result<Self> {
+        let next_run_at: Option<DateTime<Utc>> =
+            parse_cron_expression(&cron_expression)?.after(&Utc::now()).next();
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            owner,
+            cron_expression,
+            kind,
+            is_from_config: false,
+            created_at: Utc::now(),
+            last_run_at: None,
+            last_task_id: None,
+            skipped_runs: 0,
+            next_run_at,
+        })
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run_at.is_some_and(|next_run_at| next_run_at <= now)
+    }
+
+    /// Move on to the next occurrence after `now`.
+    /// Occurrences missed while the backend was down are not caught up on.
+    pub fn schedule_next_run(&mut self, now: DateTime<Utc>) {
+        self.next_run_at = match parse_cron_expression(&self.cron_expression) {
+            Ok(schedule) => schedule.after(&now).next(),
+            Err(_) => None,
+        };
+    }
+}
+
+/// Standard cron expressions have no seconds field, which the parser requires
+pub fn parse_cron_expression(cron_expression: &str) -> Result<Schedule> {
+    let normalized_cron_expression: String = match cron_expression.split_whitespace().count() {
+        5 => format!("0 {}", cron_expression),
+        _ => cron_expression.to_string(),
+    };
+
+    Schedule::from_str(&normalized_cron_expression)
+        .map_err(|error| anyhow!("Invalid cron expression `{}`: {}", cron_expression, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_schedule_next_run() {
+        let mut scheduled_job = ScheduledJob::new(
+            "nightly backup".to_string(),
+            "alice".to_string(),
+            "0 3 * * *".to_string(),
+            ScheduledJobKind::Backup,
+        )
+        .unwrap();
+
+        let now: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+        scheduled_job.next_run_at = Some(now);
+        assert!(scheduled_job.is_due(now));
+
+        scheduled_job.schedule_next_run(now);
+        assert_eq!(
+            scheduled_job.next_run_at,
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 0, 0).unwrap())
+        );
+        assert!(!scheduled_job.is_due(now));
+
+        assert!(parse_cron_expression("0 0 3 * * *").is_ok());
+        assert!(parse_cron_expression("every night").is_err());
+    }
+}
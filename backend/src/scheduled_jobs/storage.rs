@@ -0,0 +1,191 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::scheduled_job::ScheduledJob;
+use crate::{configurations::system::ScheduledJobConfig, traits::LoadAndSave};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJobsStorage {
+    pub path: PathBuf,
+    pub scheduled_jobs: Vec<ScheduledJob>,
+}
+
+impl LoadAndSave for ScheduledJobsStorage {
+    fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            scheduled_jobs: Vec::new(),
+        }
+    }
+
+    fn get_path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl ScheduledJobsStorage {
+    pub async fn add_scheduled_job(&mut self, scheduled_job: ScheduledJob) -> Result<String> {
+        if self
+            .scheduled_jobs
+            .iter()
+            .any(|item| item.owner == scheduled_job.owner && item.name == scheduled_job.name)
+        {
+            return Err(anyhow!(
+                "Scheduled job `{}` has already existed",
+                scheduled_job.name
+            ));
+        }
+
+        let id: String = scheduled_job.id.clone();
+        self.scheduled_jobs.push(scheduled_job);
+        self.save().await?;
+
+        Ok(id)
+    }
+
+    /// Jobs from the configurations can only be removed by editing the configurations
+    pub async fn remove_scheduled_job(&mut self, id: &str, owner: &str) -> Result<()> {
+        let scheduled_job: &ScheduledJob = match self
+            .scheduled_jobs
+            .iter()
+            .find(|item| item.id == id && item.owner == owner)
+        {
+            Some(result) => result,
+            None => return Err(anyhow!("Scheduled job {} was not found", id)),
+        };
+
+        if scheduled_job.is_from_config {
+            return Err(anyhow!(
+                "Scheduled job `{}` is defined in the configurations, and cannot be removed",
+                scheduled_job.name
+            ));
+        }
+
+        self.scheduled_jobs
+            .retain(|item| !(item.id == id && item.owner == owner));
+        self.save().await?;
+
+        Ok(())
+    }
+
+    pub fn get_scheduled_jobs_by_owner(&self, owner: &str) -> Vec<&ScheduledJob> {
+        self.scheduled_jobs
+            .iter()
+            .filter(|item| item.owner == owner)
+            .collect()
+    }
+
+    /// Replace the jobs from the configurations with the current ones.
+    /// Jobs that are kept in the configurations keep their ids and run history.
+    pub async fn sync_configured_jobs(&mut self, configs: &[ScheduledJobConfig]) -> Result<()> {
+        let mut previous_jobs: Vec<ScheduledJob> = Vec::new();
+        self.scheduled_jobs.retain(|item| {
+            if item.is_from_config {
+                previous_jobs.push(item.clone());
+            }
+
+            !item.is_from_config
+        });
+
+        for config in configs {
+            let mut scheduled_job = ScheduledJob::new(
+                config.name.clone(),
+                config.username.clone(),
+                config.cron_expression.clone(),
+                config.kind.clone(),
+            )?;
+            scheduled_job.is_from_config = true;
+
+            if let Some(previous_job) = previous_jobs
+                .iter()
+                .find(|item| item.owner == scheduled_job.owner && item.name == scheduled_job.name)
+            {
+                scheduled_job.id = previous_job.id.clone();
+                scheduled_job.created_at = previous_job.created_at;
+                scheduled_job.last_run_at = previous_job.last_run_at;
+                scheduled_job.last_task_id = previous_job.last_task_id.clone();
+                scheduled_job.skipped_runs = previous_job.skipped_runs;
+            }
+
+            if self
+                .scheduled_jobs
+                .iter()
+                .any(|item| item.owner == scheduled_job.owner && item.name == scheduled_job.name)
+            {
+                return Err(anyhow!(
+                    "Scheduled job `{}` of user {} is defined more than once",
+                    scheduled_job.name,
+                    scheduled_job.owner
+                ));
+            }
+
+            self.scheduled_jobs.push(scheduled_job);
+        }
+
+        self.save().await
+    }
+
+    /// Record a run that has started with the task
+    pub fn set_started(&mut self, id: &str, task_id: &str, now: DateTime<Utc>) {
+        if let Some(scheduled_job) = self.scheduled_jobs.iter_mut().find(|item| item.id == id) {
+            scheduled_job.last_run_at = Some(now);
+            scheduled_job.last_task_id = Some(task_id.to_string());
+            scheduled_job.schedule_next_run(now);
+        }
+    }
+
+    /// Record a run that was skipped, because the previous one was still in progress
+    pub fn set_skipped(&mut self, id: &str, now: DateTime<Utc>) {
+        if let Some(scheduled_job) = self.scheduled_jobs.iter_mut().find(|item| item.id == id) {
+            scheduled_job.skipped_runs += 1;
+            scheduled_job.schedule_next_run(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduled_jobs::scheduled_job::ScheduledJobKind;
+    use crate::test_utilities::TemporaryFile;
+
+    #[tokio::test]
+    async fn test_sync_configured_jobs_keeps_history() {
+        let path = TemporaryFile::new("scheduled_jobs");
+        let mut scheduled_jobs_storage = ScheduledJobsStorage::new(path.to_str().unwrap());
+        let configs: Vec<ScheduledJobConfig> = vec![ScheduledJobConfig {
+            name: "nightly backup".to_string(),
+            username: "alice".to_string(),
+            cron_expression: "0 3 * * *".to_string(),
+            kind: ScheduledJobKind::Backup,
+        }];
+
+        scheduled_jobs_storage.sync_configured_jobs(&configs).await.unwrap();
+        let id: String = scheduled_jobs_storage.scheduled_jobs[0].id.clone();
+        scheduled_jobs_storage.set_started(&id, "task", Utc::now());
+
+        // Reload as if the backend has restarted
+        scheduled_jobs_storage.save().await.unwrap();
+        let mut scheduled_jobs_storage =
+            ScheduledJobsStorage::load(path.to_str().unwrap()).unwrap();
+        scheduled_jobs_storage.sync_configured_jobs(&configs).await.unwrap();
+        assert_eq!(scheduled_jobs_storage.scheduled_jobs.len(), 1);
+        assert_eq!(scheduled_jobs_storage.scheduled_jobs[0].id, id);
+        assert_eq!(
+            scheduled_jobs_storage.scheduled_jobs[0].last_task_id.as_deref(),
+            Some("task")
+        );
+        assert!(
+            scheduled_jobs_storage
+                .remove_scheduled_job(&id, "alice")
+                .await
+                .is_err()
+        );
+
+        scheduled_jobs_storage.sync_configured_jobs(&[]).await.unwrap();
+        assert!(scheduled_jobs_storage.scheduled_jobs.is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+//! Retrieval-augmented question answering.
+//! The best matching chunks are numbered and given to the chat model as the context,
+//! so that the answer can cite them by their numbers.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{documents::document_chunk::DocumentChunkSearchResult, llm::ChatMessage};
+
+const SYSTEM_PROMPT: &str = "You answer questions about the notes of the user. \
+Only use the numbered sources below. Cite the sources you use by their numbers in square brackets, like [1]. \
+If the sources do not contain the answer, say that the notes do not cover it. \
+Answer in the language of the question.";
+
+/// A source given to the chat model, which the answer refers to as `[number]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub number: usize,
+    pub document_metadata_id: String,
+    pub document_chunk_id: String,
+    pub document_title: Option<String>,
+    pub collection_title: Option<String>,
+}
+
+pub fn build_citations(results: &[DocumentChunkSearchResult]) -> Vec<Citation> {
+    results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| Citation {
+            number: index + 1,
+            document_metadata_id: result.document_chunk.document_metadata_id.clone(),
+            document_chunk_id: result.document_chunk.id.clone(),
+            document_title: result.document_title.clone(),
+            collection_title: result.collection_title.clone(),
+        })
+        .collect()
+}
+
+/// The system prompt with the numbered sources, followed by the question
+pub fn build_messages(question: &str, results: &[DocumentChunkSearchResult]) -> Vec<ChatMessage> {
+    let mut prompt: String = SYSTEM_PROMPT.to_string();
+    prompt.push_str("\n\nSources:");
+    for (index, result) in results.iter().enumerate() {
+        prompt.push_str(&format!(
+            "\n\n[{}] {}\n{}",
+            index + 1,
+            result.document_title.as_deref().unwrap_or("Untitled"),
+            result.document_chunk.content
+        ));
+    }
+
+    vec![
+        ChatMessage::new("system", prompt),
+        ChatMessage::new("user", question.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::documents::document_chunk::DocumentChunk;
+
+    #[test]
+    fn test_build_messages_and_citations() {
+        let results = vec![
+            DocumentChunkSearchResult {
+                document_chunk: DocumentChunk {
+                    id: "chunk-1".to_string(),
+                    document_metadata_id: "document-1".to_string(),
+                    content: "Rust has no garbage collector.".to_string(),
+                    ..Default::default()
+                },
+                document_title: Some("Rust".to_string()),
+                ..Default::default()
+            },
+            DocumentChunkSearchResult {
+                document_chunk: DocumentChunk {
+                    id: "chunk-2".to_string(),
+                    document_metadata_id: "document-2".to_string(),
+                    content: "Go has a garbage collector.".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+
+        let messages = build_messages("Which one has a garbage collector?", &results);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].content.contains("[1] Rust\nRust has no garbage collector."));
+        assert!(messages[0].content.contains("[2] Untitled\nGo has a garbage collector."));
+        assert_eq!(messages[1].role, "user");
+
+        let citations = build_citations(&results);
+        assert_eq!(citations[1].number, 2);
+        assert_eq!(citations[1].document_chunk_id, "chunk-2");
+        assert_eq!(citations[1].document_metadata_id, "document-2");
+    }
+}
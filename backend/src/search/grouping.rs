@@ -0,0 +1,135 @@
+//! Document-level search results, built from the matching chunks.
+//! Long documents often have many near-duplicate hits, so grouping them
+//! leaves room in the results for the other documents.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::GROUPED_SEARCH_CANDIDATES_FACTOR,
+    documents::document_chunk::DocumentChunkSearchResult,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentSearchResult {
+    pub document_metadata_id: String,
+    pub collection_metadata_id: String,
+    pub document_title: Option<String>,
+    pub collection_title: Option<String>,
+    /// Score of the best matching chunk
+    pub score: f32,
+    /// Best matching chunks of the document, the best first
+    pub chunks: Vec<DocumentChunkSearchResult>,
+}
+
+/// Reranked results are ordered by the reranker scores, the others by the retrieval scores
+fn get_ranking_score(result: &DocumentChunkSearchResult) -> f32 {
+    result.rerank_score.unwrap_or(result.score)
+}
+
+/// How many chunks to search for, so that `documents` documents are likely to be filled
+pub fn get_grouping_candidates(documents: usize, chunks_per_document: usize) -> usize {
+    documents
+        .saturating_mul(chunks_per_document.max(1))
+        .saturating_mul(GROUPED_SEARCH_CANDIDATES_FACTOR)
+}
+
+/// Group ranked chunks by their documents.
+/// Documents are ordered by their best chunks, and keep at most `chunks_per_document` chunks.
+pub fn group_search_results(
+    results: Vec<DocumentChunkSearchResult>,
+    chunks_per_document: usize,
+) -> Vec<DocumentSearchResult> {
+    let mut groups: Vec<DocumentSearchResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for result in results {
+        let document_metadata_id: &String = &result.document_chunk.document_metadata_id;
+        match positions.get(document_metadata_id) {
+            Some(position) => groups[*position].chunks.push(result),
+            None => {
+                positions.insert(document_metadata_id.clone(), groups.len());
+                groups.push(DocumentSearchResult {
+                    document_metadata_id: document_metadata_id.clone(),
+                    collection_metadata_id: result.document_chunk.collection_metadata_id.clone(),
+                    document_title: result.document_title.clone(),
+                    collection_title: result.collection_title.clone(),
+                    score: get_ranking_score(&result),
+                    chunks: vec![result],
+                });
+            }
+        }
+    }
+
+    // The input is usually ranked already, sorting keeps the order right when it is not
+    for group in groups.iter_mut() {
+        group
+            .chunks
+            .sort_by(|a, b| get_ranking_score(b).total_cmp(&get_ranking_score(a)));
+        group.chunks.truncate(chunks_per_document);
+        group.score = group.chunks.first().map_or(0.0, get_ranking_score);
+    }
+    groups.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::documents::document_chunk::DocumentChunk;
+
+    fn create_result(id: &str, document_metadata_id: &str, score: f32) -> DocumentChunkSearchResult {
+        DocumentChunkSearchResult {
+            document_chunk: DocumentChunk {
+                id: id.to_string(),
+                document_metadata_id: document_metadata_id.to_string(),
+                ..Default::default()
+            },
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_search_results() {
+        let results = vec![
+            create_result("a1", "a", 0.9),
+            create_result("a2", "a", 0.8),
+            create_result("b1", "b", 0.7),
+            create_result("a3", "a", 0.6),
+            create_result("c1", "c", 0.5),
+            create_result("b2", "b", 0.4),
+        ];
+
+        let groups = group_search_results(results, 2);
+
+        let document_ids: Vec<&str> = groups
+            .iter()
+            .map(|group| group.document_metadata_id.as_str())
+            .collect();
+        assert_eq!(document_ids, vec!["a", "b", "c"]);
+
+        let chunk_ids: Vec<&str> = groups[0]
+            .chunks
+            .iter()
+            .map(|chunk| chunk.document_chunk.id.as_str())
+            .collect();
+        assert_eq!(chunk_ids, vec!["a1", "a2"]);
+        assert_eq!(groups[1].chunks.len(), 2);
+        assert_eq!(groups[1].score, 0.7);
+    }
+
+    #[test]
+    fn test_group_reranked_results() {
+        let mut results = vec![create_result("a1", "a", 0.9), create_result("b1", "b", 0.5)];
+        results[0].rerank_score = Some(0.1);
+        results[1].rerank_score = Some(0.8);
+
+        let groups = group_search_results(results, 3);
+
+        assert_eq!(groups[0].document_metadata_id, "b");
+        assert_eq!(groups[0].score, 0.8);
+    }
+}
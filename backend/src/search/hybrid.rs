@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::MutexGuard;
+
+use crate::{
+    configurations::{search::HybridSearchConfiguration, system::EmbedderConfig},
+    constants::RECIPROCAL_RANK_FUSION_K,
+    documents::document_chunk::DocumentChunkSearchResult,
+    metadata_storage::MetadataStorage,
+    search::SearchFilter,
+};
+
+/// What a hybrid search looks for, and how
+pub struct HybridSearchOptions<'a> {
+    pub document_metadata_ids: Vec<String>,
+    pub filter: &'a SearchFilter,
+    pub query: &'a str,
+    pub top_n: usize,
+    pub embedder_config: &'a EmbedderConfig,
+    pub configuration: &'a HybridSearchConfiguration,
+}
+
+#[async_trait]
+pub trait HybridSearch {
+    /// Search with both the dense vectors and the keywords, then fuse the rankings
+    async fn search_documents_hybrid(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        options: HybridSearchOptions<'_>,
+    ) -> Result<Vec<DocumentChunkSearchResult>>;
+}
+
+/// Weighted reciprocal rank fusion. Each list contributes `weight / (k + rank)` to the score
+/// of a chunk, so the scores of different methods don't need to be on the same scale.
+/// Lists with non-positive weights are left out.
+pub fn fuse_search_results(
+    ranked_lists: Vec<(Vec<DocumentChunkSearchResult>, f32)>,
+    top_n: usize,
+) -> Vec<DocumentChunkSearchResult> {
+    let mut fused: Vec<DocumentChunkSearchResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (results, weight) in ranked_lists {
+        if weight <= 0.0 {
+            continue;
+        }
+
+        for (rank, mut result) in results.into_iter().enumerate() {
+            let score: f32 = weight / (RECIPROCAL_RANK_FUSION_K + rank as f32 + 1.0);
+            match positions.get(&result.document_chunk.id) {
+                Some(position) => {
+                    let fused_result: &mut DocumentChunkSearchResult = &mut fused[*position];
+                    fused_result.score += score;
+                    // Only the keyword results have highlights
+                    if fused_result.highlights.is_empty() {
+                        fused_result.highlights = result.highlights;
+                    }
+                }
+                None => {
+                    positions.insert(result.document_chunk.id.clone(), fused.len());
+                    result.score = score;
+                    fused.push(result);
+                }
+            }
+        }
+    }
+
+    // Stable, so ties keep the order of the earlier lists
+    fused.sort_by(|left, right| right.score.total_cmp(&left.score));
+    fused.truncate(top_n);
+
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::documents::document_chunk::DocumentChunk;
+
+    fn create_results(ids: &[&str]) -> Vec<DocumentChunkSearchResult> {
+        ids.iter()
+            .map(|id| DocumentChunkSearchResult {
+                document_chunk: DocumentChunk {
+                    id: id.to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn get_ids(results: &[DocumentChunkSearchResult]) -> Vec<&str> {
+        results
+            .iter()
+            .map(|result| result.document_chunk.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_fuse_search_results() {
+        let dense = create_results(&["a", "b", "c"]);
+        let sparse = create_results(&["c", "d", "a"]);
+
+        // `a` and `c` are found by both
+        let results = fuse_search_results(vec![(dense.clone(), 1.0), (sparse.clone(), 1.0)], 10);
+        assert_eq!(get_ids(&results), vec!["a", "c", "b", "d"]);
+        assert!(results[0].score > results[2].score);
+
+        // Favouring the keywords puts `c` first
+        let results = fuse_search_results(vec![(dense.clone(), 1.0), (sparse.clone(), 3.0)], 2);
+        assert_eq!(get_ids(&results), vec!["c", "a"]);
+
+        // A zero weight leaves the list out
+        let results = fuse_search_results(vec![(dense, 0.0), (sparse, 1.0)], 10);
+        assert_eq!(get_ids(&results), vec!["c", "d", "a"]);
+    }
+}
@@ -4,6 +4,7 @@ use tokio::sync::MutexGuard;
 
 use crate::{
     documents::document_chunk::DocumentChunkSearchResult, metadata_storage::MetadataStorage,
+    search::SearchFilter,
 };
 
 #[async_trait]
@@ -12,7 +13,16 @@ pub trait KeywordSearch {
         &self,
         metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
         document_metadata_ids: Vec<String>,
+        filter: &SearchFilter,
         query: &str,
         top_n: usize,
     ) -> Result<Vec<DocumentChunkSearchResult>>;
+
+    /// Approximate number of the chunks matching the query, for pagination
+    async fn count_keyword_hits(
+        &self,
+        document_metadata_ids: Vec<String>,
+        filter: &SearchFilter,
+        query: &str,
+    ) -> Result<usize>;
 }
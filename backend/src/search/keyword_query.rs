@@ -0,0 +1,414 @@
+//! Keyword queries: parse them, then match, score and highlight the chunks with them.
+//!
+//! Words are joined with OR, unless `AND` is put between them.
+//! `NOT` or a leading `-` excludes a word from the results, and quotes make a phrase.
+//! For example, `"vector database" AND rust NOT python` looks for chunks with both the phrase
+//! and `rust`, but without `python`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use jieba_rs::{Jieba, TokenizeMode};
+
+use crate::{
+    constants::{BM25_B, BM25_K1},
+    documents::document_chunk::{DocumentChunk, DocumentChunkSearchResult, HighlightSpan},
+};
+
+/// Loading the dictionary takes a while, so share one across the searches
+static JIEBA: LazyLock<Jieba> = LazyLock::new(Jieba::new);
+
+/// A word of a text, with where it is in characters
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzedToken {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Lowercased words of the text. Chinese is cut into words as well.
+/// Punctuation and whitespaces are dropped.
+pub fn analyze(text: &str) -> Vec<AnalyzedToken> {
+    JIEBA
+        .tokenize(text, TokenizeMode::Default, false)
+        .into_iter()
+        .filter(|token| token.word.chars().all(|character| character.is_alphanumeric()))
+        .map(|token| AnalyzedToken {
+            text: token.word.to_lowercase(),
+            start: token.start,
+            end: token.end,
+        })
+        .collect()
+}
+
+/// A word, or a phrase of consecutive words
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub tokens: Vec<String>,
+}
+
+impl Term {
+    pub fn is_phrase(&self) -> bool {
+        self.tokens.len() > 1
+    }
+
+    /// Positions of the first and the last token of each occurrence
+    fn find(&self, tokens: &[AnalyzedToken]) -> Vec<(usize, usize)> {
+        if self.tokens.is_empty() || tokens.len() < self.tokens.len() {
+            return Vec::new();
+        }
+
+        (0..=tokens.len() - self.tokens.len())
+            .filter(|start| {
+                self.tokens
+                    .iter()
+                    .enumerate()
+                    .all(|(offset, token)| tokens[start + offset].text == *token)
+            })
+            .map(|start| (start, start + self.tokens.len() - 1))
+            .collect()
+    }
+}
+
+enum QueryItem {
+    Text(String),
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeywordQuery {
+    /// A chunk matches if it has all the terms of any of the clauses
+    pub clauses: Vec<Vec<Term>>,
+    /// A chunk doesn't match if it has any of these
+    pub excluded: Vec<Term>,
+}
+
+impl KeywordQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut keyword_query = Self::default();
+        let mut clause: Vec<Term> = Vec::new();
+        let mut is_negated: bool = false;
+        let mut is_joined_with_and: bool = false;
+
+        for item in lex(query) {
+            let text: String = match item {
+                QueryItem::And => {
+                    is_joined_with_and = true;
+                    continue;
+                }
+                QueryItem::Or => {
+                    is_joined_with_and = false;
+                    continue;
+                }
+                QueryItem::Not => {
+                    is_negated = true;
+                    continue;
+                }
+                QueryItem::Text(text) => text,
+            };
+
+            // A word may turn out to be several words, like `state-of-the-art`, which makes a phrase
+            let tokens: Vec<String> = analyze(&text).into_iter().map(|token| token.text).collect();
+            if tokens.is_empty() {
+                is_negated = false;
+                continue;
+            }
+
+            let term = Term { tokens };
+            if is_negated {
+                keyword_query.excluded.push(term);
+            } else if is_joined_with_and && !clause.is_empty() {
+                clause.push(term);
+            } else {
+                if !clause.is_empty() {
+                    keyword_query.clauses.push(clause);
+                }
+                clause = vec![term];
+            }
+
+            is_negated = false;
+            is_joined_with_and = false;
+        }
+
+        if !clause.is_empty() {
+            keyword_query.clauses.push(clause);
+        }
+
+        keyword_query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Distinct words of the terms that are not excluded
+    pub fn get_positive_tokens(&self) -> Vec<String> {
+        let mut seen: HashSet<&String> = HashSet::new();
+        self.clauses
+            .iter()
+            .flatten()
+            .flat_map(|term| term.tokens.iter())
+            .filter(|token| seen.insert(*token))
+            .cloned()
+            .collect()
+    }
+
+    pub fn matches(&self, tokens: &[AnalyzedToken]) -> bool {
+        if self.excluded.iter().any(|term| !term.find(tokens).is_empty()) {
+            return false;
+        }
+
+        self.clauses
+            .iter()
+            .any(|clause| clause.iter().all(|term| !term.find(tokens).is_empty()))
+    }
+
+    /// Where the terms that are not excluded appear, in characters.
+    /// Overlapping spans are merged.
+    pub fn highlight(&self, tokens: &[AnalyzedToken]) -> Vec<HighlightSpan> {
+        let mut spans: Vec<HighlightSpan> = self
+            .clauses
+            .iter()
+            .flatten()
+            .flat_map(|term| term.find(tokens))
+            .map(|(first, last)| HighlightSpan {
+                start: tokens[first].start,
+                end: tokens[last].end,
+            })
+            .collect();
+        spans.sort_by_key(|span| (span.start, span.end));
+
+        let mut merged: Vec<HighlightSpan> = Vec::new();
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+
+        merged
+    }
+}
+
+/// Split the query into words, quoted phrases and operators
+fn lex(query: &str) -> Vec<QueryItem> {
+    let mut items: Vec<QueryItem> = Vec::new();
+    let mut characters = query.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        if character.is_whitespace() {
+            continue;
+        }
+
+        if character == '"' {
+            let phrase: String = characters.by_ref().take_while(|item| *item != '"').collect();
+            items.push(QueryItem::Text(phrase));
+            continue;
+        }
+
+        let mut word = String::from(character);
+        while let Some(next) = characters.peek() {
+            if next.is_whitespace() || *next == '"' {
+                break;
+            }
+            word.push(*next);
+            characters.next();
+        }
+
+        // Operators are only recognized in upper case, so that `and` can still be searched
+        match word.as_str() {
+            "AND" | "&&" => items.push(QueryItem::And),
+            "OR" | "||" => items.push(QueryItem::Or),
+            "NOT" => items.push(QueryItem::Not),
+            _ => match word.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => {
+                    items.push(QueryItem::Not);
+                    items.push(QueryItem::Text(rest.to_string()));
+                }
+                _ => items.push(QueryItem::Text(word)),
+            },
+        }
+    }
+
+    items
+}
+
+/// What BM25 needs to know about all the chunks that can be searched
+#[derive(Debug, Clone, Default)]
+pub struct CorpusStatistics {
+    pub chunks_count: usize,
+    /// Average number of words in a chunk
+    pub average_length: f32,
+    /// Number of chunks having the word
+    pub document_frequencies: HashMap<String, usize>,
+}
+
+/// BM25 score of a chunk for the words of the query
+pub fn score_bm25(
+    query_tokens: &[String],
+    tokens: &[AnalyzedToken],
+    statistics: &CorpusStatistics,
+) -> f32 {
+    let mut term_frequencies: HashMap<&str, usize> = HashMap::new();
+    for token in tokens {
+        *term_frequencies.entry(token.text.as_str()).or_default() += 1;
+    }
+
+    let chunks_count: f32 = statistics.chunks_count as f32;
+    let length_ratio: f32 = if statistics.average_length > 0.0 {
+        tokens.len() as f32 / statistics.average_length
+    } else {
+        1.0
+    };
+
+    query_tokens
+        .iter()
+        .map(|token| {
+            let term_frequency: f32 =
+                term_frequencies.get(token.as_str()).copied().unwrap_or(0) as f32;
+            if term_frequency == 0.0 {
+                return 0.0;
+            }
+
+            let document_frequency: f32 = statistics
+                .document_frequencies
+                .get(token)
+                .copied()
+                .unwrap_or(0) as f32;
+            let inverse_document_frequency: f32 = (1.0
+                + (chunks_count - document_frequency + 0.5) / (document_frequency + 0.5))
+                .ln();
+
+            inverse_document_frequency * term_frequency * (BM25_K1 + 1.0)
+                / (term_frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length_ratio))
+        })
+        .sum()
+}
+
+/// Keep the chunks matching the query, then rank them by BM25 and highlight the matches
+pub fn rank_chunks(
+    query: &KeywordQuery,
+    chunks: Vec<DocumentChunk>,
+    statistics: &CorpusStatistics,
+    top_n: usize,
+) -> Vec<DocumentChunkSearchResult> {
+    let query_tokens: Vec<String> = query.get_positive_tokens();
+
+    let mut results: Vec<DocumentChunkSearchResult> = chunks
+        .into_iter()
+        .filter_map(|chunk| {
+            let tokens: Vec<AnalyzedToken> = analyze(&chunk.content);
+            if !query.matches(&tokens) {
+                return None;
+            }
+
+            Some(DocumentChunkSearchResult {
+                score: score_bm25(&query_tokens, &tokens, statistics),
+                highlights: query.highlight(&tokens),
+                document_chunk: chunk,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    // Ties are broken by the chunk ids, so that the order is stable
+    results.sort_by(|left, right| {
+        right
+            .score
+            .total_cmp(&left.score)
+            .then_with(|| left.document_chunk.id.cmp(&right.document_chunk.id))
+    });
+    results.truncate(top_n);
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_term(tokens: &[&str]) -> Term {
+        Term {
+            tokens: tokens.iter().map(|token| token.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = KeywordQuery::parse("\"Vector Database\" AND rust python -java NOT go");
+        assert_eq!(
+            query.clauses,
+            vec![
+                vec![create_term(&["vector", "database"]), create_term(&["rust"])],
+                vec![create_term(&["python"])],
+            ]
+        );
+        assert_eq!(query.excluded, vec![create_term(&["java"]), create_term(&["go"])]);
+        assert_eq!(
+            query.get_positive_tokens(),
+            vec!["vector", "database", "rust", "python"]
+        );
+
+        // Lower case operators are words
+        let query = KeywordQuery::parse("salt and pepper");
+        assert_eq!(query.clauses.len(), 3);
+
+        assert!(KeywordQuery::parse("  \"\" -  ").is_empty());
+    }
+
+    #[test]
+    fn test_match_and_highlight() {
+        let content: &str = "Rust makes a fast vector database. Rust is safe.";
+        let tokens: Vec<AnalyzedToken> = analyze(content);
+
+        assert!(KeywordQuery::parse("\"vector database\" AND rust").matches(&tokens));
+        assert!(!KeywordQuery::parse("\"database vector\"").matches(&tokens));
+        assert!(!KeywordQuery::parse("rust AND python").matches(&tokens));
+        assert!(KeywordQuery::parse("rust OR python").matches(&tokens));
+        assert!(!KeywordQuery::parse("rust NOT safe").matches(&tokens));
+
+        let highlights: Vec<HighlightSpan> =
+            KeywordQuery::parse("\"vector database\" rust").highlight(&tokens);
+        let highlighted: Vec<String> = highlights
+            .iter()
+            .map(|span| content.chars().skip(span.start).take(span.end - span.start).collect())
+            .collect();
+        assert_eq!(highlighted, vec!["Rust", "vector database", "Rust"]);
+
+        let content: &str = "我们喜欢向量数据库";
+        let highlights: Vec<HighlightSpan> =
+            KeywordQuery::parse("数据库").highlight(&analyze(content));
+        assert_eq!(highlights, vec![HighlightSpan { start: 6, end: 9 }]);
+    }
+
+    #[test]
+    fn test_rank_chunks() {
+        let chunks: Vec<DocumentChunk> = vec![
+            DocumentChunk::new("rust rust rust".to_string(), "d1", "c1"),
+            DocumentChunk::new("rust and python".to_string(), "d2", "c1"),
+            DocumentChunk::new("python only".to_string(), "d3", "c1"),
+        ];
+        let statistics = CorpusStatistics {
+            chunks_count: 3,
+            average_length: 3.0,
+            document_frequencies: HashMap::from([
+                ("rust".to_string(), 2),
+                ("python".to_string(), 2),
+            ]),
+        };
+
+        let results = rank_chunks(&KeywordQuery::parse("rust"), chunks.clone(), &statistics, 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document_chunk.document_metadata_id, "d1");
+        assert!(results[0].score > results[1].score);
+        assert_eq!(results[0].highlights.len(), 3);
+
+        let results = rank_chunks(&KeywordQuery::parse("python -rust"), chunks, &statistics, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_chunk.document_metadata_id, "d3");
+    }
+}
@@ -1,7 +1,19 @@
+use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    connectors::requests::ImportType,
+    documents::document_metadata::{DocumentMetadata, DocumentPayload},
+};
+
+pub mod ask;
+pub mod grouping;
+pub mod hybrid;
 pub mod keyword;
+pub mod keyword_query;
+pub mod related;
+pub mod rerank;
 pub mod semantic;
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
@@ -20,3 +32,122 @@ pub enum SearchScope {
     Collection,
     Userspace,
 }
+
+/// Narrows the searched documents down within the scope.
+/// Fields left empty don't filter, and the documents have to match all the others.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    /// Documents in any of these collections
+    pub collection_metadata_ids: Vec<String>,
+    /// Any of these documents
+    pub document_metadata_ids: Vec<String>,
+    pub created_at: DateRange,
+    pub last_modified: DateRange,
+    /// Words that the titles contain, case insensitive
+    pub title: Option<String>,
+    /// Documents having any of these tags
+    pub tags: Vec<String>,
+    /// Documents imported from any of these sources
+    pub import_types: Vec<ImportType>,
+}
+
+/// Both ends are inclusive and optional
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DateRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl DateRange {
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// `timestamp` is in seconds
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.from.is_none_or(|from| timestamp >= from.timestamp())
+            && self.to.is_none_or(|to| timestamp <= to.timestamp())
+    }
+}
+
+impl SearchFilter {
+    pub fn is_empty(&self) -> bool {
+        self.collection_metadata_ids.is_empty()
+            && self.document_metadata_ids.is_empty()
+            && self.created_at.is_empty()
+            && self.last_modified.is_empty()
+            && self.title.is_none()
+            && self.tags.is_empty()
+            && self.import_types.is_empty()
+    }
+
+    /// Check a document against the filter, for the databases that don't keep the document payloads.
+    /// It uses the same fields as the payloads, so that all the databases agree.
+    pub fn matches(&self, metadata: &DocumentMetadata) -> bool {
+        let payload: DocumentPayload = DocumentPayload::from(metadata);
+
+        (self.collection_metadata_ids.is_empty()
+            || self.collection_metadata_ids.contains(&payload.collection_metadata_id))
+            && (self.document_metadata_ids.is_empty()
+                || self.document_metadata_ids.contains(&payload.document_metadata_id))
+            && self.created_at.contains(payload.document_created_at)
+            && self.last_modified.contains(payload.document_last_modified)
+            && self.title.as_ref().is_none_or(|title| {
+                let document_title: String = payload.document_title.to_lowercase();
+                title
+                    .to_lowercase()
+                    .split_whitespace()
+                    .all(|word| document_title.contains(word))
+            })
+            && (self.tags.is_empty()
+                || payload.document_tags.iter().any(|tag| self.tags.contains(tag)))
+            && (self.import_types.is_empty()
+                || payload
+                    .document_import_type
+                    .is_some_and(|import_type| self.import_types.contains(&import_type)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_filter_matches() {
+        let mut metadata = DocumentMetadata::new("Rust Async Book".to_string(), "c1".to_string());
+        metadata.created_at = "2025-03-01T00:00:00Z".to_string();
+        metadata.tags = vec!["programming".to_string()];
+
+        assert!(SearchFilter::default().matches(&metadata));
+
+        let filter = SearchFilter {
+            collection_metadata_ids: vec!["c1".to_string(), "c2".to_string()],
+            created_at: DateRange {
+                from: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+                to: None,
+            },
+            title: Some("async rust".to_string()),
+            tags: vec!["programming".to_string(), "cooking".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.matches(&metadata));
+
+        let filter = SearchFilter {
+            created_at: DateRange {
+                from: None,
+                to: Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            },
+            ..Default::default()
+        };
+        assert!(!filter.matches(&metadata));
+
+        // Documents added by users have no import type
+        let filter = SearchFilter {
+            import_types: vec![ImportType::Webpage],
+            ..Default::default()
+        };
+        assert!(!filter.matches(&metadata));
+    }
+}
@@ -0,0 +1,123 @@
+//! "More like this" search. A document is represented by the mean of its chunk vectors,
+//! and the documents whose chunks are the nearest to it are related to it.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use tokio::sync::MutexGuard;
+
+use crate::{
+    documents::document_chunk::DocumentChunkSearchResult,
+    metadata_storage::MetadataStorage,
+    search::grouping::{DocumentSearchResult, get_grouping_candidates, group_search_results},
+};
+
+#[async_trait]
+pub trait RelatedDocumentsSearch: Send + Sync {
+    /// Stored dense vectors of the chunks. Chunks that are not found are left out.
+    async fn get_document_chunk_vectors(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>>;
+
+    /// Nearest chunks to the vector, within the given documents
+    async fn search_documents_by_vector(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        vector: &[f32],
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>>;
+
+    /// Best `top_n` documents among `document_metadata_ids` that are related to the given document,
+    /// each with its closest chunk. The document itself is left out.
+    async fn search_related_documents(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_id: &str,
+        document_metadata_ids: Vec<String>,
+        top_n: usize,
+    ) -> Result<Vec<DocumentSearchResult>> {
+        let document_chunks_ids: Vec<String> = match metadata_storage.documents.get(document_metadata_id) {
+            Some(metadata) => metadata.chunks.clone(),
+            None => return Err(anyhow!("Document {} was not found", document_metadata_id)),
+        };
+
+        let vectors: Vec<Vec<f32>> = self.get_document_chunk_vectors(document_chunks_ids).await?;
+        let vector: Vec<f32> = match get_mean_vector(&vectors) {
+            Some(result) => result,
+            None => return Ok(Vec::new()),
+        };
+
+        let document_metadata_ids: Vec<String> = document_metadata_ids
+            .into_iter()
+            .filter(|id| id != document_metadata_id)
+            .collect();
+        // Nothing else to relate to
+        if document_metadata_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let results: Vec<DocumentChunkSearchResult> = self
+            .search_documents_by_vector(
+                metadata_storage,
+                document_metadata_ids,
+                &vector,
+                get_grouping_candidates(top_n, 1),
+            )
+            .await?;
+
+        let mut groups: Vec<DocumentSearchResult> = group_search_results(results, 1);
+        groups.truncate(top_n);
+
+        Ok(groups)
+    }
+}
+
+/// Mean of the normalized vectors, so that every chunk weighs the same under cosine similarity.
+/// Vectors of other dimensions than the first one, and zero vectors, are left out.
+pub fn get_mean_vector(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dimensions: usize = vectors.first()?.len();
+    let mut mean: Vec<f32> = vec![0.0; dimensions];
+    let mut count: usize = 0;
+
+    for vector in vectors {
+        let norm: f32 = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if vector.len() != dimensions || norm == 0.0 {
+            continue;
+        }
+
+        for (sum, value) in mean.iter_mut().zip(vector.iter()) {
+            *sum += value / norm;
+        }
+        count += 1;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    for value in mean.iter_mut() {
+        *value /= count as f32;
+    }
+
+    Some(mean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_mean_vector() {
+        // Lengths don't matter, only the directions
+        let mean = get_mean_vector(&[vec![2.0, 0.0], vec![0.0, 0.5]]).unwrap();
+        assert_eq!(mean, vec![0.5, 0.5]);
+
+        // Mismatched and zero vectors are left out
+        let mean = get_mean_vector(&[vec![1.0, 0.0], vec![1.0, 1.0, 1.0], vec![0.0, 0.0]]).unwrap();
+        assert_eq!(mean, vec![1.0, 0.0]);
+
+        assert_eq!(get_mean_vector(&[]), None);
+        assert_eq!(get_mean_vector(&[vec![0.0, 0.0]]), None);
+    }
+}
@@ -0,0 +1,201 @@
+//! Optional reranking stage of the semantic search.
+//! A cross-encoder scores each candidate chunk against the query,
+//! which is more precise than comparing the embeddings alone.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    configurations::system::{RerankerApiFormat, RerankerConfig},
+    documents::document_chunk::DocumentChunkSearchResult,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RerankResponse {
+    /// TEI returns a bare list
+    Ranked(Vec<RankedText>),
+    /// Cohere compatible endpoints wrap the list
+    Wrapped { results: Vec<RankedText> },
+}
+
+#[derive(Debug, Deserialize)]
+struct RankedText {
+    index: usize,
+    #[serde(alias = "relevance_score")]
+    score: f32,
+}
+
+fn build_request_body(config: &RerankerConfig, query: &str, texts: &[String]) -> Value {
+    match config.api_format {
+        RerankerApiFormat::Cohere => json!({
+            "model": config.model,
+            "query": query,
+            "documents": texts,
+        }),
+        RerankerApiFormat::Tei => json!({
+            "query": query,
+            "texts": texts,
+            "truncate": true,
+        }),
+    }
+}
+
+/// Ask the reranker for the relevance of each text.
+/// Returns `(index in texts, score)` pairs, in the order the endpoint gave them.
+pub async fn send_rerank_request(
+    config: &RerankerConfig,
+    query: &str,
+    texts: &[String],
+) -> Result<Vec<(usize, f32)>> {
+    let client = reqwest::Client::new();
+
+    let mut request = client
+        .post(&config.base_url)
+        .json(&build_request_body(config, query, texts))
+        .timeout(Duration::from_secs(config.timeout_seconds));
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+
+    let response = request.send().await?;
+    if let Err(error) = response.error_for_status_ref() {
+        let error_response_body: String = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Rerank request has failed. Error: {}. Message: {}",
+            error,
+            error_response_body
+        ));
+    }
+
+    let ranked_texts: Vec<RankedText> = match response.json::<RerankResponse>().await? {
+        RerankResponse::Ranked(ranked_texts) => ranked_texts,
+        RerankResponse::Wrapped { results } => results,
+    };
+
+    Ok(ranked_texts
+        .into_iter()
+        .filter(|ranked_text| ranked_text.index < texts.len())
+        .map(|ranked_text| (ranked_text.index, ranked_text.score))
+        .collect())
+}
+
+/// Reorder the search results by the reranker scores and keep the best `top_n`.
+/// The retrieval scores stay in `score`, the reranker ones go to `rerank_score`.
+pub async fn rerank_search_results(
+    config: &RerankerConfig,
+    query: &str,
+    mut results: Vec<DocumentChunkSearchResult>,
+    top_n: usize,
+) -> Result<Vec<DocumentChunkSearchResult>> {
+    if results.is_empty() {
+        return Ok(results);
+    }
+
+    let texts: Vec<String> = results
+        .iter()
+        .map(|result| result.document_chunk.content.clone())
+        .collect();
+
+    for (index, score) in send_rerank_request(config, query, &texts).await? {
+        results[index].rerank_score = Some(score);
+    }
+
+    // Results the reranker left out go last, in their retrieval order
+    results.sort_by(|a, b| match (a.rerank_score, b.rerank_score) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    results.truncate(top_n);
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{documents::document_chunk::DocumentChunk, test_utilities::spawn_stub_server};
+
+    fn create_config(base_url: String, api_format: RerankerApiFormat) -> RerankerConfig {
+        RerankerConfig {
+            base_url,
+            model: "bge-reranker-base".to_string(),
+            api_key: String::new(),
+            api_format,
+            candidates: 50,
+            timeout_seconds: 5,
+        }
+    }
+
+    fn create_results(contents: &[&str]) -> Vec<DocumentChunkSearchResult> {
+        contents
+            .iter()
+            .enumerate()
+            .map(|(index, content)| DocumentChunkSearchResult {
+                document_chunk: DocumentChunk {
+                    content: content.to_string(),
+                    ..Default::default()
+                },
+                score: 1.0 - index as f32 * 0.1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rerank_with_cohere_format() {
+        let (base_url, handle) = spawn_stub_server(
+            "/rerank",
+            "application/json",
+            vec![r#"{"results": [{"index": 2, "relevance_score": 0.9}, {"index": 0, "relevance_score": 0.5}, {"index": 1, "relevance_score": 0.1}]}"#],
+        )
+        .await;
+        let config = create_config(base_url, RerankerApiFormat::Cohere);
+
+        let results = rerank_search_results(&config, "rust", create_results(&["a", "b", "c"]), 2)
+            .await
+            .unwrap();
+
+        let request_body = handle.await.unwrap();
+        assert_eq!(request_body["query"], "rust");
+        assert_eq!(request_body["model"], "bge-reranker-base");
+        assert_eq!(request_body["documents"], json!(["a", "b", "c"]));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document_chunk.content, "c");
+        assert_eq!(results[0].rerank_score, Some(0.9));
+        assert!((results[0].score - 0.8).abs() < 1e-6);
+        assert_eq!(results[1].document_chunk.content, "a");
+    }
+
+    #[tokio::test]
+    async fn test_rerank_with_tei_format() {
+        // The second text is missing from the response, so it goes last
+        let (base_url, handle) = spawn_stub_server(
+            "/rerank",
+            "application/json",
+            vec![r#"[{"index": 2, "score": 0.7}, {"index": 0, "score": 0.3}]"#],
+        )
+        .await;
+        let config = create_config(base_url, RerankerApiFormat::Tei);
+
+        let results = rerank_search_results(&config, "rust", create_results(&["a", "b", "c"]), 3)
+            .await
+            .unwrap();
+
+        let request_body = handle.await.unwrap();
+        assert_eq!(request_body["texts"], json!(["a", "b", "c"]));
+
+        let contents: Vec<&str> = results
+            .iter()
+            .map(|result| result.document_chunk.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["c", "a", "b"]);
+        assert_eq!(results[2].rerank_score, None);
+    }
+}
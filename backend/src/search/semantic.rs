@@ -3,21 +3,54 @@ use async_trait::async_trait;
 use tokio::sync::MutexGuard;
 
 use crate::{
-    documents::document_chunk::DocumentChunkSearchResult, metadata_storage::MetadataStorage,
+    configurations::system::EmbedderConfig,
+    documents::document_chunk::DocumentChunkSearchResult,
+    metadata_storage::MetadataStorage,
+    search::{
+        SearchFilter,
+        grouping::{DocumentSearchResult, get_grouping_candidates, group_search_results},
+    },
 };
 
 #[async_trait]
-pub trait SemanticSearch {
+pub trait SemanticSearch: Send + Sync {
     async fn search_documents_semantically(
         &self,
         metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
         document_metadata_ids: Vec<String>,
+        filter: &SearchFilter,
         query: &str,
         top_n: usize,
-        provider: &str,
-        base_url: &str,
-        api_key: &str,
-        model: &str,
-        encoding_format: &str,
+        embedder_config: &EmbedderConfig,
     ) -> Result<Vec<DocumentChunkSearchResult>>;
+
+    /// Best `documents` documents, each with its best `chunks_per_document` chunks.
+    /// It groups a wider chunk search by default. Databases that can group the chunks themselves should override it.
+    async fn search_documents_semantically_grouped(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        filter: &SearchFilter,
+        query: &str,
+        documents: usize,
+        chunks_per_document: usize,
+        embedder_config: &EmbedderConfig,
+    ) -> Result<Vec<DocumentSearchResult>> {
+        let results: Vec<DocumentChunkSearchResult> = self
+            .search_documents_semantically(
+                metadata_storage,
+                document_metadata_ids,
+                filter,
+                query,
+                get_grouping_candidates(documents, chunks_per_document),
+                embedder_config,
+            )
+            .await?;
+
+        let mut groups: Vec<DocumentSearchResult> =
+            group_search_results(results, chunks_per_document);
+        groups.truncate(documents);
+
+        Ok(groups)
+    }
 }
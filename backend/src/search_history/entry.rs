@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{configurations::search::SupportedSearchMethod, search::SearchScopeIndicator};
+
+/// A search that a user has made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHistoryEntry {
+    pub id: String,
+
+    /// Username of the user who owns the searched scope
+    pub owner: String,
+
+    pub query: String,
+
+    pub scope: SearchScopeIndicator,
+
+    pub method: SupportedSearchMethod,
+
+    pub searched_at: DateTime<Utc>,
+}
+
+impl SearchHistoryEntry {
+    pub fn new(
+        owner: String,
+        query: String,
+        scope: SearchScopeIndicator,
+        method: SupportedSearchMethod,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            owner,
+            query,
+            scope,
+            method,
+            searched_at: Utc::now(),
+        }
+    }
+
+    /// The same search, made again
+    pub fn is_same_search(&self, other: &SearchHistoryEntry) -> bool {
+        self.owner == other.owner
+            && self.query == other.query
+            && self.method == other.method
+            && self.scope.search_scope == other.scope.search_scope
+            && self.scope.id == other.scope.id
+    }
+}
@@ -0,0 +1,6 @@
+//! Recent searches and saved searches of each user.
+//! Saved searches keep everything needed to run them again, except the page.
+
+pub mod entry;
+pub mod saved_search;
+pub mod storage;
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api_models::search::{DocumentGroupingOptions, SearchDocumentRequest},
+    configurations::search::SupportedSearchMethod,
+    search::{SearchFilter, SearchScopeIndicator},
+};
+
+/// A named search that can be run again by its id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+
+    /// Unique among the saved searches of the owner
+    pub name: String,
+
+    pub owner: String,
+
+    pub method: SupportedSearchMethod,
+
+    pub query: String,
+
+    pub top_n: usize,
+
+    pub scope: SearchScopeIndicator,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+
+    #[serde(default)]
+    pub filter: SearchFilter,
+
+    #[serde(default)]
+    pub group_by_document: Option<DocumentGroupingOptions>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl SavedSearch {
+    pub fn new(
+        name: String,
+        owner: String,
+        method: SupportedSearchMethod,
+        request: SearchDocumentRequest,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            owner,
+            method,
+            query: request.query,
+            top_n: request.top_n,
+            scope: request.scope,
+            attributes: request.attributes,
+            filter: request.filter,
+            group_by_document: request.group_by_document,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// The request that runs the search again, from the given page
+    pub fn to_request(&self, offset: usize) -> SearchDocumentRequest {
+        SearchDocumentRequest {
+            query: self.query.clone(),
+            top_n: self.top_n,
+            scope: self.scope.clone(),
+            attributes: self.attributes.clone(),
+            filter: self.filter.clone(),
+            group_by_document: self.group_by_document.clone(),
+            offset,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use super::{entry::SearchHistoryEntry, saved_search::SavedSearch};
+use crate::traits::LoadAndSave;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHistoryStorage {
+    pub path: PathBuf,
+    /// The oldest first
+    pub entries: Vec<SearchHistoryEntry>,
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearch>,
+    /// Searches are saved in batches, rather than every time they are made
+    #[serde(skip)]
+    pub has_unsaved_searches: bool,
+}
+
+impl LoadAndSave for SearchHistoryStorage {
+    fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            entries: Vec::new(),
+            saved_searches: Vec::new(),
+            has_unsaved_searches: false,
+        }
+    }
+
+    fn get_path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl SearchHistoryStorage {
+    /// Record a search as the latest of its owner.
+    /// A search made again moves to the top, rather than being listed twice.
+    /// It is only saved by `save_recorded_searches`.
+    pub fn record_search(&mut self, entry: SearchHistoryEntry, max_entries_per_user: usize) {
+        self.entries.retain(|item| !item.is_same_search(&entry));
+
+        let owner: String = entry.owner.clone();
+        self.entries.push(entry);
+
+        let mut excess: usize = self
+            .entries
+            .iter()
+            .filter(|item| item.owner == owner)
+            .count()
+            .saturating_sub(max_entries_per_user);
+        self.entries.retain(|item| {
+            if excess > 0 && item.owner == owner {
+                excess -= 1;
+                return false;
+            }
+
+            true
+        });
+        self.has_unsaved_searches = true;
+    }
+
+    /// Save the searches recorded since the last save, if any
+    pub async fn save_recorded_searches(&mut self) -> Result<()> {
+        if !self.has_unsaved_searches {
+            return Ok(());
+        }
+
+        self.save().await?;
+        self.has_unsaved_searches = false;
+
+        Ok(())
+    }
+
+    /// The latest searches of the user, the latest first
+    pub fn get_history_by_owner(&self, owner: &str, limit: usize) -> Vec<&SearchHistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|item| item.owner == owner)
+            .take(limit)
+            .collect()
+    }
+
+    pub async fn clear_history(&mut self, owner: &str) -> Result<()> {
+        self.entries.retain(|item| item.owner != owner);
+        self.save().await
+    }
+
+    pub async fn add_saved_search(&mut self, saved_search: SavedSearch) -> Result<String> {
+        if self
+            .saved_searches
+            .iter()
+            .any(|item| item.owner == saved_search.owner && item.name == saved_search.name)
+        {
+            return Err(anyhow!(
+                "Saved search `{}` has already existed",
+                saved_search.name
+            ));
+        }
+
+        let id: String = saved_search.id.clone();
+        self.saved_searches.push(saved_search);
+        self.save().await?;
+
+        Ok(id)
+    }
+
+    pub async fn remove_saved_search(&mut self, id: &str, owner: &str) -> Result<()> {
+        if self.get_saved_search(id, owner).is_none() {
+            return Err(anyhow!("Saved search {} was not found", id));
+        }
+
+        self.saved_searches
+            .retain(|item| !(item.id == id && item.owner == owner));
+        self.save().await
+    }
+
+    pub fn get_saved_search(&self, id: &str, owner: &str) -> Option<&SavedSearch> {
+        self.saved_searches
+            .iter()
+            .find(|item| item.id == id && item.owner == owner)
+    }
+
+    pub fn get_saved_searches_by_owner(&self, owner: &str) -> Vec<&SavedSearch> {
+        self.saved_searches
+            .iter()
+            .filter(|item| item.owner == owner)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configurations::search::SupportedSearchMethod,
+        search::{SearchScope, SearchScopeIndicator},
+        test_utilities::TemporaryFile,
+    };
+
+    fn create_entry(owner: &str, query: &str) -> SearchHistoryEntry {
+        SearchHistoryEntry::new(
+            owner.to_string(),
+            query.to_string(),
+            SearchScopeIndicator {
+                search_scope: SearchScope::Userspace,
+                id: owner.to_string(),
+            },
+            SupportedSearchMethod::Semantic,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_record_search() {
+        let path = TemporaryFile::new("search_history");
+        let mut search_history_storage = SearchHistoryStorage::new(path.to_str().unwrap());
+
+        for query in ["rust", "go", "zig", "rust"] {
+            search_history_storage.record_search(create_entry("alice", query), 2);
+        }
+        search_history_storage.record_search(create_entry("bob", "rust"), 2);
+        assert!(!path.exists());
+        search_history_storage.save_recorded_searches().await.unwrap();
+        assert!(!search_history_storage.has_unsaved_searches);
+        let saved = SearchHistoryStorage::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(saved.entries.len(), 3);
+
+        // The repeated search moved to the top, and the oldest one was dropped
+        let queries: Vec<&str> = search_history_storage
+            .get_history_by_owner("alice", 10)
+            .iter()
+            .map(|entry| entry.query.as_str())
+            .collect();
+        assert_eq!(queries, vec!["rust", "zig"]);
+
+        search_history_storage.clear_history("alice").await.unwrap();
+        assert!(search_history_storage.get_history_by_owner("alice", 10).is_empty());
+        assert_eq!(search_history_storage.get_history_by_owner("bob", 10).len(), 1);
+    }
+}
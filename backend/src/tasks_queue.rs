@@ -0,0 +1,301 @@
+//! Run the background tasks on a bounded pool of workers.
+//! Queued tasks are taken from their owners in turns,
+//! so that a user submitting many tasks cannot starve the others.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::Arc,
+};
+
+use log::{error, info};
+use tokio::sync::{Mutex, Notify};
+
+use crate::tasks_scheduler::{TaskStatus, TasksScheduler};
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct QueuedJob {
+    task_id: String,
+    job: Job,
+}
+
+/// Jobs waiting for a worker, grouped by their owners
+#[derive(Default)]
+struct Queues {
+    /// Owners that have queued jobs, in the order of their turns
+    owners: VecDeque<String>,
+    jobs: HashMap<String, VecDeque<QueuedJob>>,
+}
+
+impl Queues {
+    fn push(&mut self, owner: &str, job: QueuedJob) {
+        if !self.jobs.contains_key(owner) {
+            self.owners.push_back(owner.to_string());
+        }
+
+        self.jobs.entry(owner.to_string()).or_default().push_back(job);
+    }
+
+    /// Take the next job of the owner in turn, then move the owner to the back.
+    fn pop(&mut self) -> Option<QueuedJob> {
+        let owner: String = self.owners.pop_front()?;
+        let jobs: &mut VecDeque<QueuedJob> = self.jobs.get_mut(&owner)?;
+        let job: Option<QueuedJob> = jobs.pop_front();
+
+        if jobs.is_empty() {
+            self.jobs.remove(&owner);
+        } else {
+            self.owners.push_back(owner);
+        }
+
+        job
+    }
+
+    fn remove(&mut self, task_id: &str) -> bool {
+        let owner: Option<String> = self
+            .jobs
+            .iter()
+            .find(|(_, jobs)| jobs.iter().any(|job| job.task_id == task_id))
+            .map(|(owner, _)| owner.clone());
+
+        let owner: String = match owner {
+            Some(result) => result,
+            None => return false,
+        };
+
+        if let Some(jobs) = self.jobs.get_mut(&owner) {
+            jobs.retain(|job| job.task_id != task_id);
+            if jobs.is_empty() {
+                self.jobs.remove(&owner);
+                self.owners.retain(|item| item != &owner);
+            }
+        }
+
+        true
+    }
+
+    /// Task ids in the order that they will be picked up
+    fn order(&self) -> Vec<String> {
+        let mut order: Vec<String> = Vec::new();
+        let mut round: usize = 0;
+        loop {
+            let length: usize = order.len();
+            for owner in self.owners.iter() {
+                if let Some(job) = self.jobs.get(owner).and_then(|jobs| jobs.get(round)) {
+                    order.push(job.task_id.clone());
+                }
+            }
+
+            if order.len() == length {
+                return order;
+            }
+            round += 1;
+        }
+    }
+}
+
+pub struct TasksQueue {
+    queues: Mutex<Queues>,
+    notify: Notify,
+    tasks_scheduler: Arc<Mutex<TasksScheduler>>,
+}
+
+impl TasksQueue {
+    /// Create the queue and start its workers
+    pub fn new(workers: usize, tasks_scheduler: Arc<Mutex<TasksScheduler>>) -> Arc<Self> {
+        let tasks_queue = Arc::new(Self {
+            queues: Mutex::new(Queues::default()),
+            notify: Notify::new(),
+            tasks_scheduler,
+        });
+
+        for _ in 0..workers.max(1) {
+            tokio::spawn(tasks_queue.clone().run_worker());
+        }
+
+        tasks_queue
+    }
+
+    /// Queue a job for the task, which takes turns with the other tasks of the same owner.
+    /// Tasks without owners share the same turn.
+    pub async fn submit<F>(&self, task_id: &str, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let owner: String = self
+            .tasks_scheduler
+            .lock()
+            .await
+            .search_by_task_id(task_id)
+            .and_then(|task| task.owner.clone())
+            .unwrap_or_default();
+
+        let mut queues = self.queues.lock().await;
+        queues.push(
+            &owner,
+            QueuedJob {
+                task_id: task_id.to_string(),
+                job: Box::pin(job),
+            },
+        );
+        self.update_queue_positions(queues.order()).await;
+        drop(queues);
+
+        self.notify.notify_one();
+    }
+
+    /// Remove a task that has not started yet.
+    /// Returns false if the task is not in the queue.
+    pub async fn remove(&self, task_id: &str) -> bool {
+        let mut queues = self.queues.lock().await;
+        if !queues.remove(task_id) {
+            return false;
+        }
+
+        self.tasks_scheduler
+            .lock()
+            .await
+            .update_queue_position_by_task_id(task_id, None);
+        self.update_queue_positions(queues.order()).await;
+
+        true
+    }
+
+    /// Renumber the queued tasks. The queues should stay locked until it is done,
+    /// so that the positions are updated in order.
+    async fn update_queue_positions(&self, order: Vec<String>) {
+        let mut tasks_scheduler = self.tasks_scheduler.lock().await;
+        for (index, task_id) in order.iter().enumerate() {
+            tasks_scheduler.update_queue_position_by_task_id(task_id, Some(index + 1));
+        }
+    }
+
+    async fn run_worker(self: Arc<Self>) {
+        loop {
+            let queued_job: Option<QueuedJob> = {
+                let mut queues = self.queues.lock().await;
+                let queued_job: Option<QueuedJob> = queues.pop();
+                if queued_job.is_some() {
+                    self.update_queue_positions(queues.order()).await;
+                }
+
+                queued_job
+            };
+
+            let queued_job: QueuedJob = match queued_job {
+                Some(result) => result,
+                None => {
+                    self.notify.notified().await;
+                    continue;
+                }
+            };
+
+            let task_id: String = queued_job.task_id;
+            {
+                let mut tasks_scheduler = self.tasks_scheduler.lock().await;
+                tasks_scheduler.update_queue_position_by_task_id(&task_id, None);
+
+                // No need to start the tasks that were cancelled while waiting
+                if tasks_scheduler.is_cancellation_requested(&task_id) {
+                    info!("Task {} was cancelled before it started", task_id);
+                    tasks_scheduler.set_status_to_cancelled(&task_id).await;
+                    continue;
+                }
+            }
+
+            // Run the job in its own task, so that a panic does not take down the worker
+            if let Err(error) = tokio::spawn(queued_job.job).await {
+                error!("Task {} has panicked: {}", task_id, error);
+                self.tasks_scheduler
+                    .lock()
+                    .await
+                    .update_status_by_task_id(&task_id, TaskStatus::Failed, Some(error.to_string()))
+                    .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::TemporaryFile;
+    use crate::traits::LoadAndSave;
+
+    fn create_job(task_id: &str) -> QueuedJob {
+        QueuedJob {
+            task_id: task_id.to_string(),
+            job: Box::pin(async {}),
+        }
+    }
+
+    #[test]
+    fn test_owners_take_turns() {
+        let mut queues = Queues::default();
+        queues.push("alice", create_job("a1"));
+        queues.push("alice", create_job("a2"));
+        queues.push("alice", create_job("a3"));
+        queues.push("bob", create_job("b1"));
+        queues.push("carol", create_job("c1"));
+        queues.push("bob", create_job("b2"));
+
+        let expected: Vec<&str> = vec!["a1", "b1", "c1", "a2", "b2", "a3"];
+        assert_eq!(queues.order(), expected);
+
+        assert!(queues.remove("c1"));
+        assert!(!queues.remove("c1"));
+        let expected: Vec<&str> = vec!["a1", "b1", "a2", "b2", "a3"];
+        assert_eq!(queues.order(), expected);
+
+        let mut popped: Vec<String> = Vec::new();
+        while let Some(job) = queues.pop() {
+            popped.push(job.task_id);
+        }
+        assert_eq!(popped, expected);
+        assert!(queues.owners.is_empty());
+        assert!(queues.jobs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_queued_tasks_report_their_positions() {
+        let path = TemporaryFile::new("tasks_queue");
+        let tasks_scheduler = Arc::new(Mutex::new(TasksScheduler::new(path.to_str().unwrap())));
+        let tasks_queue = TasksQueue::new(1, tasks_scheduler.clone());
+
+        let running_task_id: String = tasks_scheduler.lock().await.create_new_task(None).await;
+        let queued_task_id: String = tasks_scheduler.lock().await.create_new_task(None).await;
+
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        tasks_queue
+            .submit(&running_task_id, async move {
+                let _ = receiver.await;
+            })
+            .await;
+        tasks_queue.submit(&queued_task_id, async {}).await;
+
+        // Wait for the worker to pick up the first task
+        while tasks_scheduler
+            .lock()
+            .await
+            .search_by_task_id(&running_task_id)
+            .unwrap()
+            .queue_position
+            .is_some()
+        {
+            tokio::task::yield_now().await;
+        }
+
+        let queue_position: Option<usize> = tasks_scheduler
+            .lock()
+            .await
+            .search_by_task_id(&queued_task_id)
+            .unwrap()
+            .queue_position;
+        assert_eq!(queue_position, Some(1));
+
+        assert!(tasks_queue.remove(&queued_task_id).await);
+        assert!(!tasks_queue.remove(&running_task_id).await);
+        let _ = sender.send(());
+    }
+}
@@ -1,16 +1,35 @@
-//! Track down the status of each task
+//! Track down the status of each task.
+//! Task records are persisted, so that the clients can still poll them after a restart.
 
-use std::{collections::HashMap, vec};
+use std::{collections::HashMap, path::PathBuf, vec};
 
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::traits::LoadAndSave;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq)]
 pub enum TaskStatus {
     InProgress,
     Completed,
     Failed,
+    Cancelled,
+}
+
+/// Capacity of the channel that pushes task updates to the subscribers.
+/// Slow subscribers will skip the updates that overflow.
+const TASK_UPDATES_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskProgress {
+    /// What the task is working on, like `embedding`
+    pub phase: String,
+    pub done: usize,
+    pub total: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,35 +37,149 @@ pub struct TaskRecord {
     pub task_id: String,
     pub status: TaskStatus,
     pub message: Option<String>,
+
+    /// Username of the user who started the task.
+    /// Empty for the tasks that are not started on behalf of a user.
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    /// The task will stop at its next batch boundary
+    #[serde(default)]
+    pub is_cancellation_requested: bool,
+
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+
+    /// Empty if the task is still in progress
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+
+    /// Empty if the task does not report its progress
+    #[serde(default)]
+    pub progress: Option<TaskProgress>,
+
+    /// Position of the task in the queue, starting from 1.
+    /// Empty once the task has been picked up by a worker.
+    #[serde(default)]
+    pub queue_position: Option<usize>,
+
+    /// The scheduled job that started the task, if any
+    #[serde(default)]
+    pub scheduled_job_id: Option<String>,
 }
 
 impl TaskRecord {
-    pub fn new() -> Self {
+    pub fn new(owner: Option<&str>) -> Self {
         Self {
             task_id: Uuid::new_v4().to_string(),
             status: TaskStatus::InProgress,
             message: None,
+            owner: owner.map(|owner| owner.to_string()),
+            is_cancellation_requested: false,
+            created_at: Utc::now(),
+            finished_at: None,
+            progress: None,
+            queue_position: None,
+            scheduled_job_id: None,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TasksScheduler {
+    #[serde(default)]
+    pub path: PathBuf,
     pub registered_tasks: Vec<TaskRecord>,
     pub generated_results: HashMap<String, Value>,
+
+    #[serde(skip, default = "create_task_updates_sender")]
+    task_updates: broadcast::Sender<TaskRecord>,
 }
 
-impl TasksScheduler {
-    pub fn new() -> Self {
+fn create_task_updates_sender() -> broadcast::Sender<TaskRecord> {
+    broadcast::channel(TASK_UPDATES_CAPACITY).0
+}
+
+impl LoadAndSave for TasksScheduler {
+    fn new(path: &str) -> Self {
         Self {
+            path: PathBuf::from(path),
             registered_tasks: vec![],
             generated_results: HashMap::new(),
+            task_updates: create_task_updates_sender(),
+        }
+    }
+
+    fn get_path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl TasksScheduler {
+    /// Tasks that were still running when the backend stopped will never finish,
+    /// so they are marked as interrupted.
+    pub async fn fail_interrupted_tasks(&mut self) -> Result<usize> {
+        let now: DateTime<Utc> = Utc::now();
+        let mut count: usize = 0;
+        for task in self
+            .registered_tasks
+            .iter_mut()
+            .filter(|task| task.status == TaskStatus::InProgress)
+        {
+            task.status = TaskStatus::Failed;
+            task.message = Some("interrupted".to_string());
+            task.finished_at = Some(now);
+            task.queue_position = None;
+            count += 1;
+        }
+
+        self.save().await?;
+        Ok(count)
+    }
+
+    /// Remove the finished tasks and their unclaimed results that are older than the ttl.
+    /// Tasks in progress are kept regardless of their ages.
+    pub async fn sweep(&mut self, ttl: Duration) -> Result<usize> {
+        // Nothing can be older than a deadline before the earliest time
+        let deadline: DateTime<Utc> = match Utc::now().checked_sub_signed(ttl) {
+            Some(result) => result,
+            None => return Ok(0),
+        };
+        let expired_task_ids: Vec<String> = self
+            .registered_tasks
+            .iter()
+            .filter(|task| task.finished_at.is_some_and(|finished_at| finished_at < deadline))
+            .map(|task| task.task_id.clone())
+            .collect();
+
+        if expired_task_ids.is_empty() {
+            return Ok(0);
         }
+
+        self.registered_tasks
+            .retain(|task| !expired_task_ids.contains(&task.task_id));
+        for task_id in expired_task_ids.iter() {
+            self.generated_results.remove(task_id);
+        }
+
+        self.save().await?;
+        Ok(expired_task_ids.len())
+    }
+
+    pub async fn create_new_task(&mut self, owner: Option<&str>) -> String {
+        let task: TaskRecord = TaskRecord::new(owner);
+        self.registered_tasks.push(task.clone());
+        self.save_or_log().await;
+
+        task.task_id
     }
 
-    pub fn create_new_task(&mut self) -> String {
-        let task: TaskRecord = TaskRecord::new();
+    /// Tasks started by scheduled jobs make up the run history of the jobs
+    pub async fn create_new_scheduled_task(&mut self, owner: &str, scheduled_job_id: &str) -> String {
+        let mut task: TaskRecord = TaskRecord::new(Some(owner));
+        task.scheduled_job_id = Some(scheduled_job_id.to_string());
         self.registered_tasks.push(task.clone());
+        self.save_or_log().await;
 
         task.task_id
     }
@@ -57,22 +190,71 @@ impl TasksScheduler {
             .find(|task| task.task_id == task_id)
     }
 
+    /// Tasks of the user, from the newest to the oldest
+    pub fn get_tasks_by_owner(&self, owner: &str, status: Option<&TaskStatus>) -> Vec<&TaskRecord> {
+        let mut tasks: Vec<&TaskRecord> = self
+            .registered_tasks
+            .iter()
+            .filter(|task| task.owner.as_deref() == Some(owner))
+            .filter(|task| status.is_none_or(|status| task.status == *status))
+            .collect();
+        tasks.sort_by_key(|task| std::cmp::Reverse(task.created_at));
+
+        tasks
+    }
+
+    /// Request a task to stop. Tasks check the request at their batch boundaries,
+    /// then undo their partial writes and mark themselves as cancelled.
+    pub async fn request_cancellation(&mut self, task_id: &str, owner: &str) -> Result<()> {
+        let task: &mut TaskRecord = match self
+            .registered_tasks
+            .iter_mut()
+            .find(|task| task.task_id == task_id && task.owner.as_deref() == Some(owner))
+        {
+            Some(result) => result,
+            None => return Err(anyhow!("Task {} was not found", task_id)),
+        };
+
+        if task.status != TaskStatus::InProgress {
+            return Err(anyhow!("Task {} has already finished", task_id));
+        }
+
+        task.is_cancellation_requested = true;
+        let task: TaskRecord = task.clone();
+        self.publish(task);
+        self.save_or_log().await;
+
+        Ok(())
+    }
+
+    pub fn is_cancellation_requested(&self, task_id: &str) -> bool {
+        self.search_by_task_id(task_id)
+            .is_some_and(|task| task.is_cancellation_requested)
+    }
+
+    pub async fn set_status_to_cancelled(&mut self, task_id: &str) -> bool {
+        self.update_status_by_task_id(task_id, TaskStatus::Cancelled, Some("cancelled".to_string()))
+            .await
+    }
+
     /// Reserved for future uses
     #[allow(dead_code)]
-    pub fn delete_by_task_id(&mut self, task_id: &str) -> bool {
+    pub async fn delete_by_task_id(&mut self, task_id: &str) -> bool {
         if let Some(pos) = self
             .registered_tasks
             .iter()
             .position(|task| task.task_id == task_id)
         {
             self.registered_tasks.remove(pos);
+            self.generated_results.remove(task_id);
+            self.save_or_log().await;
             return true;
         }
 
         false
     }
 
-    pub fn update_status_by_task_id(
+    pub async fn update_status_by_task_id(
         &mut self,
         task_id: &str,
         new_status: TaskStatus,
@@ -83,29 +265,231 @@ impl TasksScheduler {
             .iter_mut()
             .find(|task| task.task_id == task_id)
         {
+            if new_status != TaskStatus::InProgress {
+                task.finished_at = Some(Utc::now());
+            }
             task.status = new_status;
             task.message = message;
+            let task: TaskRecord = task.clone();
+            self.publish(task);
+            self.save_or_log().await;
             return true;
         }
 
         false
     }
 
-    pub fn set_status_to_complete(&mut self, task_id: &str, data: Value) -> bool {
+    pub async fn set_status_to_complete(&mut self, task_id: &str, data: Value) -> bool {
         if let Some(task) = self
             .registered_tasks
             .iter_mut()
             .find(|task| task.task_id == task_id)
         {
             task.status = TaskStatus::Completed;
+            task.finished_at = Some(Utc::now());
+            if let Some(progress) = task.progress.as_mut() {
+                progress.done = progress.total;
+            }
+            let task: TaskRecord = task.clone();
+            self.publish(task);
             self.generated_results.insert(task_id.to_string(), data);
+            self.save_or_log().await;
+            return true;
+        }
+
+        false
+    }
+
+    /// Like the progress, queue positions are not persisted.
+    /// Only publish when the position has changed, as the whole queue is renumbered at once.
+    pub fn update_queue_position_by_task_id(
+        &mut self,
+        task_id: &str,
+        queue_position: Option<usize>,
+    ) -> bool {
+        if let Some(task) = self
+            .registered_tasks
+            .iter_mut()
+            .find(|task| task.task_id == task_id)
+        {
+            if task.queue_position != queue_position {
+                task.queue_position = queue_position;
+                let task: TaskRecord = task.clone();
+                self.publish(task);
+            }
+            return true;
+        }
+
+        false
+    }
+
+    /// Progress is not persisted, as the tasks in progress will be interrupted by restarts anyway.
+    pub fn update_progress_by_task_id(
+        &mut self,
+        task_id: &str,
+        phase: &str,
+        done: usize,
+        total: usize,
+    ) -> bool {
+        if let Some(task) = self
+            .registered_tasks
+            .iter_mut()
+            .find(|task| task.task_id == task_id)
+        {
+            task.progress = Some(TaskProgress {
+                phase: phase.to_string(),
+                done,
+                total,
+            });
+            let task: TaskRecord = task.clone();
+            self.publish(task);
+            return true;
+        }
+
+        false
+    }
+
+    /// Mark one more unit of the current phase as done
+    pub fn advance_progress_by_task_id(&mut self, task_id: &str) -> bool {
+        if let Some(task) = self
+            .registered_tasks
+            .iter_mut()
+            .find(|task| task.task_id == task_id)
+        {
+            if let Some(progress) = task.progress.as_mut() {
+                progress.done = (progress.done + 1).min(progress.total);
+            }
+            let task: TaskRecord = task.clone();
+            self.publish(task);
             return true;
         }
 
         false
     }
 
-    pub fn get_task_result(&mut self, task_id: &str) -> Option<Value> {
-        return self.generated_results.remove(task_id);
+    /// Receive the records of the tasks whenever they change
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskRecord> {
+        self.task_updates.subscribe()
+    }
+
+    fn publish(&self, task: TaskRecord) {
+        // It fails when there are no subscribers, which is fine
+        let _ = self.task_updates.send(task);
+    }
+
+    pub async fn get_task_result(&mut self, task_id: &str) -> Option<Value> {
+        let result: Option<Value> = self.generated_results.remove(task_id);
+        if result.is_some() {
+            self.save_or_log().await;
+        }
+
+        result
+    }
+
+    /// Failing to persist the tasks should not fail the tasks themselves
+    async fn save_or_log(&self) {
+        if let Err(error) = self.save().await {
+            log::error!("Failed to save the tasks scheduler: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::TemporaryFile;
+
+    #[tokio::test]
+    async fn test_fail_interrupted_tasks_and_sweep() {
+        let path = TemporaryFile::new("tasks_scheduler");
+        let mut tasks_scheduler = TasksScheduler::new(path.to_str().unwrap());
+
+        let finished_task_id: String = tasks_scheduler.create_new_task(None).await;
+        tasks_scheduler
+            .set_status_to_complete(&finished_task_id, serde_json::json!({}))
+            .await;
+        let running_task_id: String = tasks_scheduler.create_new_task(None).await;
+
+        // Reload as if the backend has restarted
+        let mut tasks_scheduler = TasksScheduler::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(tasks_scheduler.fail_interrupted_tasks().await.unwrap(), 1);
+        let running_task: &TaskRecord = tasks_scheduler.search_by_task_id(&running_task_id).unwrap();
+        assert_eq!(running_task.status, TaskStatus::Failed);
+        assert_eq!(running_task.message.as_deref(), Some("interrupted"));
+
+        assert_eq!(tasks_scheduler.sweep(Duration::hours(1)).await.unwrap(), 0);
+        assert_eq!(tasks_scheduler.sweep(Duration::MAX).await.unwrap(), 0);
+        assert_eq!(tasks_scheduler.sweep(Duration::seconds(-1)).await.unwrap(), 2);
+        assert!(tasks_scheduler.generated_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_cancel_tasks_by_owner() {
+        let path = TemporaryFile::new("tasks_scheduler");
+        let mut tasks_scheduler = TasksScheduler::new(path.to_str().unwrap());
+
+        let task_id: String = tasks_scheduler.create_new_task(Some("alice")).await;
+        tasks_scheduler.create_new_task(Some("bob")).await;
+
+        assert_eq!(tasks_scheduler.get_tasks_by_owner("alice", None).len(), 1);
+        assert!(
+            tasks_scheduler
+                .get_tasks_by_owner("alice", Some(&TaskStatus::Completed))
+                .is_empty()
+        );
+
+        assert!(tasks_scheduler.request_cancellation(&task_id, "bob").await.is_err());
+        tasks_scheduler.request_cancellation(&task_id, "alice").await.unwrap();
+        assert!(tasks_scheduler.is_cancellation_requested(&task_id));
+
+        tasks_scheduler.set_status_to_cancelled(&task_id).await;
+        assert!(tasks_scheduler.request_cancellation(&task_id, "alice").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_progress_updates_are_published() {
+        let path = TemporaryFile::new("tasks_scheduler");
+        let mut tasks_scheduler = TasksScheduler::new(path.to_str().unwrap());
+        let mut receiver = tasks_scheduler.subscribe();
+
+        let task_id: String = tasks_scheduler.create_new_task(None).await;
+        tasks_scheduler.update_progress_by_task_id(&task_id, "embedding", 0, 2);
+        tasks_scheduler.advance_progress_by_task_id(&task_id);
+
+        assert_eq!(receiver.recv().await.unwrap().progress.unwrap().done, 0);
+        assert_eq!(
+            receiver.recv().await.unwrap().progress,
+            Some(TaskProgress {
+                phase: "embedding".to_string(),
+                done: 1,
+                total: 2,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_file_is_set_aside() {
+        let path = TemporaryFile::new("tasks_scheduler");
+        std::fs::write(&path, "{\"registered_tasks\": [").unwrap();
+        assert!(TasksScheduler::load(path.to_str().unwrap()).is_err());
+
+        let tasks_scheduler = TasksScheduler::load_or_set_aside(path.to_str().unwrap()).unwrap();
+        assert!(tasks_scheduler.registered_tasks.is_empty());
+        assert!(!path.exists());
+
+        // Kept for inspection, next to the file
+        let prefix: String = format!("{}.", path.file_name().unwrap().to_str().unwrap());
+        let aside: Vec<PathBuf> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".corrupt"))
+            })
+            .collect();
+        assert_eq!(aside.len(), 1);
+        let _ = std::fs::remove_file(&aside[0]);
     }
 }
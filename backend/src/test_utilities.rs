@@ -0,0 +1,106 @@
+//! Helpers shared by the tests
+
+use std::{
+    ffi::OsString,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+/// A uniquely named file in the temporary directory.
+/// It is removed when dropped, so a failed assertion doesn't leave it behind.
+pub struct TemporaryFile {
+    path: PathBuf,
+}
+
+impl TemporaryFile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            path: std::env::temp_dir().join(format!("{}_{}.json", name, Uuid::new_v4())),
+        }
+    }
+}
+
+impl Deref for TemporaryFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TemporaryFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        // Left over if a save was interrupted
+        let mut temporary_path: OsString = self.path.as_os_str().to_owned();
+        temporary_path.push(".tmp");
+        let _ = std::fs::remove_file(temporary_path);
+    }
+}
+
+/// Serve a single request at `path` with the given response chunks, and hand back the request body
+pub async fn spawn_stub_server(
+    path: &str,
+    content_type: &'static str,
+    response_chunks: Vec<&'static str>,
+) -> (String, JoinHandle<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        let request_body = loop {
+            let read = stream.read(&mut chunk).await.unwrap();
+            buffer.extend_from_slice(&chunk[..read]);
+
+            let request = String::from_utf8_lossy(&buffer).to_string();
+            if let Some(header_end) = request.find("\r\n\r\n") {
+                let content_length: usize = request[..header_end]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse().ok())?
+                    })
+                    .unwrap_or(0);
+                if buffer.len() >= header_end + 4 + content_length {
+                    break buffer[header_end + 4..header_end + 4 + content_length].to_vec();
+                }
+            }
+        };
+
+        let headers = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n",
+            content_type
+        );
+        stream.write_all(headers.as_bytes()).await.unwrap();
+        for response_chunk in response_chunks {
+            let encoded = format!("{:x}\r\n{}\r\n", response_chunk.len(), response_chunk);
+            stream.write_all(encoded.as_bytes()).await.unwrap();
+            stream.flush().await.unwrap();
+        }
+        stream.write_all(b"0\r\n\r\n").await.unwrap();
+        stream.shutdown().await.unwrap();
+
+        serde_json::from_slice(&request_body).unwrap()
+    });
+
+    (format!("http://{}{}", address, path), handle)
+}
@@ -1,9 +1,11 @@
 use std::{
+    ffi::OsString,
     io::Read,
     path::{Path, PathBuf},
 };
 
 use anyhow::Result;
+use chrono::Utc;
 use log::info;
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::io::AsyncWriteExt;
@@ -46,11 +48,48 @@ where
         }
     }
 
-    async fn save(&self) -> Result<()> {
-        let mut file: tokio::fs::File = tokio::fs::File::create(self.get_path()).await?;
-        let buffer: String = serde_json::to_string_pretty(self)?;
-        let _ = file.write(buffer.as_bytes()).await;
+    /// Same as `load`, but a file that can't be parsed, such as one truncated by a crash,
+    /// is moved aside and the storage starts empty, rather than keeping the backend from starting
+    fn load_or_set_aside(path: &str) -> Result<Self> {
+        match Self::load(path) {
+            Err(error) if error.is::<serde_json::Error>() => {
+                let aside: String =
+                    format!("{}.{}.corrupt", path, Utc::now().format("%Y%m%d%H%M%S"));
+                log::error!(
+                    "Failed to parse `{}`, so it is moved to `{}` and starts empty: {}",
+                    path,
+                    aside,
+                    error
+                );
+                std::fs::rename(path, &aside)?;
 
-        Ok(())
+                Ok(Self::new(path))
+            }
+            result => result,
+        }
     }
+
+    /// Pretty printed, unless the storage is too large to read by eye
+    fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    async fn save(&self) -> Result<()> {
+        write_file_atomically(self.get_path(), &self.to_json()?).await
+    }
+}
+
+/// Write to a temporary file next to the file, then swap it in,
+/// so that a crash while writing leaves the previous file intact
+pub async fn write_file_atomically(path: &Path, buffer: &[u8]) -> Result<()> {
+    let mut temporary_path: OsString = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+
+    let mut file: tokio::fs::File = tokio::fs::File::create(&temporary_path).await?;
+    file.write_all(buffer).await?;
+    // Tokio writes in the background, so flush to make sure the file is complete
+    file.flush().await?;
+    tokio::fs::rename(&temporary_path, path).await?;
+
+    Ok(())
 }
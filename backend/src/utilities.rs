@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use actix_web::web;
+use actix_web::{cookie::time::UtcDateTime, web};
 use tokio::sync::{Mutex, RwLock};
 
 use crate::{
@@ -46,3 +46,10 @@ pub async fn acquire_data(
         backups_storage,
     )
 }
+
+/// Format a unix timestamp in the same way as the timestamps in metadata
+pub fn format_unix_timestamp(timestamp: i64) -> Option<String> {
+    UtcDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .map(|datetime| datetime.to_string())
+}
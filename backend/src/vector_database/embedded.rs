@@ -0,0 +1,685 @@
+//! An in-process vector database for single-user installs.
+//! Chunks and their vectors are kept in memory and persisted to a JSON file in the data directory.
+//! Dense vectors are searched by brute force, and keywords are searched with an inverted index
+//! that is rebuilt on loading.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{MutexGuard, RwLock, Semaphore};
+
+use crate::{
+    configurations::system::{Config, DatabaseConfig, EmbedderConfig},
+    constants::HYBRID_SEARCH_CANDIDATES_FACTOR,
+    documents::document_chunk::{DocumentChunk, DocumentChunkSearchResult},
+    embedder::VectorizationStatistics,
+    embedding_cache::EmbeddingCache,
+    metadata_storage::MetadataStorage,
+    search::{
+        SearchFilter,
+        hybrid::{HybridSearch, HybridSearchOptions, fuse_search_results},
+        keyword::KeywordSearch,
+        keyword_query::{AnalyzedToken, CorpusStatistics, KeywordQuery, analyze, rank_chunks},
+        related::RelatedDocumentsSearch,
+        semantic::SemanticSearch,
+    },
+    traits::LoadAndSave,
+    vector_database::{
+        shared::{fill_in_titles, vectorize_document_chunks, vectorize_query},
+        traits::VectorDatabase,
+    },
+};
+
+/// A document chunk along with its vector, which `DocumentChunk` does not serialize
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredDocumentChunk {
+    id: String,
+    document_metadata_id: String,
+    collection_metadata_id: String,
+    content: String,
+    dense_text_vector: Vec<f32>,
+}
+
+impl From<DocumentChunk> for StoredDocumentChunk {
+    fn from(value: DocumentChunk) -> Self {
+        Self {
+            id: value.id,
+            document_metadata_id: value.document_metadata_id,
+            collection_metadata_id: value.collection_metadata_id,
+            content: value.content,
+            dense_text_vector: value.dense_text_vector,
+        }
+    }
+}
+
+impl From<&StoredDocumentChunk> for DocumentChunk {
+    fn from(value: &StoredDocumentChunk) -> Self {
+        Self {
+            id: value.id.clone(),
+            document_metadata_id: value.document_metadata_id.clone(),
+            collection_metadata_id: value.collection_metadata_id.clone(),
+            content: value.content.clone(),
+            dense_text_vector: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EmbeddedStorage {
+    path: PathBuf,
+    dimensions: usize,
+    chunks: HashMap<String, StoredDocumentChunk>,
+    /// Keyword to the ids of the chunks containing it
+    #[serde(skip)]
+    inverted_index: HashMap<String, HashSet<String>>,
+    /// Number of words in all the chunks, for BM25
+    #[serde(skip)]
+    total_length: usize,
+}
+
+impl LoadAndSave for EmbeddedStorage {
+    fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            dimensions: 0,
+            chunks: HashMap::new(),
+            inverted_index: HashMap::new(),
+            total_length: 0,
+        }
+    }
+
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The vectors make the file large, so it is not pretty printed
+    fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+impl EmbeddedStorage {
+    fn rebuild_inverted_index(&mut self) {
+        self.inverted_index.clear();
+        self.total_length = 0;
+        let chunks: Vec<(String, String)> = self
+            .chunks
+            .values()
+            .map(|chunk| (chunk.id.clone(), chunk.content.clone()))
+            .collect();
+        for (id, content) in chunks {
+            self.index_keywords(&id, &content);
+        }
+    }
+
+    fn index_keywords(&mut self, id: &str, content: &str) {
+        let tokens: Vec<AnalyzedToken> = analyze(content);
+        self.total_length += tokens.len();
+        for token in tokens {
+            self.inverted_index
+                .entry(token.text)
+                .or_default()
+                .insert(id.to_string());
+        }
+    }
+
+    fn remove_chunk(&mut self, id: &str) {
+        let chunk: StoredDocumentChunk = match self.chunks.remove(id) {
+            Some(result) => result,
+            None => return,
+        };
+
+        let tokens: Vec<AnalyzedToken> = analyze(&chunk.content);
+        self.total_length -= tokens.len();
+        for token in tokens {
+            if let Some(ids) = self.inverted_index.get_mut(&token.text) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.inverted_index.remove(&token.text);
+                }
+            }
+        }
+    }
+
+    /// Insert the vectorized chunks, or overwrite the chunks with the same ids
+    fn upsert_document_chunks(&mut self, chunks: Vec<DocumentChunk>) {
+        for chunk in chunks {
+            self.remove_chunk(&chunk.id);
+            self.index_keywords(&chunk.id, &chunk.content);
+            self.chunks.insert(chunk.id.clone(), chunk.into());
+        }
+    }
+
+    fn delete_documents(&mut self, document_metadata_ids: &[String]) {
+        let ids: Vec<String> = self
+            .chunks
+            .values()
+            .filter(|chunk| document_metadata_ids.contains(&chunk.document_metadata_id))
+            .map(|chunk| chunk.id.clone())
+            .collect();
+
+        for id in ids {
+            self.remove_chunk(&id);
+        }
+    }
+
+    /// Same as the Qdrant filter: chunks of any of the given documents, or no chunks if none is given
+    fn is_in_documents(chunk: &StoredDocumentChunk, document_metadata_ids: &HashSet<&str>) -> bool {
+        document_metadata_ids.contains(chunk.document_metadata_id.as_str())
+    }
+
+    /// Nearest chunks by cosine similarity, within the given documents
+    fn search_by_vector(
+        &self,
+        vector: &[f32],
+        document_metadata_ids: &[String],
+        top_n: usize,
+    ) -> Vec<DocumentChunkSearchResult> {
+        let document_metadata_ids: HashSet<&str> =
+            document_metadata_ids.iter().map(|id| id.as_str()).collect();
+
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .chunks
+            .values()
+            .filter(|chunk| Self::is_in_documents(chunk, &document_metadata_ids))
+            .map(|chunk| DocumentChunkSearchResult {
+                document_chunk: chunk.into(),
+                score: cosine_similarity(vector, &chunk.dense_text_vector),
+                ..Default::default()
+            })
+            .collect();
+
+        sort_and_truncate(&mut results, top_n);
+        results
+    }
+
+    /// Chunks matching the keyword query, ranked by BM25
+    fn search_by_keywords(
+        &self,
+        query: &str,
+        document_metadata_ids: &[String],
+        top_n: usize,
+    ) -> Vec<DocumentChunkSearchResult> {
+        let keyword_query = KeywordQuery::parse(query);
+        let (candidates, statistics) =
+            self.get_keyword_candidates(&keyword_query, document_metadata_ids);
+
+        rank_chunks(&keyword_query, candidates, &statistics, top_n)
+    }
+
+    fn count_keyword_hits(&self, query: &str, document_metadata_ids: &[String]) -> usize {
+        // Nothing to count in
+        if document_metadata_ids.is_empty() {
+            return 0;
+        }
+
+        let keyword_query = KeywordQuery::parse(query);
+        let (candidates, _) = self.get_keyword_candidates(&keyword_query, document_metadata_ids);
+
+        candidates
+            .iter()
+            .filter(|chunk| keyword_query.matches(&analyze(&chunk.content)))
+            .count()
+    }
+
+    /// Chunks of the documents having any of the words, with the statistics to rank them
+    fn get_keyword_candidates(
+        &self,
+        keyword_query: &KeywordQuery,
+        document_metadata_ids: &[String],
+    ) -> (Vec<DocumentChunk>, CorpusStatistics) {
+        let document_metadata_ids: HashSet<&str> =
+            document_metadata_ids.iter().map(|id| id.as_str()).collect();
+        let tokens: Vec<String> = keyword_query.get_positive_tokens();
+
+        let mut statistics = CorpusStatistics {
+            chunks_count: self.chunks.len(),
+            average_length: self.total_length as f32 / self.chunks.len().max(1) as f32,
+            ..Default::default()
+        };
+        let mut candidate_ids: HashSet<&String> = HashSet::new();
+        for token in tokens {
+            if let Some(ids) = self.inverted_index.get(&token) {
+                statistics.document_frequencies.insert(token, ids.len());
+                candidate_ids.extend(ids);
+            }
+        }
+
+        let candidates: Vec<DocumentChunk> = candidate_ids
+            .into_iter()
+            .filter_map(|id| self.chunks.get(id))
+            .filter(|chunk| Self::is_in_documents(chunk, &document_metadata_ids))
+            .map(|chunk| chunk.into())
+            .collect();
+
+        (candidates, statistics)
+    }
+}
+
+pub struct EmbeddedDatabase {
+    storage: Arc<RwLock<EmbeddedStorage>>,
+    /// Bounds the vectorization requests of document chunks across all tasks.
+    /// Queries are not bounded, to keep searches responsive during large imports.
+    embedding_permits: Arc<Semaphore>,
+    embedding_cache: Arc<EmbeddingCache>,
+    /// Chunks are saved in batches, rather than every time they are written
+    has_unsaved_chunks: AtomicBool,
+}
+
+#[async_trait]
+impl VectorDatabase for EmbeddedDatabase {
+    async fn add_document_chunks_to_database(
+        &self,
+        embedder_config: &EmbedderConfig,
+        _database_config: &DatabaseConfig,
+        chunks: Vec<DocumentChunk>,
+    ) -> Result<VectorizationStatistics> {
+        let (chunks, statistics) = vectorize_document_chunks(
+            embedder_config,
+            &self.embedding_permits,
+            &self.embedding_cache,
+            chunks,
+        )
+        .await?;
+
+        self.storage.write().await.upsert_document_chunks(chunks);
+        self.has_unsaved_chunks.store(true, Ordering::Release);
+
+        Ok(statistics)
+    }
+
+    async fn reindex_documents(&self, configuration: &Config) -> Result<()> {
+        let document_chunks: Vec<DocumentChunk> = self
+            .storage
+            .read()
+            .await
+            .chunks
+            .values()
+            .map(|chunk| chunk.into())
+            .collect();
+
+        // Vectorize before overwriting the old vectors, so a failure leaves the storage untouched
+        let (document_chunks, _) = vectorize_document_chunks(
+            &configuration.embedder,
+            &self.embedding_permits,
+            &self.embedding_cache,
+            document_chunks,
+        )
+        .await?;
+
+        {
+            // Chunks written in the meantime are already vectorized with the new model,
+            // and the deleted ones must not come back
+            let mut storage = self.storage.write().await;
+            let document_chunks: Vec<DocumentChunk> = document_chunks
+                .into_iter()
+                .filter(|chunk| {
+                    storage
+                        .chunks
+                        .get(&chunk.id)
+                        .is_some_and(|stored| stored.content == chunk.content)
+                })
+                .collect();
+            storage.dimensions = configuration.embedder.dimensions;
+            storage.upsert_document_chunks(document_chunks);
+        }
+        self.has_unsaved_chunks.store(true, Ordering::Release);
+
+        self.save_pending_changes().await
+    }
+
+    async fn delete_documents_from_database(
+        &self,
+        _database_config: &DatabaseConfig,
+        document_ids: &Vec<String>,
+    ) -> Result<()> {
+        self.storage.write().await.delete_documents(document_ids);
+        self.has_unsaved_chunks.store(true, Ordering::Release);
+
+        Ok(())
+    }
+
+    async fn save_pending_changes(&self) -> Result<()> {
+        // Writes wait for the read lock, so nothing is written between saving and clearing the flag.
+        // Searches go on while the file is written.
+        let storage = self.storage.read().await;
+        if !self.has_unsaved_chunks.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        storage.save().await?;
+        self.has_unsaved_chunks.store(false, Ordering::Release);
+
+        Ok(())
+    }
+
+    async fn get_document_chunks(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<DocumentChunk>> {
+        let storage = self.storage.read().await;
+
+        Ok(document_chunks_ids
+            .iter()
+            .filter_map(|id| storage.chunks.get(id))
+            .map(|chunk| chunk.into())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl SemanticSearch for EmbeddedDatabase {
+    async fn search_documents_semantically(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+        top_n: usize,
+        embedder_config: &EmbedderConfig,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .storage
+            .read()
+            .await
+            .search_by_vector(&vector, &document_metadata_ids, top_n);
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl KeywordSearch for EmbeddedDatabase {
+    async fn search_documents(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .storage
+            .read()
+            .await
+            .search_by_keywords(query, &document_metadata_ids, top_n);
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+
+    async fn count_keyword_hits(
+        &self,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+    ) -> Result<usize> {
+        Ok(self
+            .storage
+            .read()
+            .await
+            .count_keyword_hits(query, &document_metadata_ids))
+    }
+}
+
+#[async_trait]
+impl HybridSearch for EmbeddedDatabase {
+    async fn search_documents_hybrid(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        options: HybridSearchOptions<'_>,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let HybridSearchOptions {
+            document_metadata_ids,
+            filter: _,
+            query,
+            top_n,
+            embedder_config,
+            configuration,
+        } = options;
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let limit: usize = top_n.saturating_mul(HYBRID_SEARCH_CANDIDATES_FACTOR);
+        let storage = self.storage.read().await;
+        let mut dense_results: Vec<DocumentChunkSearchResult> =
+            storage.search_by_vector(&vector, &document_metadata_ids, limit);
+        let mut sparse_results: Vec<DocumentChunkSearchResult> =
+            storage.search_by_keywords(query, &document_metadata_ids, limit);
+        drop(storage);
+        for result in dense_results.iter_mut().chain(sparse_results.iter_mut()) {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(fuse_search_results(
+            vec![
+                (dense_results, configuration.dense_weight),
+                (sparse_results, configuration.sparse_weight),
+            ],
+            top_n,
+        ))
+    }
+}
+
+#[async_trait]
+impl RelatedDocumentsSearch for EmbeddedDatabase {
+    async fn get_document_chunk_vectors(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>> {
+        let storage = self.storage.read().await;
+
+        Ok(document_chunks_ids
+            .iter()
+            .filter_map(|id| storage.chunks.get(id))
+            .map(|chunk| chunk.dense_text_vector.clone())
+            .collect())
+    }
+
+    async fn search_documents_by_vector(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        vector: &[f32],
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .storage
+            .read()
+            .await
+            .search_by_vector(vector, &document_metadata_ids, top_n);
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+impl EmbeddedDatabase {
+    /// The `base_url` of the database configuration is the directory to keep the data,
+    /// and the `index` names the file in it.
+    pub async fn new(configuration: &Config, embedding_cache: Arc<EmbeddingCache>) -> Result<Self> {
+        let index: &str = &configuration.database.index;
+        if index.is_empty()
+            || !index.chars().all(|character| {
+                character.is_alphanumeric() || character == '_' || character == '-'
+            })
+        {
+            return Err(anyhow!(
+                "`{}` is not a valid index name. Use letters, digits, underscores and hyphens only",
+                index
+            ));
+        }
+
+        let path: PathBuf =
+            PathBuf::from(&configuration.database.base_url).join(format!("{}.json", index));
+        let mut storage: EmbeddedStorage = EmbeddedStorage::load(&path.to_string_lossy())?;
+        storage.rebuild_inverted_index();
+
+        if storage.chunks.is_empty() {
+            storage.dimensions = configuration.embedder.dimensions;
+        } else if storage.dimensions != configuration.embedder.dimensions {
+            log::warn!(
+                "Index uses {} dimensional vector, but config uses {}. Mismatched",
+                storage.dimensions,
+                configuration.embedder.dimensions
+            );
+        }
+        info!(
+            "Loaded {} document chunks from `{}`",
+            storage.chunks.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            storage: Arc::new(RwLock::new(storage)),
+            embedding_permits: Arc::new(Semaphore::new(
+                configuration.embedder.max_concurrent_requests,
+            )),
+            embedding_cache,
+            has_unsaved_chunks: AtomicBool::new(false),
+        })
+    }
+}
+
+fn cosine_similarity(left: &[f32], right: &[f32]) -> f32 {
+    if left.len() != right.len() {
+        return 0.0;
+    }
+
+    let mut dot_product: f32 = 0.0;
+    let mut left_norm: f32 = 0.0;
+    let mut right_norm: f32 = 0.0;
+    for (left, right) in left.iter().zip(right.iter()) {
+        dot_product += left * right;
+        left_norm += left * left;
+        right_norm += right * right;
+    }
+
+    if left_norm == 0.0 || right_norm == 0.0 {
+        return 0.0;
+    }
+
+    dot_product / (left_norm.sqrt() * right_norm.sqrt())
+}
+
+/// Highest scores first. Ties are broken by the chunk ids, so that the order is stable.
+fn sort_and_truncate(results: &mut Vec<DocumentChunkSearchResult>, top_n: usize) {
+    results.sort_by(|left, right| {
+        right
+            .score
+            .total_cmp(&left.score)
+            .then_with(|| left.document_chunk.id.cmp(&right.document_chunk.id))
+    });
+    results.truncate(top_n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utilities::TemporaryFile;
+
+    fn create_chunk(content: &str, document_metadata_id: &str, vector: Vec<f32>) -> DocumentChunk {
+        let mut chunk = DocumentChunk::new(content.to_string(), document_metadata_id, "c1");
+        chunk.dense_text_vector = vector;
+        chunk
+    }
+
+    #[test]
+    fn test_search_and_delete() {
+        let mut storage = EmbeddedStorage::new("unused.json");
+        let rust_chunk = create_chunk("Rust is fast", "d1", vec![1.0, 0.0, 0.0]);
+        let tea_chunk = create_chunk("Tea is hot. 我们喜欢喝茶", "d2", vec![0.0, 1.0, 0.0]);
+        storage.upsert_document_chunks(vec![rust_chunk.clone(), tea_chunk.clone()]);
+        let documents: Vec<String> = vec!["d1".to_string(), "d2".to_string()];
+
+        // No documents in the scope match no chunks
+        assert!(storage.search_by_vector(&[0.9, 0.1, 0.0], &[], 2).is_empty());
+        assert_eq!(storage.count_keyword_hits("rust", &[]), 0);
+
+        let results = storage.search_by_vector(&[0.9, 0.1, 0.0], &documents, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document_chunk.id, rust_chunk.id);
+
+        let results = storage.search_by_vector(&[0.9, 0.1, 0.0], &["d2".to_string()], 2);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_chunk.id, tea_chunk.id);
+
+        let results = storage.search_by_keywords("HOT coffee", &documents, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_chunk.id, tea_chunk.id);
+        assert_eq!(results[0].highlights.len(), 1);
+
+        let results = storage.search_by_keywords("喝茶", &documents, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(storage.count_keyword_hits("HOT coffee", &documents), 1);
+        assert_eq!(storage.count_keyword_hits("rust NOT tea", &documents), 1);
+
+        // Overwriting a chunk drops its old keywords
+        let mut updated_chunk = rust_chunk.clone();
+        updated_chunk.content = "Rust is safe".to_string();
+        storage.upsert_document_chunks(vec![updated_chunk]);
+        assert!(storage.search_by_keywords("fast", &documents, 10).is_empty());
+        assert_eq!(storage.search_by_keywords("safe", &documents, 10).len(), 1);
+
+        storage.delete_documents(&["d1".to_string()]);
+        assert_eq!(storage.chunks.len(), 1);
+        assert!(storage.search_by_keywords("rust", &documents, 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_persist_and_reload() {
+        let path = TemporaryFile::new("embedded_database");
+        let mut storage = EmbeddedStorage::new(path.to_str().unwrap());
+        storage.dimensions = 3;
+        storage.upsert_document_chunks(vec![create_chunk(
+            "Rust is fast",
+            "d1",
+            vec![1.0, 0.0, 0.0],
+        )]);
+        storage.save().await.unwrap();
+
+        let documents: Vec<String> = vec!["d1".to_string()];
+        let mut reloaded = EmbeddedStorage::load(path.to_str().unwrap()).unwrap();
+        reloaded.rebuild_inverted_index();
+        assert_eq!(reloaded.dimensions, 3);
+        assert_eq!(reloaded.search_by_keywords("rust", &documents, 10).len(), 1);
+        assert_eq!(
+            reloaded.search_by_vector(&[1.0, 0.0, 0.0], &documents, 1)[0].score,
+            1.0
+        );
+    }
+}
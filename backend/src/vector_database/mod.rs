@@ -1,3 +1,5 @@
+pub mod embedded;
+pub mod pgvector;
 pub mod qdrant;
 pub mod traits;
 pub mod shared;
\ No newline at end of file
@@ -0,0 +1,853 @@
+//! Store the document chunks in PostgreSQL with the pgvector extension.
+//! Each chunk is a row of the table named after the configured index.
+//! Dense vectors are searched with cosine distance, and keywords are searched with tsvector.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use log::info;
+use sqlx::{PgConnection, Row, postgres::PgPool, postgres::PgRow};
+use tokio::sync::{MutexGuard, Semaphore};
+
+use crate::{
+    configurations::system::{Config, DatabaseConfig, EmbedderConfig},
+    constants::{
+        HYBRID_SEARCH_CANDIDATES_FACTOR, KEYWORD_SEARCH_CANDIDATES_LIMIT, REINDEX_PAGE_SIZE,
+    },
+    documents::{
+        document_chunk::{DocumentChunk, DocumentChunkSearchResult},
+        traits::{GetIndexableFields, IndexableField},
+    },
+    embedder::VectorizationStatistics,
+    embedding_cache::EmbeddingCache,
+    metadata_storage::MetadataStorage,
+    search::{
+        SearchFilter,
+        hybrid::{HybridSearch, HybridSearchOptions, fuse_search_results},
+        keyword::KeywordSearch,
+        keyword_query::{CorpusStatistics, KeywordQuery, Term, analyze, rank_chunks},
+        related::RelatedDocumentsSearch,
+        semantic::SemanticSearch,
+    },
+    vector_database::{
+        shared::{fill_in_titles, vectorize_document_chunks, vectorize_query},
+        traits::VectorDatabase,
+    },
+};
+
+/// Text search configuration of the tsvector column.
+/// `simple` does not stem the words, so it works the same for every language.
+/// It does not cut Chinese into words though, so the column is filled from the analyzed words.
+const TEXT_SEARCH_CONFIGURATION: &str = "simple";
+
+/// Same as the Qdrant filter: chunks of any of the given documents, or no chunks if none is given
+const DOCUMENT_METADATA_IDS_CONDITION: &str = "document_metadata_id = ANY($2::text[])";
+
+#[derive(Clone)]
+pub struct PgvectorDatabase {
+    index: String,
+    pool: PgPool,
+    /// Bounds the vectorization requests of document chunks across all tasks.
+    /// Queries are not bounded, to keep searches responsive during large imports.
+    embedding_permits: Arc<Semaphore>,
+    embedding_cache: Arc<EmbeddingCache>,
+}
+
+#[async_trait]
+impl VectorDatabase for PgvectorDatabase {
+    async fn add_document_chunks_to_database(
+        &self,
+        embedder_config: &EmbedderConfig,
+        _database_config: &DatabaseConfig,
+        chunks: Vec<DocumentChunk>,
+    ) -> Result<VectorizationStatistics> {
+        let (chunks, statistics) = vectorize_document_chunks(
+            embedder_config,
+            &self.embedding_permits,
+            &self.embedding_cache,
+            chunks,
+        )
+        .await?;
+
+        self.upsert_document_chunks(&mut *self.pool.acquire().await?, chunks)
+            .await?;
+
+        Ok(statistics)
+    }
+
+    async fn reindex_documents(&self, configuration: &Config) -> Result<()> {
+        // The table is swapped in one transaction, so a failure rolls it back untouched.
+        // Writes wait for the swap instead of going to the table being dropped,
+        // while searches go on until the drop.
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query(&format!("LOCK TABLE {} IN SHARE MODE", self.index))
+            .execute(&mut *transaction)
+            .await?;
+
+        let document_chunks: Vec<DocumentChunk> = sqlx::query(&format!(
+            "SELECT id, document_metadata_id, collection_metadata_id, content FROM {}",
+            self.index
+        ))
+        .fetch_all(&mut *transaction)
+        .await?
+        .iter()
+        .map(build_document_chunk)
+        .collect::<Result<Vec<DocumentChunk>>>()?;
+
+        let (document_chunks, _) = vectorize_document_chunks(
+            &configuration.embedder,
+            &self.embedding_permits,
+            &self.embedding_cache,
+            document_chunks,
+        )
+        .await?;
+
+        sqlx::query(&format!("DROP TABLE {}", self.index))
+            .execute(&mut *transaction)
+            .await?;
+        create_table(
+            &mut transaction,
+            &self.index,
+            configuration.embedder.dimensions,
+        )
+        .await?;
+        self.upsert_document_chunks(&mut transaction, document_chunks)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_documents_from_database(
+        &self,
+        _database_config: &DatabaseConfig,
+        document_ids: &Vec<String>,
+    ) -> Result<()> {
+        match sqlx::query(&format!(
+            "DELETE FROM {} WHERE document_metadata_id = ANY($1)",
+            self.index
+        ))
+        .bind(document_ids)
+        .execute(&self.pool)
+        .await
+        {
+            Ok(_) => {}
+            Err(error) => log::error!(
+                "Postgres cannot delete documents {:?} due to {}",
+                document_ids,
+                error
+            ),
+        }
+
+        Ok(())
+    }
+
+    async fn get_document_chunks(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<DocumentChunk>> {
+        sqlx::query(&format!(
+            "SELECT id, document_metadata_id, collection_metadata_id, content FROM {} WHERE id = ANY($1)",
+            self.index
+        ))
+        .bind(document_chunks_ids)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(build_document_chunk)
+        .collect()
+    }
+}
+
+#[async_trait]
+impl SemanticSearch for PgvectorDatabase {
+    async fn search_documents_semantically(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+        top_n: usize,
+        embedder_config: &EmbedderConfig,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .search_by_vector(&vector, document_metadata_ids, top_n)
+            .await?;
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl KeywordSearch for PgvectorDatabase {
+    async fn search_documents(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .search_by_keywords(query, document_metadata_ids, top_n)
+            .await?;
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+
+    async fn count_keyword_hits(
+        &self,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+        query: &str,
+    ) -> Result<usize> {
+        // Nothing to count in
+        if document_metadata_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let tsquery: String = match build_tsquery(&KeywordQuery::parse(query)) {
+            Some(result) => result,
+            None => return Ok(0),
+        };
+
+        let row: PgRow = sqlx::query(&format!(
+            "SELECT count(*) AS hits FROM {} \
+            WHERE content_tsvector @@ to_tsquery('{}', $1) AND {}",
+            self.index, TEXT_SEARCH_CONFIGURATION, DOCUMENT_METADATA_IDS_CONDITION,
+        ))
+        .bind(tsquery)
+        .bind(document_metadata_ids)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.try_get::<i64, _>("hits")? as usize)
+    }
+}
+
+#[async_trait]
+impl HybridSearch for PgvectorDatabase {
+    async fn search_documents_hybrid(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        options: HybridSearchOptions<'_>,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let HybridSearchOptions {
+            document_metadata_ids,
+            filter: _,
+            query,
+            top_n,
+            embedder_config,
+            configuration,
+        } = options;
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let limit: usize = top_n.saturating_mul(HYBRID_SEARCH_CANDIDATES_FACTOR);
+        let (mut dense_results, mut sparse_results) = tokio::try_join!(
+            self.search_by_vector(&vector, document_metadata_ids.clone(), limit),
+            self.search_by_keywords(query, document_metadata_ids, limit),
+        )?;
+        for result in dense_results.iter_mut().chain(sparse_results.iter_mut()) {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(fuse_search_results(
+            vec![
+                (dense_results, configuration.dense_weight),
+                (sparse_results, configuration.sparse_weight),
+            ],
+            top_n,
+        ))
+    }
+}
+
+#[async_trait]
+impl RelatedDocumentsSearch for PgvectorDatabase {
+    async fn get_document_chunk_vectors(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>> {
+        sqlx::query(&format!(
+            "SELECT dense_text_vector::real[] AS dense_text_vector FROM {} WHERE id = ANY($1)",
+            self.index
+        ))
+        .bind(document_chunks_ids)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| Ok(row.try_get::<Vec<f32>, _>("dense_text_vector")?))
+        .collect()
+    }
+
+    async fn search_documents_by_vector(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        vector: &[f32],
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let mut results: Vec<DocumentChunkSearchResult> = self
+            .search_by_vector(vector, document_metadata_ids, top_n)
+            .await?;
+        for result in results.iter_mut() {
+            fill_in_titles(
+                result,
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+impl PgvectorDatabase {
+    pub async fn new(configuration: &Config, embedding_cache: Arc<EmbeddingCache>) -> Result<Self> {
+        validate_table_name(&configuration.database.index)?;
+
+        let pool: PgPool = PgPool::connect(&configuration.database.base_url).await?;
+        sqlx::query("CREATE EXTENSION IF NOT EXISTS vector")
+            .execute(&pool)
+            .await?;
+
+        match get_table_dimensions(&pool, &configuration.database.index).await? {
+            Some(dimensions) => {
+                info!(
+                    "Table `{}` has already existed. Skip creation",
+                    configuration.database.index
+                );
+
+                migrate_content_tsvector(&pool, &configuration.database.index).await?;
+
+                if dimensions != configuration.embedder.dimensions as i32 {
+                    log::warn!(
+                        "Table uses {} dimensional vector, but config uses {}. Mismatched",
+                        dimensions,
+                        configuration.embedder.dimensions
+                    );
+                }
+            }
+            None => {
+                create_table(
+                    &mut *pool.acquire().await?,
+                    &configuration.database.index,
+                    configuration.embedder.dimensions,
+                )
+                .await?
+            }
+        }
+
+        Ok(Self {
+            index: configuration.database.index.clone(),
+            pool,
+            embedding_permits: Arc::new(Semaphore::new(
+                configuration.embedder.max_concurrent_requests,
+            )),
+            embedding_cache,
+        })
+    }
+
+    /// Insert the vectorized chunks, or overwrite the chunks with the same ids
+    async fn upsert_document_chunks(
+        &self,
+        connection: &mut PgConnection,
+        chunks: Vec<DocumentChunk>,
+    ) -> Result<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let mut ids: Vec<String> = Vec::new();
+        let mut document_metadata_ids: Vec<String> = Vec::new();
+        let mut collection_metadata_ids: Vec<String> = Vec::new();
+        let mut contents: Vec<String> = Vec::new();
+        let mut vectors: Vec<String> = Vec::new();
+        let mut indexed_texts: Vec<String> = Vec::new();
+        for chunk in chunks {
+            vectors.push(to_vector_literal(&chunk.dense_text_vector));
+            indexed_texts.push(to_indexed_text(&chunk.content));
+            ids.push(chunk.id);
+            document_metadata_ids.push(chunk.document_metadata_id);
+            collection_metadata_ids.push(chunk.collection_metadata_id);
+            contents.push(chunk.content);
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, document_metadata_id, collection_metadata_id, content, dense_text_vector, content_tsvector) \
+            SELECT id, document_metadata_id, collection_metadata_id, content, dense_text_vector::vector, \
+            to_tsvector('{}', indexed_text) \
+            FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[]) \
+            AS chunks(id, document_metadata_id, collection_metadata_id, content, dense_text_vector, indexed_text) \
+            ON CONFLICT (id) DO UPDATE SET \
+            document_metadata_id = EXCLUDED.document_metadata_id, \
+            collection_metadata_id = EXCLUDED.collection_metadata_id, \
+            content = EXCLUDED.content, \
+            dense_text_vector = EXCLUDED.dense_text_vector, \
+            content_tsvector = EXCLUDED.content_tsvector",
+            self.index, TEXT_SEARCH_CONFIGURATION
+        ))
+        .bind(ids)
+        .bind(document_metadata_ids)
+        .bind(collection_metadata_ids)
+        .bind(contents)
+        .bind(vectors)
+        .bind(indexed_texts)
+        .execute(connection)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Nearest chunks by cosine similarity, within the given documents
+    async fn search_by_vector(
+        &self,
+        vector: &[f32],
+        document_metadata_ids: Vec<String>,
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        sqlx::query(&format!(
+            "SELECT id, document_metadata_id, collection_metadata_id, content, \
+            (1 - (dense_text_vector <=> $1::vector))::real AS score \
+            FROM {} WHERE {} \
+            ORDER BY dense_text_vector <=> $1::vector LIMIT $3",
+            self.index, DOCUMENT_METADATA_IDS_CONDITION
+        ))
+        .bind(to_vector_literal(vector))
+        .bind(document_metadata_ids)
+        .bind(top_n as i64)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(build_search_result)
+        .collect()
+    }
+
+    /// Chunks matching the keyword query, ranked by BM25.
+    /// Postgres finds the matching chunks, and the ranking is done here,
+    /// because `ts_rank` does not take the rarity of the words into account.
+    async fn search_by_keywords(
+        &self,
+        query: &str,
+        document_metadata_ids: Vec<String>,
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let keyword_query = KeywordQuery::parse(query);
+        let tsquery: String = match build_tsquery(&keyword_query) {
+            Some(result) => result,
+            None => return Ok(Vec::new()),
+        };
+
+        let chunks: Vec<DocumentChunk> = sqlx::query(&format!(
+            "SELECT id, document_metadata_id, collection_metadata_id, content \
+            FROM {} WHERE content_tsvector @@ to_tsquery('{}', $1) AND {} \
+            ORDER BY ts_rank(content_tsvector, to_tsquery('{}', $1)) DESC LIMIT $3",
+            self.index,
+            TEXT_SEARCH_CONFIGURATION,
+            DOCUMENT_METADATA_IDS_CONDITION,
+            TEXT_SEARCH_CONFIGURATION,
+        ))
+        .bind(tsquery)
+        .bind(document_metadata_ids)
+        .bind(KEYWORD_SEARCH_CANDIDATES_LIMIT as i64)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(build_document_chunk)
+        .collect::<Result<Vec<DocumentChunk>>>()?;
+
+        let statistics: CorpusStatistics = self
+            .get_corpus_statistics(&keyword_query.get_positive_tokens())
+            .await?;
+
+        Ok(rank_chunks(&keyword_query, chunks, &statistics, top_n))
+    }
+
+    async fn get_corpus_statistics(&self, tokens: &[String]) -> Result<CorpusStatistics> {
+        let row: PgRow = sqlx::query(&format!(
+            "SELECT count(*) AS chunks_count, \
+            coalesce(avg(length(content_tsvector)), 0)::real AS average_length FROM {}",
+            self.index
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+
+        let document_frequencies: HashMap<String, usize> = sqlx::query(&format!(
+            "SELECT token, (SELECT count(*) FROM {} \
+            WHERE content_tsvector @@ to_tsquery('{}', token)) AS document_frequency \
+            FROM UNNEST($1::text[]) AS token",
+            self.index, TEXT_SEARCH_CONFIGURATION
+        ))
+        .bind(tokens)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok((
+                row.try_get::<String, _>("token")?,
+                row.try_get::<i64, _>("document_frequency")? as usize,
+            ))
+        })
+        .collect::<Result<HashMap<String, usize>>>()?;
+
+        Ok(CorpusStatistics {
+            chunks_count: row.try_get::<i64, _>("chunks_count")? as usize,
+            average_length: row.try_get("average_length")?,
+            document_frequencies,
+        })
+    }
+}
+
+/// The table name is put into the statements as is, so only plain identifiers are accepted
+fn validate_table_name(name: &str) -> Result<()> {
+    let mut characters = name.chars();
+    let is_valid: bool = match characters.next() {
+        Some(first) => {
+            (first.is_ascii_alphabetic() || first == '_')
+                && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+        }
+        None => false,
+    };
+
+    if !is_valid || name.len() > 63 {
+        return Err(anyhow!(
+            "`{}` is not a valid table name. Use letters, digits and underscores only",
+            name
+        ));
+    }
+
+    Ok(())
+}
+
+/// Dimensions of the vector column, or `None` if the table does not exist
+async fn get_table_dimensions(pool: &PgPool, table: &str) -> Result<Option<i32>> {
+    let row: Option<PgRow> = sqlx::query(
+        "SELECT atttypmod FROM pg_attribute \
+        WHERE attrelid = to_regclass($1) AND attname = 'dense_text_vector'",
+    )
+    .bind(table)
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(row.try_get::<i32, _>("atttypmod")?)),
+        None => Ok(None),
+    }
+}
+
+async fn create_table(connection: &mut PgConnection, table: &str, dimensions: usize) -> Result<()> {
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+        id TEXT PRIMARY KEY, \
+        document_metadata_id TEXT NOT NULL, \
+        collection_metadata_id TEXT NOT NULL, \
+        content TEXT NOT NULL, \
+        dense_text_vector vector({}) NOT NULL, \
+        content_tsvector tsvector NOT NULL)",
+        table, dimensions
+    ))
+    .execute(&mut *connection)
+    .await?;
+    info!("Created a new table `{}` to record document chunks", table);
+
+    sqlx::query(&format!(
+        "CREATE INDEX IF NOT EXISTS {}_dense_text_vector_index ON {} \
+        USING hnsw (dense_text_vector vector_cosine_ops)",
+        table, table
+    ))
+    .execute(&mut *connection)
+    .await?;
+
+    // Create index for these fields that are potentially be filters.
+    for field in DocumentChunk::get_indexable_fields() {
+        let statement: String = match field {
+            // The primary key is indexed already
+            IndexableField::Keyword(field) if field == "id" => continue,
+            IndexableField::Keyword(field) | IndexableField::Integer(field) => format!(
+                "CREATE INDEX IF NOT EXISTS {}_{}_index ON {} ({})",
+                table, field, table, field
+            ),
+            IndexableField::FullText(field) => format!(
+                "CREATE INDEX IF NOT EXISTS {}_{}_tsvector_index ON {} USING gin ({}_tsvector)",
+                table, field, table, field
+            ),
+        };
+
+        sqlx::query(&statement).execute(&mut *connection).await?;
+    }
+
+    Ok(())
+}
+
+/// Tables created by the older versions generate the tsvector from the content as it is.
+/// Turn it into a plain column, and fill it from the analyzed words.
+async fn migrate_content_tsvector(pool: &PgPool, table: &str) -> Result<()> {
+    let is_generated: bool = match sqlx::query(
+        "SELECT attgenerated = 's' AS is_generated FROM pg_attribute \
+        WHERE attrelid = to_regclass($1) AND attname = 'content_tsvector'",
+    )
+    .bind(table)
+    .fetch_optional(pool)
+    .await?
+    {
+        Some(row) => row.try_get("is_generated")?,
+        None => false,
+    };
+    if !is_generated {
+        return Ok(());
+    }
+
+    info!("Re-indexing the keywords of table `{}`", table);
+    let mut transaction = pool.begin().await?;
+    sqlx::query(&format!(
+        "ALTER TABLE {} ALTER COLUMN content_tsvector DROP EXPRESSION",
+        table
+    ))
+    .execute(&mut *transaction)
+    .await?;
+
+    let rows: Vec<(String, String)> = sqlx::query(&format!("SELECT id, content FROM {}", table))
+        .fetch_all(&mut *transaction)
+        .await?
+        .iter()
+        .map(|row| Ok((row.try_get("id")?, row.try_get("content")?)))
+        .collect::<Result<Vec<(String, String)>>>()?;
+    for batch in rows.chunks(REINDEX_PAGE_SIZE as usize) {
+        let ids: Vec<String> = batch.iter().map(|(id, _)| id.clone()).collect();
+        let indexed_texts: Vec<String> = batch
+            .iter()
+            .map(|(_, content)| to_indexed_text(content))
+            .collect();
+        sqlx::query(&format!(
+            "UPDATE {} SET content_tsvector = to_tsvector('{}', chunks.indexed_text) \
+            FROM UNNEST($1::text[], $2::text[]) AS chunks(id, indexed_text) \
+            WHERE {}.id = chunks.id",
+            table, TEXT_SEARCH_CONFIGURATION, table
+        ))
+        .bind(ids)
+        .bind(indexed_texts)
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// The analyzed words of the content separated by spaces, for the tsvector column.
+/// The queries are made of the same words, so they match the column.
+fn to_indexed_text(content: &str) -> String {
+    analyze(content)
+        .into_iter()
+        .map(|token| token.text)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// pgvector accepts vectors in the text form of `[1,2,3]`
+fn to_vector_literal(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// Turn the keyword query into a tsquery.
+/// The words only have letters and digits, so they can't break the tsquery syntax.
+fn build_tsquery(keyword_query: &KeywordQuery) -> Option<String> {
+    if keyword_query.is_empty() {
+        return None;
+    }
+
+    let build_term = |term: &Term| format!("({})", term.tokens.join(" <-> "));
+    let clauses: Vec<String> = keyword_query
+        .clauses
+        .iter()
+        .map(|clause| {
+            let terms: Vec<String> = clause.iter().map(build_term).collect();
+            format!("({})", terms.join(" & "))
+        })
+        .collect();
+
+    let mut tsquery: String = format!("({})", clauses.join(" | "));
+    for term in keyword_query.excluded.iter() {
+        tsquery.push_str(&format!(" & !{}", build_term(term)));
+    }
+
+    Some(tsquery)
+}
+
+fn build_document_chunk(row: &PgRow) -> Result<DocumentChunk> {
+    Ok(DocumentChunk {
+        id: row.try_get("id")?,
+        document_metadata_id: row.try_get("document_metadata_id")?,
+        collection_metadata_id: row.try_get("collection_metadata_id")?,
+        content: row.try_get("content")?,
+        ..Default::default()
+    })
+}
+
+fn build_search_result(row: &PgRow) -> Result<DocumentChunkSearchResult> {
+    Ok(DocumentChunkSearchResult {
+        document_chunk: build_document_chunk(row)?,
+        score: row.try_get("score")?,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_tsquery() {
+        assert_eq!(
+            build_tsquery(&KeywordQuery::parse("\"Vector Database\" AND rust tokio -python")),
+            Some("(((vector <-> database) & (rust)) | ((tokio))) & !(python)".to_string())
+        );
+        assert_eq!(build_tsquery(&KeywordQuery::parse(" !&| ")), None);
+    }
+
+    #[test]
+    fn test_to_indexed_text() {
+        assert_eq!(to_indexed_text("Tea is HOT."), "tea is hot");
+        // Chinese is cut into words, which `simple` can't do by itself
+        assert!(
+            to_indexed_text("我们喜欢喝茶")
+                .split(' ')
+                .any(|word| word == "喝茶")
+        );
+    }
+
+    #[test]
+    fn test_validate_table_name() {
+        assert!(validate_table_name("notes").is_ok());
+        assert!(validate_table_name("_notes_2").is_ok());
+        assert!(validate_table_name("").is_err());
+        assert!(validate_table_name("2notes").is_err());
+        assert!(validate_table_name("notes; DROP TABLE users").is_err());
+    }
+
+    /// Runs against a real database when `PGVECTOR_TEST_URL` is set, e.g. a local
+    /// `pgvector/pgvector` container. Skipped otherwise.
+    #[tokio::test]
+    async fn test_search_against_postgres() {
+        let url: String = match std::env::var("PGVECTOR_TEST_URL") {
+            Ok(result) => result,
+            Err(_) => return,
+        };
+
+        let index: String = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let pool: PgPool = PgPool::connect(&url).await.unwrap();
+        sqlx::query("CREATE EXTENSION IF NOT EXISTS vector")
+            .execute(&pool)
+            .await
+            .unwrap();
+        create_table(&mut pool.acquire().await.unwrap(), &index, 3)
+            .await
+            .unwrap();
+        assert_eq!(get_table_dimensions(&pool, &index).await.unwrap(), Some(3));
+
+        let database = PgvectorDatabase {
+            index: index.clone(),
+            pool: pool.clone(),
+            embedding_permits: Arc::new(Semaphore::new(1)),
+            embedding_cache: Arc::new(EmbeddingCache::new(
+                &crate::configurations::system::EmbeddingCacheConfig {
+                    max_chunk_entries: 0,
+                    ..Default::default()
+                },
+            )),
+        };
+
+        let mut rust_chunk = DocumentChunk::new("Rust is fast".to_string(), "d1", "c1");
+        rust_chunk.dense_text_vector = vec![1.0, 0.0, 0.0];
+        let mut tea_chunk = DocumentChunk::new("Tea is hot. 我们喜欢喝茶".to_string(), "d2", "c1");
+        tea_chunk.dense_text_vector = vec![0.0, 1.0, 0.0];
+        database
+            .upsert_document_chunks(
+                &mut pool.acquire().await.unwrap(),
+                vec![rust_chunk.clone(), tea_chunk.clone()],
+            )
+            .await
+            .unwrap();
+
+        let documents: Vec<String> = vec!["d1".to_string(), "d2".to_string()];
+        // No documents in the scope match no chunks
+        let results = database
+            .search_by_vector(&[0.9, 0.1, 0.0], Vec::new(), 2)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+
+        let results = database
+            .search_by_vector(&[0.9, 0.1, 0.0], documents.clone(), 2)
+            .await
+            .unwrap();
+        assert_eq!(results[0].document_chunk.id, rust_chunk.id);
+
+        let results = database
+            .search_by_vector(&[0.9, 0.1, 0.0], vec!["d2".to_string()], 2)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_chunk.id, tea_chunk.id);
+
+        let results = database
+            .search_by_keywords("hot coffee", documents, 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_chunk.id, tea_chunk.id);
+        assert_eq!(results[0].highlights.len(), 1);
+
+        let results = database
+            .search_by_keywords("喝茶", vec!["d2".to_string()], 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        database
+            .delete_documents_from_database(
+                &DatabaseConfig {
+                    kind: crate::vector_database::traits::VectorDatabaseKind::Pgvector,
+                    index: index.clone(),
+                    base_url: url.clone(),
+                    api_key: "".to_string(),
+                },
+                &vec!["d1".to_string()],
+            )
+            .await
+            .unwrap();
+        let chunks = database
+            .get_document_chunks(vec![rust_chunk.id.clone(), tea_chunk.id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+
+        sqlx::query(&format!("DROP TABLE {}", index))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
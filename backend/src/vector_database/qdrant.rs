@@ -1,45 +1,71 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
-use futures::future::join_all;
 use log::info;
 use qdrant_client::{
-    Qdrant,
+    Payload, Qdrant,
     config::QdrantConfig,
     qdrant::{
-        CollectionExistsRequest, Condition, CreateCollectionBuilder,
+        CollectionExistsRequest, Condition, CountPointsBuilder, CreateAliasBuilder, CreateCollectionBuilder,
         CreateFieldIndexCollectionBuilder, DeleteCollectionBuilder, DeletePointsBuilder, FieldType,
-        Filter, GetCollectionInfoRequest, GetPointsBuilder, PointId, PointStruct,
+        Document, Filter, GetCollectionInfoRequest, GetPointsBuilder, Modifier, PointId,
+        PointStruct, Query, QueryPointGroupsBuilder, Range, SetPayloadPointsBuilder,
         QueryPointsBuilder, RetrievedPoint, ScoredPoint, ScrollPointsBuilder, ScrollResponse,
         SearchParamsBuilder, SparseVectorParamsBuilder, SparseVectorsConfigBuilder,
-        TextIndexParamsBuilder, TokenizerType, UpsertPointsBuilder, VectorParamsBuilder,
-        VectorsConfigBuilder,
+        TextIndexParamsBuilder, TokenizerType, UpdateCollectionBuilder, UpsertPointsBuilder,
+        VectorParamsBuilder,
+        VectorsConfigBuilder, VectorsSelector, vector_output::Vector,
     },
 };
-use tokio::sync::MutexGuard;
+use sha2::{Digest, Sha256};
+use tokio::sync::{MutexGuard, RwLock, Semaphore};
 
 use crate::{
     configurations::system::{Config, DatabaseConfig, EmbedderConfig},
     constants::{
-        QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME, QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
+        HYBRID_SEARCH_CANDIDATES_FACTOR, QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
+        QDRANT_SPARSE_TEXT_VECTOR_MODEL, QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
+        REINDEX_PAGE_SIZE,
     },
     documents::{
         collection_metadata::CollectionMetadata,
         document_chunk::{DocumentChunk, DocumentChunkSearchResult},
-        document_metadata::DocumentMetadata,
+        document_metadata::{DocumentMetadata, DocumentPayload},
         traits::{GetIndexableFields, IndexableField},
     },
-    embedder::send_vectorization,
+    embedder::VectorizationStatistics,
+    embedding_cache::EmbeddingCache,
     metadata_storage::MetadataStorage,
-    search::{keyword::KeywordSearch, semantic::SemanticSearch},
-    vector_database::traits::VectorDatabase,
+    search::{
+        SearchFilter,
+        grouping::{DocumentSearchResult, group_search_results},
+        hybrid::{HybridSearch, HybridSearchOptions, fuse_search_results},
+        keyword::KeywordSearch,
+        keyword_query::{KeywordQuery, Term, analyze},
+        related::RelatedDocumentsSearch,
+        semantic::SemanticSearch,
+    },
+    vector_database::{
+        shared::{fill_in_titles, vectorize_document_chunks, vectorize_query},
+        traits::VectorDatabase,
+    },
 };
 
 #[derive(Clone)]
 pub struct QdrantDatabase {
     index: String,
     client: Qdrant,
+    /// Bounds the vectorization requests of document chunks across all tasks.
+    /// Queries are not bounded, to keep searches responsive during large imports.
+    embedding_permits: Arc<Semaphore>,
+    embedding_cache: Arc<EmbeddingCache>,
+    /// Writes share it, and re-indexation holds it exclusively,
+    /// so that nothing is written to the collection being copied
+    writes: Arc<RwLock<()>>,
 }
 
 #[async_trait]
@@ -49,111 +75,124 @@ impl VectorDatabase for QdrantDatabase {
         embedder_config: &EmbedderConfig,
         database_config: &DatabaseConfig,
         chunks: Vec<DocumentChunk>,
-    ) -> Result<()> {
-        // Vectorize the chunks
-        // - Split the chunks into batches
-        // - Vectorize batch by batch
-        // - Batch is configurable
-        let mut batches: Vec<Vec<DocumentChunk>> = Vec::new();
-        let mut batch: Vec<DocumentChunk> = Vec::new();
-        for chunk in chunks {
-            if batch.len() == embedder_config.vectorization_batch_size {
-                batches.push(batch);
-                batch = Vec::new();
-            }
-
-            batch.push(chunk);
-        }
-
-        if !batch.is_empty() {
-            batches.push(batch);
-        }
-
-        // Record the data entries
-        let mut tasks = Vec::new();
-        for batch in batches.into_iter() {
-            tasks.push(send_vectorization(
-                &embedder_config.provider,
-                &embedder_config.base_url,
-                &embedder_config.api_key,
-                &embedder_config.model,
-                &embedder_config.encoding_format,
-                batch,
-            ));
-        }
-
-        let results: Vec<std::result::Result<Vec<DocumentChunk>, anyhow::Error>> =
-            join_all(tasks).await;
-        let mut chunks: Vec<DocumentChunk> = Vec::new();
-        for result in results {
-            let result = result?;
-            chunks.extend(result);
-        }
-
-        let points: Vec<PointStruct> = chunks
-            .into_iter()
-            .map(|chunk| PointStruct::from(chunk))
-            .collect();
+    ) -> Result<VectorizationStatistics> {
+        let (chunks, statistics) = vectorize_document_chunks(
+            embedder_config,
+            &self.embedding_permits,
+            &self.embedding_cache,
+            chunks,
+        )
+        .await?;
 
-        self.client
-            .upsert_points(UpsertPointsBuilder::new(&database_config.index, points).wait(true))
+        let _writes = self.writes.read().await;
+        self.upsert_document_chunks(&database_config.index, chunks)
             .await?;
 
-        Ok(())
+        Ok(statistics)
     }
 
+    /// Copy the chunks page by page into a shadow collection with the new vectors,
+    /// then switch the alias over and drop the old collection.
+    /// The shadow collection is named after the embedder, so an interrupted re-indexation
+    /// resumes from the chunks that have not been copied yet.
+    /// Writes wait until the alias is switched, so none of them is left behind in the old collection.
     async fn reindex_documents(&self, configuration: &Config) -> Result<()> {
-        let counts: u64 = match self
+        let _writes = self.writes.write().await;
+        let index: &str = &configuration.database.index;
+        let source: String = match resolve_collection_name(&self.client, index).await? {
+            Some(result) => result,
+            None => return Err(anyhow!("Collection `{}` does not exist. Re-indexation failed", index)),
+        };
+        let shadow: String = get_collection_name_for_embedder(
+            &configuration.database.index,
+            &configuration.embedder.model,
+            configuration.embedder.dimensions,
+            configuration.embedder.get_document_prefix(),
+        );
+        if source == shadow {
+            info!("Collection `{}` is up to date. Skip re-indexation", shadow);
+            return Ok(());
+        }
+
+        if self
             .client
-            .collection_info(GetCollectionInfoRequest {
-                collection_name: configuration.database.index.to_string(),
+            .collection_exists(CollectionExistsRequest {
+                collection_name: shadow.clone(),
             })
             .await?
-            .result
         {
-            Some(result) => result.points_count(),
-            None => return Err(anyhow!("Cannot get points count. Re-indexation failed")),
-        };
-
-        // For now, this is only a simple implementation.
-        // TODO: Should consider dealing with larger collection.
-        if counts > u32::MAX as u64 {
-            return Err(anyhow!(
-                "Number of document chunks had exceeded the re-indexation limit {}",
-                u32::MAX
-            ));
+            info!("Resume re-indexation into collection `{}`", shadow);
+        } else {
+            create_collection(&self.client, configuration, &shadow).await?;
         }
 
-        let retrieved_points = self
-            .client
-            .scroll(
-                ScrollPointsBuilder::new(configuration.database.index.clone())
-                    .with_payload(true)
-                    .limit(counts as u32)
-                    .build(),
-            )
-            .await?
-            .result;
+        let mut source_ids: HashSet<String> = HashSet::new();
+        let mut offset: Option<PointId> = None;
+        let mut processed: usize = 0;
+        loop {
+            let mut builder = ScrollPointsBuilder::new(&source)
+                .with_payload(true)
+                .limit(REINDEX_PAGE_SIZE);
+            if let Some(offset) = offset {
+                builder = builder.offset(offset);
+            }
+            let response: ScrollResponse = self.client.scroll(builder).await?;
 
-        self.client
-            .delete_collection(DeleteCollectionBuilder::new(
-                configuration.database.index.clone(),
-            ))
-            .await?;
+            // Skip the chunks that were copied before an interruption, unless they have changed since
+            let ids: Vec<PointId> = response
+                .result
+                .iter()
+                .filter_map(|point| point.id.clone())
+                .collect();
+            let copied: Vec<DocumentChunk> = self
+                .client
+                .get_points(GetPointsBuilder::new(&shadow, ids).with_payload(true))
+                .await?
+                .result
+                .into_iter()
+                .map(|point| point.into())
+                .collect();
 
-        create_collection(&self.client, configuration).await?;
+            processed += response.result.len();
+            let document_chunks: Vec<DocumentChunk> = response
+                .result
+                .into_iter()
+                .map(|point| point.into())
+                .filter(|chunk: &DocumentChunk| {
+                    !copied
+                        .iter()
+                        .any(|copied| copied.id == chunk.id && copied.content == chunk.content)
+                })
+                .collect();
+            source_ids.extend(
+                copied
+                    .into_iter()
+                    .map(|chunk| chunk.id)
+                    .chain(document_chunks.iter().map(|chunk| chunk.id.clone())),
+            );
+            if !document_chunks.is_empty() {
+                let (document_chunks, _) = vectorize_document_chunks(
+                    &configuration.embedder,
+                    &self.embedding_permits,
+                    &self.embedding_cache,
+                    document_chunks,
+                )
+                .await?;
+                self.upsert_document_chunks(&shadow, document_chunks)
+                    .await?;
+            }
+            info!("Re-indexed {} document chunks", processed);
 
-        let document_chunks: Vec<DocumentChunk> = retrieved_points
-            .into_iter()
-            .map(|item| item.into())
-            .collect();
+            offset = response.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
 
-        self.add_document_chunks_to_database(
-            &configuration.embedder,
-            &configuration.database,
-            document_chunks,
-        )
-        .await?;
+        // Chunks deleted from the source after an interruption must not come back
+        self.delete_chunks_not_in(&shadow, &source_ids).await?;
+
+        switch_alias(&self.client, index, &source, &shadow).await?;
 
         Ok(())
     }
@@ -168,6 +207,7 @@ impl VectorDatabase for QdrantDatabase {
             conditions.push(Condition::matches("document_metadata_id", id.to_owned()));
         }
 
+        let _writes = self.writes.read().await;
         match self
             .client
             .delete_points(
@@ -188,6 +228,42 @@ impl VectorDatabase for QdrantDatabase {
         Ok(())
     }
 
+    async fn update_document_payloads(
+        &self,
+        database_config: &DatabaseConfig,
+        payloads: Vec<DocumentPayload>,
+    ) -> Result<()> {
+        let _writes = self.writes.read().await;
+        for payload in payloads {
+            let document_metadata_id: String = payload.document_metadata_id.clone();
+            self.client
+                .set_payload(
+                    SetPayloadPointsBuilder::new(
+                        &database_config.index,
+                        Payload::try_from(serde_json::to_value(payload)?)?,
+                    )
+                    .points_selector(Filter::must([Condition::matches(
+                        "document_metadata_id",
+                        document_metadata_id,
+                    )]))
+                    .wait(true),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// The filter is turned into the conditions on the payloads instead
+    fn narrow_document_ids(
+        &self,
+        _metadata_storage: &MetadataStorage,
+        document_metadata_ids: Vec<String>,
+        _filter: &SearchFilter,
+    ) -> Vec<String> {
+        document_metadata_ids
+    }
+
     async fn get_document_chunks(
         &self,
         document_chunks_ids: Vec<String>,
@@ -227,26 +303,12 @@ impl SemanticSearch for QdrantDatabase {
         &self,
         metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
         document_metadata_ids: Vec<String>,
+        search_filter: &SearchFilter,
         query: &str,
         top_n: usize,
-        provider: &str,
-        base_url: &str,
-        api_key: &str,
-        model: &str,
-        encoding_format: &str,
+        embedder_config: &EmbedderConfig,
     ) -> Result<Vec<DocumentChunkSearchResult>> {
-        // Convert to vec
-        let chunks: Vec<DocumentChunk> = send_vectorization(
-            provider,
-            base_url,
-            api_key,
-            model,
-            encoding_format,
-            vec![DocumentChunk::new(query.to_owned(), "", "")],
-        )
-        .await?;
-
-        let conditions: Vec<Condition> = build_conditions(document_metadata_ids);
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
 
         let response = self
             .client
@@ -254,9 +316,9 @@ impl SemanticSearch for QdrantDatabase {
                 QueryPointsBuilder::new(&self.index)
                     .using("dense_text_vector")
                     .with_payload(true)
-                    .query(chunks[0].dense_text_vector.to_owned())
+                    .query(vector)
                     .limit(top_n as u64)
-                    .filter(Filter::any(conditions))
+                    .filter(build_search_filter(document_metadata_ids, search_filter))
                     .params(SearchParamsBuilder::default().hnsw_ef(128).exact(false)),
             )
             .await?;
@@ -270,6 +332,117 @@ impl SemanticSearch for QdrantDatabase {
 
         Ok(results)
     }
+
+    /// Qdrant groups the chunks by their documents itself
+    async fn search_documents_semantically_grouped(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        search_filter: &SearchFilter,
+        query: &str,
+        documents: usize,
+        chunks_per_document: usize,
+        embedder_config: &EmbedderConfig,
+    ) -> Result<Vec<DocumentSearchResult>> {
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let response = self
+            .client
+            .query_groups(
+                QueryPointGroupsBuilder::new(&self.index, "document_metadata_id")
+                    .using(QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME)
+                    .with_payload(true)
+                    .query(vector)
+                    .limit(documents as u64)
+                    .group_size(chunks_per_document as u64)
+                    .filter(build_search_filter(document_metadata_ids, search_filter))
+                    .params(SearchParamsBuilder::default().hnsw_ef(128).exact(false)),
+            )
+            .await?;
+
+        // Hits come ranked within the ranked groups, so grouping them again keeps the order
+        let hits: Vec<ScoredPoint> = response
+            .result
+            .map(|result| result.groups)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|group| group.hits)
+            .collect();
+        let results: Vec<DocumentChunkSearchResult> = build_search_results(
+            Some(hits),
+            None,
+            &metadata_storage.collections,
+            &metadata_storage.documents,
+        );
+
+        Ok(group_search_results(results, chunks_per_document))
+    }
+}
+
+#[async_trait]
+impl RelatedDocumentsSearch for QdrantDatabase {
+    async fn get_document_chunk_vectors(
+        &self,
+        document_chunks_ids: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>> {
+        let response = self
+            .client
+            .get_points(
+                GetPointsBuilder::new(
+                    &self.index,
+                    document_chunks_ids
+                        .into_iter()
+                        .map(|chunk| chunk.into())
+                        .collect::<Vec<PointId>>(),
+                )
+                .with_vectors(VectorsSelector {
+                    names: vec![QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME.to_string()],
+                }),
+            )
+            .await?;
+
+        Ok(response
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                match point
+                    .vectors?
+                    .get_vector_by_name(QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME)?
+                {
+                    Vector::Dense(vector) => Some(vector.data),
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+
+    async fn search_documents_by_vector(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        document_metadata_ids: Vec<String>,
+        vector: &[f32],
+        top_n: usize,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let response = self
+            .client
+            .query(
+                QueryPointsBuilder::new(&self.index)
+                    .using(QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME)
+                    .with_payload(true)
+                    .query(vector.to_owned())
+                    .limit(top_n as u64)
+                    .filter(build_search_filter(document_metadata_ids, &SearchFilter::default()))
+                    .params(SearchParamsBuilder::default().hnsw_ef(128).exact(false)),
+            )
+            .await?;
+
+        Ok(build_search_results(
+            Some(response.result),
+            None,
+            &metadata_storage.collections,
+            &metadata_storage.documents,
+        ))
+    }
 }
 
 #[async_trait]
@@ -278,57 +451,231 @@ impl KeywordSearch for QdrantDatabase {
         &self,
         metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
         document_metadata_ids: Vec<String>,
+        search_filter: &SearchFilter,
         query: &str,
         top_n: usize,
     ) -> Result<Vec<DocumentChunkSearchResult>> {
-        let conditions: Vec<Condition> = build_conditions(document_metadata_ids);
+        let keyword_query = KeywordQuery::parse(query);
+        if keyword_query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let filter: Filter =
+            build_keyword_filter(&keyword_query, document_metadata_ids, search_filter);
 
-        let response: ScrollResponse = self
+        // Qdrant ranks the matching chunks by their BM25 sparse vectors
+        let response = self
             .client
-            .scroll(
-                ScrollPointsBuilder::new(&self.index)
-                    .filter(Filter {
-                        should: conditions,
-                        must: vec![Condition::matches_text_any("content", query)],
+            .query(
+                QueryPointsBuilder::new(&self.index)
+                    .using(QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME)
+                    .with_payload(true)
+                    .query(Query::new_nearest(Document {
+                        text: keyword_query.get_positive_tokens().join(" "),
+                        model: QDRANT_SPARSE_TEXT_VECTOR_MODEL.into(),
                         ..Default::default()
-                    })
-                    .limit(top_n as u32)
-                    .build(),
+                    }))
+                    .limit(top_n as u64)
+                    .filter(filter.clone()),
             )
             .await?;
+        let mut results: Vec<DocumentChunkSearchResult> = build_search_results(
+            Some(response.result),
+            None,
+            &metadata_storage.collections,
+            &metadata_storage.documents,
+        );
 
-        let results: Vec<DocumentChunkSearchResult> = build_search_results(
+        // The BM25 model may drop words that the text index matches, like stop words.
+        // Such chunks still match, so they come after the ranked ones.
+        if results.len() < top_n {
+            let response: ScrollResponse = self
+                .client
+                .scroll(
+                    ScrollPointsBuilder::new(&self.index)
+                        .filter(filter)
+                        .limit(top_n as u32)
+                        .build(),
+                )
+                .await?;
+            for result in build_search_results(
+                None,
+                Some(response.result),
+                &metadata_storage.collections,
+                &metadata_storage.documents,
+            ) {
+                if results.len() == top_n {
+                    break;
+                }
+                if results
+                    .iter()
+                    .all(|item| item.document_chunk.id != result.document_chunk.id)
+                {
+                    results.push(result);
+                }
+            }
+        }
+
+        for result in results.iter_mut() {
+            result.highlights = keyword_query.highlight(&analyze(&result.document_chunk.content));
+        }
+
+        Ok(results)
+    }
+
+    async fn count_keyword_hits(
+        &self,
+        document_metadata_ids: Vec<String>,
+        search_filter: &SearchFilter,
+        query: &str,
+    ) -> Result<usize> {
+        let keyword_query = KeywordQuery::parse(query);
+        // Nothing to look for, or nowhere to look
+        if keyword_query.is_empty() || document_metadata_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let response = self
+            .client
+            .count(
+                CountPointsBuilder::new(&self.index)
+                    .filter(build_keyword_filter(
+                        &keyword_query,
+                        document_metadata_ids,
+                        search_filter,
+                    ))
+                    .exact(false),
+            )
+            .await?;
+
+        Ok(response.result.map_or(0, |result| result.count as usize))
+    }
+}
+
+#[async_trait]
+impl HybridSearch for QdrantDatabase {
+    async fn search_documents_hybrid(
+        &self,
+        metadata_storage: &mut MutexGuard<'_, MetadataStorage>,
+        options: HybridSearchOptions<'_>,
+    ) -> Result<Vec<DocumentChunkSearchResult>> {
+        let HybridSearchOptions {
+            document_metadata_ids,
+            filter: search_filter,
+            query,
+            top_n,
+            embedder_config,
+            configuration,
+        } = options;
+        let vector: Vec<f32> = vectorize_query(&self.embedding_cache, embedder_config, query).await?;
+
+        let limit: u64 = top_n.saturating_mul(HYBRID_SEARCH_CANDIDATES_FACTOR) as u64;
+        let filter: Filter = build_search_filter(document_metadata_ids, search_filter);
+        let (dense_response, sparse_response) = tokio::try_join!(
+            self.client.query(
+                QueryPointsBuilder::new(&self.index)
+                    .using(QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME)
+                    .with_payload(true)
+                    .query(vector)
+                    .limit(limit)
+                    .filter(filter.clone())
+                    .params(SearchParamsBuilder::default().hnsw_ef(128).exact(false)),
+            ),
+            self.client.query(
+                QueryPointsBuilder::new(&self.index)
+                    .using(QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME)
+                    .with_payload(true)
+                    .query(Query::new_nearest(Document {
+                        text: query.to_owned(),
+                        model: QDRANT_SPARSE_TEXT_VECTOR_MODEL.into(),
+                        ..Default::default()
+                    }))
+                    .limit(limit)
+                    .filter(filter),
+            ),
+        )?;
+
+        let dense_results: Vec<DocumentChunkSearchResult> = build_search_results(
+            Some(dense_response.result),
             None,
-            Some(response.result),
             &metadata_storage.collections,
             &metadata_storage.documents,
         );
+        let sparse_results: Vec<DocumentChunkSearchResult> = build_search_results(
+            Some(sparse_response.result),
+            None,
+            &metadata_storage.collections,
+            &metadata_storage.documents,
+        );
+
+        let mut results: Vec<DocumentChunkSearchResult> = fuse_search_results(
+            vec![
+                (dense_results, configuration.dense_weight),
+                (sparse_results, configuration.sparse_weight),
+            ],
+            top_n,
+        );
+        let keyword_query = KeywordQuery::parse(query);
+        for result in results.iter_mut() {
+            result.highlights = keyword_query.highlight(&analyze(&result.document_chunk.content));
+        }
 
         Ok(results)
     }
 }
 
 impl QdrantDatabase {
-    pub async fn new(configuration: &Config) -> Result<Self> {
+    pub async fn new(configuration: &Config, embedding_cache: Arc<EmbeddingCache>) -> Result<Self> {
         let qdrant_config: QdrantConfig = QdrantConfig::from_url(&configuration.database.base_url)
             // Timeout for preventing Qdrant killing time-consuming operations
             .timeout(std::time::Duration::from_secs(1000));
         let client: Qdrant = Qdrant::new(qdrant_config)?;
+        let index: &str = &configuration.database.index;
 
-        if client
-            .collection_exists(CollectionExistsRequest {
-                collection_name: configuration.database.index.to_string(),
-            })
-            .await?
-        {
-            info!("Collection `note` has already existed. Skip creation");
+        if let Some(collection_name) = resolve_collection_name(&client, index).await? {
+            info!("Collection `{}` has already existed. Skip creation", index);
+            // Collections created by the older versions don't have the indexes of the document payloads
+            create_field_indexes(&client, &collection_name, DocumentPayload::get_indexable_fields())
+                .await?;
+            // Nor do they have the inverse document frequencies for the keyword searches
+            client
+                .update_collection(
+                    UpdateCollectionBuilder::new(&collection_name)
+                        .sparse_vectors_config(build_sparse_vectors_config()),
+                )
+                .await?;
             return Ok(Self {
                 index: configuration.database.index.clone(),
                 client,
+                embedding_permits: Arc::new(Semaphore::new(
+                    configuration.embedder.max_concurrent_requests,
+                )),
+                embedding_cache: embedding_cache.clone(),
+                writes: Arc::new(RwLock::new(())),
             });
         }
 
-        create_collection(&client, configuration).await?;
+        // The collection is reached through an alias, so that re-indexation can swap it.
+        // If the collection for the embedder exists, a re-indexation was interrupted
+        // right before the switch, so it only needs to be pointed to.
+        let collection_name: String = get_collection_name_for_embedder(
+            &configuration.database.index,
+            &configuration.embedder.model,
+            configuration.embedder.dimensions,
+            configuration.embedder.get_document_prefix(),
+        );
+        if client
+            .collection_exists(CollectionExistsRequest {
+                collection_name: collection_name.clone(),
+            })
+            .await?
+        {
+            info!("Finish switching to collection `{}`", collection_name);
+        } else {
+            create_collection(&client, configuration, &collection_name).await?;
+        }
+        client
+            .create_alias(CreateAliasBuilder::new(&collection_name, index))
+            .await?;
 
         match validate_configuration(&client, configuration).await {
             Ok(_) => {}
@@ -345,8 +692,139 @@ impl QdrantDatabase {
         Ok(Self {
             index: configuration.database.index.clone(),
             client,
+            embedding_permits: Arc::new(Semaphore::new(
+                configuration.embedder.max_concurrent_requests,
+            )),
+            embedding_cache,
+            writes: Arc::new(RwLock::new(())),
+        })
+    }
+
+    /// Insert the vectorized chunks into the collection, or overwrite the chunks with the same ids
+    async fn upsert_document_chunks(
+        &self,
+        collection: &str,
+        chunks: Vec<DocumentChunk>,
+    ) -> Result<()> {
+        let points: Vec<PointStruct> = chunks
+            .into_iter()
+            .map(|chunk| PointStruct::from(chunk))
+            .collect();
+
+        self.client
+            .upsert_points(UpsertPointsBuilder::new(collection, points).wait(true))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete the chunks of the collection whose ids are not among the given ones
+    async fn delete_chunks_not_in(&self, collection: &str, ids: &HashSet<String>) -> Result<()> {
+        let mut offset: Option<PointId> = None;
+        loop {
+            let mut builder = ScrollPointsBuilder::new(collection)
+                .with_payload(true)
+                .limit(REINDEX_PAGE_SIZE);
+            if let Some(offset) = offset {
+                builder = builder.offset(offset);
+            }
+            let response: ScrollResponse = self.client.scroll(builder).await?;
+
+            let stale: Vec<PointId> = response
+                .result
+                .into_iter()
+                .filter_map(|point| {
+                    let id: Option<PointId> = point.id.clone();
+                    let chunk: DocumentChunk = point.into();
+                    if ids.contains(&chunk.id) { None } else { id }
+                })
+                .collect();
+            if !stale.is_empty() {
+                self.client
+                    .delete_points(
+                        DeletePointsBuilder::new(collection)
+                            .points(stale)
+                            .wait(true),
+                    )
+                    .await?;
+            }
+
+            offset = response.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Name of the collection that the alias points to, or the collection itself if it is not an alias.
+/// Returns `None` if neither exists.
+async fn resolve_collection_name(client: &Qdrant, index: &str) -> Result<Option<String>> {
+    if let Some(alias) = client
+        .list_aliases()
+        .await?
+        .aliases
+        .into_iter()
+        .find(|alias| alias.alias_name == index)
+    {
+        return Ok(Some(alias.collection_name));
+    }
+
+    if client
+        .collection_exists(CollectionExistsRequest {
+            collection_name: index.to_string(),
         })
+        .await?
+    {
+        return Ok(Some(index.to_string()));
     }
+
+    Ok(None)
+}
+
+/// Each embedder model, dimensions and document prefix get their own collection behind the alias
+fn get_collection_name_for_embedder(
+    index: &str,
+    model: &str,
+    dimensions: usize,
+    document_prefix: &str,
+) -> String {
+    // Collections without a document prefix keep the names from before the prefixes were introduced
+    let key: String = if document_prefix.is_empty() {
+        format!("{}:{}", model, dimensions)
+    } else {
+        format!("{}:{}:{}", model, dimensions, document_prefix)
+    };
+    let digest: String = format!("{:x}", Sha256::digest(key));
+
+    format!("{}_{}", index, &digest[..12])
+}
+
+/// Point the alias to the new collection, then drop the old one
+async fn switch_alias(client: &Qdrant, index: &str, source: &str, target: &str) -> Result<()> {
+    if source == index {
+        // Collections created before the aliases were introduced take the name of the alias.
+        // It has to be removed first. If it is interrupted here, the alias is created on the next start.
+        client
+            .delete_collection(DeleteCollectionBuilder::new(source))
+            .await?;
+        client
+            .create_alias(CreateAliasBuilder::new(target, index))
+            .await?;
+    } else {
+        // Creating an existing alias reassigns it in one step
+        client
+            .create_alias(CreateAliasBuilder::new(target, index))
+            .await?;
+        client
+            .delete_collection(DeleteCollectionBuilder::new(source))
+            .await?;
+    }
+    info!("Collection `{}` now points to `{}`", index, target);
+
+    Ok(())
 }
 
 async fn validate_configuration(qdrant_client: &Qdrant, configuration: &Config) -> Result<()> {
@@ -390,7 +868,22 @@ async fn validate_configuration(qdrant_client: &Qdrant, configuration: &Config)
     Ok(())
 }
 
-async fn create_collection(client: &Qdrant, configuration: &Config) -> Result<()> {
+fn build_sparse_vectors_config() -> SparseVectorsConfigBuilder {
+    let mut sparse_vector_config = SparseVectorsConfigBuilder::default();
+    sparse_vector_config.add_named_vector_params(
+        QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
+        // BM25 needs the inverse document frequencies, which Qdrant keeps up to date
+        SparseVectorParamsBuilder::default().modifier(Modifier::Idf),
+    );
+
+    sparse_vector_config
+}
+
+async fn create_collection(
+    client: &Qdrant,
+    configuration: &Config,
+    collection_name: &str,
+) -> Result<()> {
     let mut dense_text_vector_config = VectorsConfigBuilder::default();
     dense_text_vector_config.add_named_vector_params(
         QDRANT_DENSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
@@ -400,22 +893,19 @@ async fn create_collection(client: &Qdrant, configuration: &Config) -> Result<()
         ),
     );
 
-    let mut sparse_vector_config = SparseVectorsConfigBuilder::default();
-    sparse_vector_config.add_named_vector_params(
-        QDRANT_SPARSE_TEXT_VECTOR_NAMED_PARAMS_NAME,
-        SparseVectorParamsBuilder::default(),
-    );
-
     match client
         .create_collection(
-            CreateCollectionBuilder::new(&configuration.database.index)
+            CreateCollectionBuilder::new(collection_name)
                 .vectors_config(dense_text_vector_config)
-                .sparse_vectors_config(sparse_vector_config)
+                .sparse_vectors_config(build_sparse_vectors_config())
                 .build(),
         )
         .await
     {
-        Ok(_) => info!("Created a new collection `note` to record document chunks"),
+        Ok(_) => info!(
+            "Created a new collection `{}` to record document chunks",
+            collection_name
+        ),
         Err(error) => {
             // we can't use the notebook without having a collection
             panic!("Failed to initialize collection due to: {}", error);
@@ -424,12 +914,25 @@ async fn create_collection(client: &Qdrant, configuration: &Config) -> Result<()
 
     // Create index for these fields that are potentially be filters.
     // This is to optimize the search performance when the user stores large datasets.
-    for field in DocumentChunk::get_indexable_fields() {
+    let mut fields: Vec<IndexableField> = DocumentChunk::get_indexable_fields();
+    fields.extend(DocumentPayload::get_indexable_fields());
+    create_field_indexes(client, collection_name, fields).await?;
+
+    Ok(())
+}
+
+/// Qdrant keeps an index as it is if it exists already
+async fn create_field_indexes(
+    client: &Qdrant,
+    collection_name: &str,
+    fields: Vec<IndexableField>,
+) -> Result<()> {
+    for field in fields {
         match field {
             IndexableField::Keyword(field) => {
                 client
                     .create_field_index(CreateFieldIndexCollectionBuilder::new(
-                        &configuration.database.index,
+                        collection_name,
                         field,
                         FieldType::Keyword,
                     ))
@@ -443,7 +946,7 @@ async fn create_collection(client: &Qdrant, configuration: &Config) -> Result<()
                 client
                     .create_field_index(
                         CreateFieldIndexCollectionBuilder::new(
-                            &configuration.database.index,
+                            collection_name,
                             field,
                             FieldType::Text,
                         )
@@ -451,17 +954,115 @@ async fn create_collection(client: &Qdrant, configuration: &Config) -> Result<()
                     )
                     .await?;
             }
+            IndexableField::Integer(field) => {
+                client
+                    .create_field_index(CreateFieldIndexCollectionBuilder::new(
+                        collection_name,
+                        field,
+                        FieldType::Integer,
+                    ))
+                    .await?;
+            }
         }
     }
 
     Ok(())
 }
 
-pub fn build_conditions(document_metadata_ids: Vec<String>) -> Vec<Condition> {
-    document_metadata_ids
-        .into_iter()
-        .map(|id| Condition::matches("document_metadata_id", id.to_string()))
-        .collect()
+/// Chunks of any of the given documents. No documents match no chunks,
+/// so that an empty scope never widens to the whole collection.
+fn build_document_condition(document_metadata_ids: Vec<String>) -> Condition {
+    Condition::matches("document_metadata_id", document_metadata_ids)
+}
+
+/// Match the chunks in the documents with the keyword query
+fn build_keyword_filter(
+    keyword_query: &KeywordQuery,
+    document_metadata_ids: Vec<String>,
+    search_filter: &SearchFilter,
+) -> Filter {
+    let build_condition = |term: &Term| {
+        if term.is_phrase() {
+            Condition::matches_phrase("content", term.tokens.join(" "))
+        } else {
+            Condition::matches_text("content", term.tokens[0].clone())
+        }
+    };
+
+    let clauses: Vec<Condition> = keyword_query
+        .clauses
+        .iter()
+        .map(|clause| Filter::must(clause.iter().map(build_condition)).into())
+        .collect();
+    let mut must: Vec<Condition> = vec![
+        Filter::should(clauses).into(),
+        build_document_condition(document_metadata_ids),
+    ];
+    must.extend(build_payload_conditions(search_filter));
+
+    Filter {
+        must,
+        must_not: keyword_query.excluded.iter().map(build_condition).collect(),
+        ..Default::default()
+    }
+}
+
+/// Chunks of the scoped documents that match the search filter
+fn build_search_filter(document_metadata_ids: Vec<String>, search_filter: &SearchFilter) -> Filter {
+    let mut must: Vec<Condition> = build_payload_conditions(search_filter);
+    must.push(build_document_condition(document_metadata_ids));
+
+    Filter::must(must)
+}
+
+/// Conditions on the document payloads copied onto the chunks
+fn build_payload_conditions(search_filter: &SearchFilter) -> Vec<Condition> {
+    let mut conditions: Vec<Condition> = Vec::new();
+
+    if !search_filter.collection_metadata_ids.is_empty() {
+        conditions.push(Condition::matches(
+            "collection_metadata_id",
+            search_filter.collection_metadata_ids.clone(),
+        ));
+    }
+    if !search_filter.document_metadata_ids.is_empty() {
+        conditions.push(Condition::matches(
+            "document_metadata_id",
+            search_filter.document_metadata_ids.clone(),
+        ));
+    }
+    for (field, date_range) in [
+        ("document_created_at", &search_filter.created_at),
+        ("document_last_modified", &search_filter.last_modified),
+    ] {
+        if !date_range.is_empty() {
+            conditions.push(Condition::range(
+                field,
+                Range {
+                    gte: date_range.from.map(|from| from.timestamp() as f64),
+                    lte: date_range.to.map(|to| to.timestamp() as f64),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+    if let Some(title) = &search_filter.title {
+        conditions.push(Condition::matches_text("document_title", title.clone()));
+    }
+    if !search_filter.tags.is_empty() {
+        conditions.push(Condition::matches("document_tags", search_filter.tags.clone()));
+    }
+    if !search_filter.import_types.is_empty() {
+        let import_types: Vec<String> = search_filter
+            .import_types
+            .iter()
+            .filter_map(|import_type| serde_json::to_value(import_type).ok())
+            .filter_map(|value| value.as_str().map(|value| value.to_string()))
+            .collect();
+        conditions.push(Condition::matches("document_import_type", import_types));
+    }
+
+    conditions
 }
 
 /// To fill in the document and collection title
@@ -476,18 +1077,11 @@ pub fn build_search_results(
     if let Some(points) = scored_points {
         for point in points {
             let mut result: DocumentChunkSearchResult = DocumentChunkSearchResult::from(point);
-
-            if let Some(document_metadata) =
-                document_metadatas_from_storage.get(&result.document_chunk.document_metadata_id)
-            {
-                result.document_title = Some(document_metadata.title.clone());
-            }
-
-            if let Some(collection_metadata) =
-                collection_metadatas_from_storage.get(&result.document_chunk.collection_metadata_id)
-            {
-                result.collection_title = Some(collection_metadata.title.clone());
-            }
+            fill_in_titles(
+                &mut result,
+                collection_metadatas_from_storage,
+                document_metadatas_from_storage,
+            );
 
             results.push(result);
         }
@@ -496,18 +1090,11 @@ pub fn build_search_results(
     if let Some(points) = retrieved_points {
         for point in points {
             let mut result: DocumentChunkSearchResult = DocumentChunkSearchResult::from(point);
-
-            if let Some(document_metadata) =
-                document_metadatas_from_storage.get(&result.document_chunk.document_metadata_id)
-            {
-                result.document_title = Some(document_metadata.title.clone());
-            }
-
-            if let Some(collection_metadata) =
-                collection_metadatas_from_storage.get(&result.document_chunk.collection_metadata_id)
-            {
-                result.collection_title = Some(collection_metadata.title.clone());
-            }
+            fill_in_titles(
+                &mut result,
+                collection_metadatas_from_storage,
+                document_metadatas_from_storage,
+            );
 
             results.push(result);
         }
@@ -515,3 +1102,31 @@ pub fn build_search_results(
 
     results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_name_for_embedder() {
+        let name: String = get_collection_name_for_embedder("notes", "all-MiniLM-L6-v2", 384, "");
+        assert!(name.starts_with("notes_"));
+        assert_eq!(name.len(), "notes_".len() + 12);
+        assert_eq!(
+            name,
+            get_collection_name_for_embedder("notes", "all-MiniLM-L6-v2", 384, "")
+        );
+        assert_ne!(
+            name,
+            get_collection_name_for_embedder("notes", "all-MiniLM-L6-v2", 768, "")
+        );
+        assert_ne!(
+            name,
+            get_collection_name_for_embedder("notes", "bge-small-en", 384, "")
+        );
+        assert_ne!(
+            name,
+            get_collection_name_for_embedder("notes", "all-MiniLM-L6-v2", 384, "passage: ")
+        );
+    }
+}
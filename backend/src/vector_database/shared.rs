@@ -1,18 +1,192 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
+use futures::future::join_all;
+use tokio::sync::Semaphore;
 
 use crate::{
-    configurations::system::Config,
+    configurations::system::{Config, EmbedderConfig},
+    documents::{
+        collection_metadata::CollectionMetadata,
+        document_chunk::{DocumentChunk, DocumentChunkSearchResult},
+        document_metadata::DocumentMetadata,
+    },
+    embedder::{VectorizationStatistics, send_vectorization, send_vectorization_with_retries},
+    embedding_cache::EmbeddingCache,
     vector_database::{
+        embedded::EmbeddedDatabase,
+        pgvector::PgvectorDatabase,
         qdrant::QdrantDatabase,
         traits::{VectorDatabase, VectorDatabaseKind},
     },
 };
 
 /// Dynamically create a vector database
-pub async fn create_vector_database(config: &Config) -> Result<Arc<dyn VectorDatabase>> {
+pub async fn create_vector_database(
+    config: &Config,
+    embedding_cache: Arc<EmbeddingCache>,
+) -> Result<Arc<dyn VectorDatabase>> {
     match config.database.kind {
-        VectorDatabaseKind::Qdrant => Ok(Arc::new(QdrantDatabase::new(config).await?)),
+        VectorDatabaseKind::Qdrant => {
+            Ok(Arc::new(QdrantDatabase::new(config, embedding_cache).await?))
+        }
+        VectorDatabaseKind::Pgvector => {
+            Ok(Arc::new(PgvectorDatabase::new(config, embedding_cache).await?))
+        }
+        VectorDatabaseKind::Embedded => {
+            Ok(Arc::new(EmbeddedDatabase::new(config, embedding_cache).await?))
+        }
+    }
+}
+
+/// Vectorize the chunks batch by batch. The batch size is configurable.
+/// Each request of a batch holds one of the embedding permits, but not the backoff in between.
+/// Chunks whose contents were vectorized by the same model before take their vectors from the cache.
+pub async fn vectorize_document_chunks(
+    embedder_config: &EmbedderConfig,
+    embedding_permits: &Semaphore,
+    embedding_cache: &EmbeddingCache,
+    chunks: Vec<DocumentChunk>,
+) -> Result<(Vec<DocumentChunk>, VectorizationStatistics)> {
+    let max_chunk_entries: usize = embedding_cache.config.max_chunk_entries;
+    // The vectors depend on the document prefix as well, so it is a part of the cached texts
+    let document_prefix: &str = embedder_config.get_document_prefix();
+    let cached_vectors: Vec<Option<Vec<f32>>> = if max_chunk_entries > 0 {
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|chunk| format!("{}{}", document_prefix, chunk.content))
+            .collect();
+        embedding_cache
+            .chunks
+            .lock()
+            .await
+            .get_vectors(&embedder_config.model, &texts)
+    } else {
+        vec![None; chunks.len()]
+    };
+
+    let mut cached_chunks: Vec<DocumentChunk> = Vec::new();
+    let mut batches: Vec<Vec<DocumentChunk>> = Vec::new();
+    let mut batch: Vec<DocumentChunk> = Vec::new();
+    for (mut chunk, vector) in chunks.into_iter().zip(cached_vectors) {
+        // Vectors of other dimensions are left over from an earlier configuration of the model
+        if let Some(vector) = vector
+            && vector.len() == embedder_config.dimensions
+        {
+            chunk.dense_text_vector = vector;
+            cached_chunks.push(chunk);
+            continue;
+        }
+
+        if batch.len() == embedder_config.vectorization_batch_size {
+            batches.push(batch);
+            batch = Vec::new();
+        }
+
+        batch.push(chunk);
+    }
+
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    let mut tasks = Vec::new();
+    for batch in batches.into_iter() {
+        tasks.push(send_vectorization_with_retries(
+            embedder_config,
+            embedding_permits,
+            batch,
+        ));
+    }
+
+    let results: Vec<Result<(Vec<DocumentChunk>, VectorizationStatistics)>> = join_all(tasks).await;
+    let mut chunks: Vec<DocumentChunk> = Vec::new();
+    let mut statistics = VectorizationStatistics {
+        cached: cached_chunks.len(),
+        ..Default::default()
+    };
+    for result in results {
+        let (result, batch_statistics) = result?;
+        chunks.extend(result);
+        statistics += batch_statistics;
+    }
+
+    if max_chunk_entries > 0 && !chunks.is_empty() {
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|chunk| format!("{}{}", document_prefix, chunk.content))
+            .collect();
+        let vectors: Vec<Vec<f32>> = chunks
+            .iter()
+            .map(|chunk| chunk.dense_text_vector.clone())
+            .collect();
+
+        // Saved in batches in the background
+        embedding_cache.chunks.lock().await.insert_vectors(
+            &embedder_config.model,
+            &texts,
+            &vectors,
+            max_chunk_entries,
+        );
+    }
+
+    chunks.extend(cached_chunks);
+
+    Ok((chunks, statistics))
+}
+
+/// Vectorize a search query with the query prefix of the model.
+/// Recent queries take their vectors from the cache.
+pub async fn vectorize_query(
+    embedding_cache: &EmbeddingCache,
+    embedder_config: &EmbedderConfig,
+    query: &str,
+) -> Result<Vec<f32>> {
+    let query: String = format!("{}{}", embedder_config.get_query_prefix(), query);
+    if let Some(vector) = embedding_cache
+        .queries
+        .lock()
+        .await
+        .get(&embedder_config.model, &query)
+    {
+        return Ok(vector);
+    }
+
+    let mut chunks: Vec<DocumentChunk> = send_vectorization(
+        &embedder_config.provider,
+        &embedder_config.base_url,
+        &embedder_config.api_key,
+        &embedder_config.model,
+        &embedder_config.encoding_format,
+        vec![DocumentChunk::new(query.clone(), "", "")],
+    )
+    .await?;
+    let vector: Vec<f32> = chunks.remove(0).dense_text_vector;
+
+    embedding_cache
+        .queries
+        .lock()
+        .await
+        .insert(&embedder_config.model, &query, vector.clone());
+
+    Ok(vector)
+}
+
+/// Fill in the document and collection title of a search result
+pub fn fill_in_titles(
+    result: &mut DocumentChunkSearchResult,
+    collection_metadatas_from_storage: &HashMap<String, CollectionMetadata>,
+    document_metadatas_from_storage: &HashMap<String, DocumentMetadata>,
+) {
+    if let Some(document_metadata) =
+        document_metadatas_from_storage.get(&result.document_chunk.document_metadata_id)
+    {
+        result.document_title = Some(document_metadata.title.clone());
+    }
+
+    if let Some(collection_metadata) =
+        collection_metadatas_from_storage.get(&result.document_chunk.collection_metadata_id)
+    {
+        result.collection_title = Some(collection_metadata.title.clone());
     }
 }
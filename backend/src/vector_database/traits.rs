@@ -8,26 +8,36 @@ use tokio::sync::Mutex;
 
 use crate::configurations::system::{Config, DatabaseConfig, EmbedderConfig};
 use crate::documents::document_chunk::DocumentChunk;
-use crate::documents::document_metadata::DocumentMetadata;
+use crate::documents::document_metadata::{DocumentMetadata, DocumentPayload};
+use crate::documents::operations::filter_document_ids_by_search_filter;
+use crate::embedder::VectorizationStatistics;
 use crate::metadata_storage::MetadataStorage;
+use crate::search::hybrid::HybridSearch;
+use crate::search::SearchFilter;
 use crate::search::keyword::KeywordSearch;
+use crate::search::related::RelatedDocumentsSearch;
 use crate::search::semantic::SemanticSearch;
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum VectorDatabaseKind {
     Qdrant,
+    Pgvector,
+    Embedded,
 }
 
 #[async_trait]
-pub trait VectorDatabase: Send + Sync + SemanticSearch + KeywordSearch {
-    /// Required for adding chunk data to the database
+pub trait VectorDatabase:
+    Send + Sync + SemanticSearch + KeywordSearch + HybridSearch + RelatedDocumentsSearch
+{
+    /// Required for adding chunk data to the database.
+    /// Returns how much effort it took to vectorize the chunks.
     async fn add_document_chunks_to_database(
         &self,
         embedder_config: &EmbedderConfig,
         database_config: &DatabaseConfig,
         chunks: Vec<DocumentChunk>,
-    ) -> Result<()>;
+    ) -> Result<VectorizationStatistics>;
 
     async fn add_document_chunks_to_database_and_metadata_storage(
         &self,
@@ -36,14 +46,18 @@ pub trait VectorDatabase: Send + Sync + SemanticSearch + KeywordSearch {
         chunks: Vec<DocumentChunk>,
         metadata_storage: Arc<Mutex<MetadataStorage>>,
         metadata: DocumentMetadata,
-    ) -> Result<String> {
-        self.add_document_chunks_to_database(embedder_config, database_config, chunks)
+    ) -> Result<(String, VectorizationStatistics)> {
+        let statistics: VectorizationStatistics = self
+            .add_document_chunks_to_database(embedder_config, database_config, chunks)
             .await?;
 
         let metadata_id: String = metadata.id.clone();
+        let payload: DocumentPayload = DocumentPayload::from(&metadata);
         metadata_storage.lock().await.add_document(metadata).await?;
+        self.update_document_payloads(database_config, vec![payload])
+            .await?;
 
-        Ok(metadata_id)
+        Ok((metadata_id, statistics))
     }
 
     async fn delete_documents_from_database(
@@ -59,4 +73,31 @@ pub trait VectorDatabase: Send + Sync + SemanticSearch + KeywordSearch {
 
     /// Required for reindex features
     async fn reindex_documents(&self, configuration: &Config) -> Result<()>;
+
+    /// Save the chunks written since the last save, for databases that save them in batches.
+    /// Databases that persist every write themselves have nothing to save.
+    async fn save_pending_changes(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Copy the document fields onto the chunks of the documents, so that the searches can filter by them.
+    /// Databases that don't keep them leave it as it is, and filter the documents by their metadatas instead.
+    async fn update_document_payloads(
+        &self,
+        _database_config: &DatabaseConfig,
+        _payloads: Vec<DocumentPayload>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Narrow the documents to search down with the filter, before searching.
+    /// Databases that filter the document payloads themselves keep the documents as they are.
+    fn narrow_document_ids(
+        &self,
+        metadata_storage: &MetadataStorage,
+        document_metadata_ids: Vec<String>,
+        filter: &SearchFilter,
+    ) -> Vec<String> {
+        filter_document_ids_by_search_filter(metadata_storage, document_metadata_ids, filter)
+    }
 }
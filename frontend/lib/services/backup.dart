@@ -139,11 +139,15 @@ class BackupService {
   }
 
   /// Returns task_id
-  Future<String> restoreBackup(Dio dio, String backupId) async {
+  Future<String> restoreBackup(
+    Dio dio,
+    String username,
+    String backupId,
+  ) async {
     try {
       final response = await dio.post(
         restoreBackupEndpoint,
-        data: {"backup_id": backupId},
+        data: {"username": username, "backup_id": backupId},
       );
       final genericResponse = GenericResponse.fromJson(
         response.data as Map<String, dynamic>,
@@ -92,10 +92,17 @@ class DocumentManagementService {
     return response.data!["task_id"];
   }
 
-  Future<String> deleteDocument(Dio dio, String documentMetadataId) async {
+  Future<String> deleteDocument(
+    Dio dio,
+    String username,
+    String documentMetadataId,
+  ) async {
     final response = await dio.post(
       deleteDocumentEndpoint,
-      data: {"document_metadata_id": documentMetadataId},
+      data: {
+        "username": username,
+        "document_metadata_id": documentMetadataId,
+      },
     );
     return response.data!["task_id"];
   }
@@ -37,7 +37,7 @@ class SearchService {
         "scope": {"search_scope": scope.name, "id": scopeId},
       },
     );
-    final List<dynamic> chunksJson = response.data!["data"];
+    final List<dynamic> chunksJson = response.data!["data"]["results"];
     return chunksJson.map((json) => DocumentChunkSearchResult.fromJson(json)).toList();
   }
 
@@ -56,7 +56,7 @@ class SearchService {
         "scope": {"search_scope": scope.name, "id": scopeId},
       },
     );
-    final List<dynamic> chunksJson = response.data!["data"];
+    final List<dynamic> chunksJson = response.data!["data"]["results"];
     return chunksJson.map((json) => DocumentChunkSearchResult.fromJson(json)).toList();
   }
 }
@@ -284,8 +284,13 @@ class AppState extends ChangeNotifier
   }
 
   Future<void> restoreBackup(String backupId) async {
+    if (username == null) return;
     try {
-      final taskId = await backupService.restoreBackup(dio, backupId);
+      final taskId = await backupService.restoreBackup(
+        dio,
+        username!,
+        backupId,
+      );
       addTask(taskId, "Restoring backup", pollTasks);
     } catch (e) {
       rethrow;
@@ -461,8 +466,9 @@ class AppState extends ChangeNotifier
   }
 
   Future<void> deleteDocument(String id) async {
+    if (username == null) return;
     final title = documentById[id]?.title ?? "document";
-    final taskId = await documents.deleteDocument(dio, id);
+    final taskId = await documents.deleteDocument(dio, username!, id);
     documentById.remove(id);
 
     // Remove from tree cache